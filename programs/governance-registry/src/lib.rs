@@ -1,8 +1,32 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
 
-declare_id!("govHvVVCZsdJLynaFJdqEWBU9AbJ4aHYdZsWno114V9");
+declare_id!("govfUrkiFsU122D56z1Fb1EqDADdogRTXLHxVyNqnAR");
 const CONFIG_PREFIX: &str = "registry-config";
+const ROLE_PREFIX: &str = "role";
+const DELEGATE_PREFIX: &str = "delegate";
+const ATTESTATION_PREFIX: &str = "attestation";
+const BADGES_PREFIX: &str = "badges";
+const MAX_BADGES: usize = 32;
+const MAX_BADGE_NAME_LEN: usize = 32;
+const BADGE_DEFINITIONS_SIZE: usize = 8 + 1 + 32 + 4 + MAX_BADGES * (4 + MAX_BADGE_NAME_LEN);
+const BADGE_GRANT_SIZE: usize = 1 + 1 + 33 + 9 + 9;
+// entries verified before badges existed migrate to this badge
+const LEGACY_VERIFIED_BADGE: u8 = 0;
+// entries written before badges kept `is_verified` in the version byte, so 0 and 1 are the legacy layout
+const ENTRY_VERSION: u8 = 2;
+const MAX_MULTISIG_SIGNERS: usize = 10;
+// spl-governance GovernanceAccountType discriminants for v1 and v2 accounts
+const SPL_GOVERNANCE_REALM_TYPES: [u8; 2] = [1, 16];
+const SPL_GOVERNANCE_GOVERNANCE_TYPES: [u8; 8] = [3, 4, 9, 10, 18, 19, 20, 21];
+// serialized size of every Entry field except `data`, with all options set
+// (badge grants are sized separately)
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 1 + 9 + 1 + 33 + 1 + 43 + 9 + 33 + 32 + 32 + 1 + 8 + 4 + 4 + 8;
 
 #[program]
 pub mod governance_registry {
@@ -10,51 +34,490 @@ pub mod governance_registry {
 
     pub fn init(ctx: Context<Init>, ix: InitIx) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.bump = ix.bump;
+        registry_config.name = ix.name;
         registry_config.authority = *ctx.accounts.authority.key;
         registry_config.realm_seed = ix.realm_seed;
+        registry_config.permissionless_add = ix.permissionless_add;
+        registry_config.strict_realm_validation = ix.strict_realm_validation;
+        registry_config.require_proof = ix.require_proof;
+        registry_config.authority_can_update = ix.authority_can_update;
+        registry_config.require_executable = ix.require_executable;
+
+        if let Some(governance_program_id) = ix.governance_program_id {
+            let (realm, governance) = match ctx.remaining_accounts {
+                [realm, governance, ..] => (realm, governance),
+                _ => return Err(ErrorCode::InvalidGovernanceAccount.into()),
+            };
+            assert_realm_governance(&governance_program_id, realm, governance)?;
+            registry_config.governance_program_id = Some(governance_program_id);
+            registry_config.realm = Some(*realm.key);
+            // proposals executed by the governance are the only way to act as authority
+            registry_config.authority = *governance.key;
+        }
+        registry_config.max_entries = ix.max_entries;
+        registry_config.max_entry_size = ix.max_entry_size;
+        registry_config.moderation_treasury = ix.moderation_treasury;
+        registry_config.verification_threshold = ix.verification_threshold;
+        registry_config.verification_ttl = ix.verification_ttl;
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
         registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn propose_authority(ctx: Context<ProposeAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = Some(*ctx.accounts.new_authority.key);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = None;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn set_multisig(ctx: Context<SetMultisig>, ix: SetMultisigIx) -> ProgramResult {
+        if ix.signers.len() > MAX_MULTISIG_SIGNERS || ix.threshold as usize > ix.signers.len() {
+            return Err(ErrorCode::InvalidMultisig.into());
+        }
+        for (i, signer) in ix.signers.iter().enumerate() {
+            if ix.signers[..i].contains(signer) {
+                return Err(ErrorCode::InvalidMultisig.into());
+            }
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.multisig_signers = ix.signers;
+        registry_config.multisig_threshold = ix.threshold;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn update_config(ctx: Context<UpdateConfig>, ix: UpdateConfigIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if let Some(permissionless_add) = ix.permissionless_add {
+            config_changed(registry, "permissionless_add", &registry_config.permissionless_add, &permissionless_add);
+            registry_config.permissionless_add = permissionless_add;
+        }
+        if let Some(realm_seed) = ix.realm_seed {
+            if registry_config.entry_count > 0 {
+                return Err(ErrorCode::SeedLocked.into());
+            }
+            config_changed(registry, "realm_seed", &registry_config.realm_seed.clone(), &realm_seed);
+            registry_config.realm_seed = realm_seed;
+        }
+        if let Some(strict_realm_validation) = ix.strict_realm_validation {
+            config_changed(registry, "strict_realm_validation", &registry_config.strict_realm_validation, &strict_realm_validation);
+            registry_config.strict_realm_validation = strict_realm_validation;
+        }
+        if let Some(require_proof) = ix.require_proof {
+            config_changed(registry, "require_proof", &registry_config.require_proof, &require_proof);
+            registry_config.require_proof = require_proof;
+        }
+        if let Some(require_executable) = ix.require_executable {
+            config_changed(registry, "require_executable", &registry_config.require_executable, &require_executable);
+            registry_config.require_executable = require_executable;
+        }
+        if let Some(authority_can_update) = ix.authority_can_update {
+            config_changed(registry, "authority_can_update", &registry_config.authority_can_update, &authority_can_update);
+            registry_config.authority_can_update = authority_can_update;
+        }
+        if let Some(max_entries) = ix.max_entries {
+            config_changed(registry, "max_entries", &registry_config.max_entries, &max_entries);
+            registry_config.max_entries = max_entries;
+        }
+        if let Some(max_entry_size) = ix.max_entry_size {
+            config_changed(registry, "max_entry_size", &registry_config.max_entry_size, &max_entry_size);
+            registry_config.max_entry_size = max_entry_size;
+        }
+        if let Some(moderation_treasury) = ix.moderation_treasury {
+            config_changed(
+                registry,
+                "moderation_treasury",
+                &format!("{:?}", registry_config.moderation_treasury),
+                &format!("{:?}", moderation_treasury),
+            );
+            registry_config.moderation_treasury = moderation_treasury;
+        }
+        if let Some(verification_threshold) = ix.verification_threshold {
+            config_changed(registry, "verification_threshold", &registry_config.verification_threshold, &verification_threshold);
+            registry_config.verification_threshold = verification_threshold;
+        }
+        if let Some(verification_ttl) = ix.verification_ttl {
+            config_changed(registry, "verification_ttl", &registry_config.verification_ttl, &verification_ttl);
+            registry_config.verification_ttl = verification_ttl;
+        }
+        Ok(())
+    }
+
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.admin_role, &ctx.accounts.admin, ctx.remaining_accounts))]
+    pub fn grant_role(ctx: Context<GrantRole>, ix: GrantRoleIx) -> ProgramResult {
+        let role_member = &mut ctx.accounts.role_member;
+        role_member.bump = ix.bump;
+        role_member.registry = *ctx.accounts.registry_config.to_account_info().key;
+        role_member.role = ix.role;
+        role_member.member = *ctx.accounts.member.key;
+        role_member.granted_at = Clock::get().unwrap().unix_timestamp;
         Ok(())
     }
+
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.admin_role, &ctx.accounts.admin, ctx.remaining_accounts))]
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> ProgramResult {
+        ctx.accounts.role_member.close(ctx.accounts.admin.to_account_info())
+    }
     
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn add_entry(ctx: Context<AddEntry>, ix: AddEntryIx) -> ProgramResult {
+        entry_size(&ctx.accounts.registry_config, &ix.data, 0)?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if registry_config.max_entries > 0 && registry_config.entry_count >= registry_config.max_entries as u64 {
+            return Err(ErrorCode::RegistryFull.into());
+        }
+        registry_config.entry_count += 1;
+        let delegate_generation = next_delegate_generation(registry_config);
+        let verification_round = next_verification_round(registry_config);
         let entry = &mut ctx.accounts.entry;
+        entry.bump = ix.bump;
+        entry.delegate_generation = delegate_generation;
+        entry.verification_round = verification_round;
+        entry.registry = *ctx.accounts.registry_config.to_account_info().key;
         entry.address = ix.address;
         entry.data = ix.data;
         entry.schema_version = ix.schema_version;
         entry.creator = *ctx.accounts.creator.key;
+        entry.update_authority = *ctx.accounts.creator.key;
+        entry.rent_payer = *ctx.accounts.creator.key;
         entry.created_at = Clock::get().unwrap().unix_timestamp;
-        entry.is_verified = false; // default to false
+        entry.version = ENTRY_VERSION;
+        if ctx.accounts.registry_config.strict_realm_validation {
+            entry.community_mint = Some(assert_realm_entry(&ctx.accounts.realm, &entry.address, &entry.data)?);
+        }
+        if ctx.accounts.registry_config.require_proof && ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        assert_proof(ix.proof, &entry.address, &ctx.accounts.creator, &entry.data, &ctx.accounts.realm, ctx.remaining_accounts)?;
+        entry.proof = ix.proof;
+        if ctx.accounts.registry_config.require_executable {
+            entry.deployment = Some(program_deployment(&entry.data.program_id, ctx.remaining_accounts)?);
+        }
         Ok(())
     }
 
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn update_entry(ctx: Context<UpdateEntry>, ix: UpdateEntryIx) -> ProgramResult {
+        resize_entry(
+            &ctx.accounts.entry.to_account_info(),
+            entry_size(&ctx.accounts.registry_config, &ix.data, ctx.accounts.entry.badge_grants.len())?,
+            &ctx.accounts.updater,
+            &ctx.accounts.rent_payer,
+            &ctx.accounts.system_program,
+        )?;
         let entry = &mut ctx.accounts.entry;
         entry.data = ix.data;
-        entry.is_verified = false; // default to false
+        if matches!(entry.verified_hash, Some(hash) if hash != content_hash(entry.schema_version, &entry.data)) {
+            revoke_verification(entry, &mut ctx.accounts.registry_config);
+            emit!(VerificationRevoked {
+                registry: entry.registry,
+                entry: *entry.to_account_info().key,
+                reason: RevocationReason::ContentChanged,
+            });
+        }
+        if ctx.accounts.registry_config.strict_realm_validation {
+            entry.community_mint = Some(assert_realm_entry(&ctx.accounts.realm, &entry.address, &entry.data)?);
+        }
+        if ctx.accounts.registry_config.require_executable {
+            entry.deployment = Some(program_deployment(&entry.data.program_id, ctx.remaining_accounts)?);
+        }
+        Ok(())
+    }
+
+    pub fn set_update_authority(ctx: Context<SetUpdateAuthority>) -> ProgramResult {
+        let entry = &mut ctx.accounts.entry;
+        entry.update_authority = *ctx.accounts.new_update_authority.key;
+        Ok(())
+    }
+
+    pub fn add_delegate(ctx: Context<AddDelegate>, ix: AddDelegateIx) -> ProgramResult {
+        let entry_delegate = &mut ctx.accounts.entry_delegate;
+        entry_delegate.bump = ix.bump;
+        entry_delegate.entry = *ctx.accounts.entry.to_account_info().key;
+        entry_delegate.delegate = *ctx.accounts.delegate.key;
+        entry_delegate.scope = ix.scope;
+        entry_delegate.expires_at = ix.expires_at;
+        entry_delegate.generation = ctx.accounts.entry.delegate_generation;
+        Ok(())
+    }
+
+    pub fn remove_delegate(ctx: Context<RemoveDelegate>) -> ProgramResult {
+        ctx.accounts.entry_delegate.close(ctx.accounts.update_authority.to_account_info())
+    }
+
+    pub fn claim_entry(ctx: Context<ClaimEntry>, ix: ClaimEntryIx) -> ProgramResult {
+        let entry = &mut ctx.accounts.entry;
+        if ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        assert_proof(ix.proof, &entry.address, &ctx.accounts.claimant, &entry.data, &ctx.accounts.realm, ctx.remaining_accounts)?;
+        let previous_creator = entry.creator;
+        entry.creator = *ctx.accounts.claimant.key;
+        entry.update_authority = *ctx.accounts.claimant.key;
+        // delegates appointed by the previous creator stop applying
+        entry.delegate_generation = next_delegate_generation(&mut ctx.accounts.registry_config);
+        entry.proof = ix.proof;
+        revoke_verification(entry, &mut ctx.accounts.registry_config);
+        emit!(EntryClaimed {
+            registry: entry.registry,
+            entry: *entry.to_account_info().key,
+            address: entry.address,
+            previous_creator,
+            new_creator: entry.creator,
+        });
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn init_badges(ctx: Context<InitBadges>, ix: InitBadgesIx) -> ProgramResult {
+        let badge_definitions = &mut ctx.accounts.badge_definitions;
+        badge_definitions.bump = ix.bump;
+        badge_definitions.registry = *ctx.accounts.registry_config.to_account_info().key;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn define_badge(ctx: Context<DefineBadge>, ix: DefineBadgeIx) -> ProgramResult {
+        let badge_definitions = &mut ctx.accounts.badge_definitions;
+        if badge_definitions.names.len() >= MAX_BADGES || ix.name.len() > MAX_BADGE_NAME_LEN {
+            return Err(ErrorCode::InvalidBadge.into());
+        }
+        badge_definitions.names.push(ix.name);
         Ok(())
     }
 
-    pub fn verify_entry(ctx: Context<VerifyEntry>) -> ProgramResult {
+    pub fn verify_entry(ctx: Context<VerifyEntry>, ix: VerifyEntryIx) -> ProgramResult {
+        if ix.badge as usize >= ctx.accounts.badge_definitions.names.len() {
+            return Err(ErrorCode::InvalidBadge.into());
+        }
+        let now = Clock::get().unwrap().unix_timestamp;
+        let threshold = ctx.accounts.registry_config.verification_threshold.max(1);
         let entry = &mut ctx.accounts.entry;
-        entry.is_verified = true;
-        entry.verified_at = Some(Clock::get().unwrap().unix_timestamp);
+        if !entry.badge_grants.iter().any(|grant| grant.badge == ix.badge) {
+            let size = entry_size(&ctx.accounts.registry_config, &entry.data, entry.badge_grants.len() + 1)?;
+            if size > entry.to_account_info().data_len() {
+                resize_entry(&entry.to_account_info(), size, &ctx.accounts.payer, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+            }
+            entry.badge_grants.push(BadgeGrant { badge: ix.badge, attestations: 0, granted_by: None, granted_at: None, expires_at: None });
+        }
+        let attestation = &mut ctx.accounts.attestation;
+        // an attestation left over from an earlier round or a removed entry no longer counts and is taken over,
+        // its rent stays with whoever paid for it
+        if attestation.rent_payer == Pubkey::default() {
+            attestation.rent_payer = *ctx.accounts.payer.key;
+        } else if attestation.round == entry.verification_round {
+            return Err(ErrorCode::AlreadyAttested.into());
+        }
+        attestation.bump = ix.bump;
+        attestation.entry = *entry.to_account_info().key;
+        attestation.verifier = *ctx.accounts.verifier.key;
+        attestation.round = entry.verification_round;
+        attestation.attested_at = now;
+        attestation.badge = ix.badge;
+        entry.attestation_count = entry.attestation_count.checked_add(1).ok_or(ErrorCode::TooManyAttestations)?;
+        let grant = entry.badge_grants.iter_mut().find(|grant| grant.badge == ix.badge).unwrap();
+        grant.attestations = grant.attestations.checked_add(1).ok_or(ErrorCode::TooManyAttestations)?;
+        if grant.granted_at.is_none() && grant.attestations >= threshold {
+            grant.granted_by = Some(*ctx.accounts.verifier.key);
+            grant.granted_at = Some(now);
+            grant.expires_at = verification_expiry(&ctx.accounts.registry_config, now);
+            entry.badges |= 1 << ix.badge;
+            if entry.verified_at.is_none() {
+                entry.verified_at = Some(now);
+                entry.verified_deploy_slot = checked_deployment(&ctx.accounts.program, &ctx.accounts.program_data)?.deploy_slot;
+                entry.verified_hash = Some(content_hash(entry.schema_version, &entry.data));
+            }
+        }
         Ok(())
     }
 
     pub fn unverify_entry(ctx: Context<UnverifyEntry>) -> ProgramResult {
+        let badge = ctx.accounts.attestation.badge;
+        let threshold = ctx.accounts.registry_config.verification_threshold.max(1);
+        let entry = &mut ctx.accounts.entry;
+        // attestations made before a revocation or for a removed entry were already discounted
+        if ctx.accounts.attestation.round == entry.verification_round {
+            entry.attestation_count = entry.attestation_count.checked_sub(1).ok_or(ErrorCode::InvalidAttestation)?;
+            if let Some(index) = entry.badge_grants.iter().position(|grant| grant.badge == badge) {
+                let grant = &mut entry.badge_grants[index];
+                grant.attestations = grant.attestations.checked_sub(1).ok_or(ErrorCode::InvalidAttestation)?;
+                let attestations = grant.attestations;
+                if grant.granted_at.is_some() && attestations < threshold {
+                    grant.granted_by = None;
+                    grant.granted_at = None;
+                    grant.expires_at = None;
+                    entry.badges &= !(1 << badge);
+                }
+                if attestations == 0 {
+                    entry.badge_grants.remove(index);
+                }
+            }
+            if entry.badges == 0 {
+                entry.verified_at = None;
+                entry.verified_deploy_slot = None;
+                entry.verified_hash = None;
+            }
+        }
+        ctx.accounts.attestation.close(ctx.accounts.rent_payer.to_account_info())
+    }
+
+    pub fn renew_verification(ctx: Context<RenewVerification>, ix: RenewVerificationIx) -> ProgramResult {
+        // a renewal needs a fresh threshold of verifiers, co-signers follow as (attestation, verifier, verifier_role) triples
+        let entry = ctx.accounts.entry.key();
+        let mut verifiers = vec![renewing_verifier(&entry, ix.badge, ctx.accounts.entry.verification_round, &ctx.accounts.attestation, &ctx.accounts.verifier)?];
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let verifier = match accounts {
+                [_, verifier, verifier_role] if !has_role(&ctx.accounts.registry_config, verifier_role, verifier.key, Role::Verifier) => {
+                    return Err(ErrorCode::InsufficientAuthority.into())
+                }
+                [attestation, verifier, _] => renewing_verifier(&entry, ix.badge, ctx.accounts.entry.verification_round, attestation, verifier)?,
+                _ => return Err(ErrorCode::InvalidAttestation.into()),
+            };
+            if !verifiers.contains(&verifier) {
+                verifiers.push(verifier);
+            }
+        }
+        if verifiers.len() < ctx.accounts.registry_config.verification_threshold.max(1) as usize {
+            return Err(ErrorCode::InsufficientAttestations.into());
+        }
+        let expires_at = verification_expiry(&ctx.accounts.registry_config, Clock::get().unwrap().unix_timestamp);
+        let grant = ctx.accounts.entry.badge_grants.iter_mut()
+            .find(|grant| grant.badge == ix.badge && grant.granted_at.is_some())
+            .ok_or(ErrorCode::BadgeNotGranted)?;
+        grant.expires_at = expires_at;
+        Ok(())
+    }
+
+    pub fn assert_verified(ctx: Context<AssertVerified>, ix: AssertVerifiedIx) -> ProgramResult {
+        let entry = &ctx.accounts.entry;
+        // an empty badge set accepts any badge
+        let holds = |badges: u32| if ix.badges == 0 { badges != 0 } else { badges & ix.badges == ix.badges };
+        if holds(active_badges(entry, Clock::get().unwrap().unix_timestamp)) {
+            return Ok(());
+        }
+        if holds(entry.badges) {
+            return Err(ErrorCode::VerificationExpired.into());
+        }
+        Err(ErrorCode::BadgeNotGranted.into())
+    }
+
+    // rewrites entries from before badges in place. Entries from before registry-bound seeds sit at
+    // [realm_seed, address] without a bump or registry and cannot be moved to their current address here
+    pub fn migrate_entry(ctx: Context<MigrateEntry>) -> ProgramResult {
+        let entry_info = &ctx.accounts.entry;
+        if entry_info.owner != &ID {
+            return Err(ErrorCode::InvalidRegistry.into());
+        }
+        if predates_registry_seeds(entry_info, &ctx.accounts.registry_config.realm_seed)? {
+            return Err(ErrorCode::UnsupportedEntryLayout.into());
+        }
+        let mut entry = {
+            let data = entry_info.try_borrow_data()?;
+            // entries written before badges end with a u32 round where the badge fields begin, zeroes widen
+            // the round and decode as no badges and the first delegate generation
+            let mut padded = data.to_vec();
+            padded.extend_from_slice(&[0; 20]);
+            Entry::try_deserialize(&mut padded.as_slice())?
+        };
+        if entry.version == ENTRY_VERSION {
+            return Err(ErrorCode::EntryMigrated.into());
+        }
+        if entry.registry != *ctx.accounts.registry_config.to_account_info().key {
+            return Err(ErrorCode::InvalidRegistry.into());
+        }
+        // existing attestations already sit at the legacy badge address and keep counting towards it
+        let verified = entry.version == 1;
+        if verified || entry.attestation_count > 0 {
+            let granted_at = if verified { entry.verified_at } else { None };
+            entry.badge_grants = vec![BadgeGrant {
+                badge: LEGACY_VERIFIED_BADGE,
+                attestations: entry.attestation_count,
+                granted_by: None,
+                granted_at,
+                expires_at: granted_at.and_then(|granted_at| verification_expiry(&ctx.accounts.registry_config, granted_at)),
+            }];
+        }
+        if verified {
+            entry.badges = 1 << LEGACY_VERIFIED_BADGE;
+        }
+        // rounds handed out from now on stay ahead of the round the entry counted on its own
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.verification_round = registry_config.verification_round.max(entry.verification_round);
+        entry.version = ENTRY_VERSION;
+        let size = entry_info.data_len() + 20 + entry.badge_grants.len() * BADGE_GRANT_SIZE;
+        resize_entry(entry_info, size, &ctx.accounts.payer, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+        let mut data = entry_info.try_borrow_mut_data()?;
+        entry.try_serialize(&mut data.as_mut())
+    }
+
+    pub fn check_verification(ctx: Context<CheckVerification>) -> ProgramResult {
+        let program = &ctx.accounts.program;
+        let program_data = &ctx.accounts.program_data;
         let entry = &mut ctx.accounts.entry;
-        entry.is_verified = false;
-        entry.verified_at = None;
+        let verified_deploy_slot = match entry.verified_deploy_slot {
+            Some(slot) => slot,
+            // nothing was snapshotted so there is nothing to compare against
+            _ => return Ok(()),
+        };
+        // only a program that is gone counts as closed, accounts that fail to parse are rejected
+        let reason = if program.lamports() == 0 || !program.executable {
+            RevocationReason::ProgramClosed
+        } else {
+            match programdata_address(program)? {
+                Some(address) if address != *program_data.key => return Err(ErrorCode::InvalidProgramData.into()),
+                // closing an upgradeable program empties its program data but leaves the program account
+                Some(_) if program_data.data_is_empty() => RevocationReason::ProgramClosed,
+                _ if checked_deployment(program, program_data)?.deploy_slot == Some(verified_deploy_slot) => return Ok(()),
+                _ => RevocationReason::ProgramRedeployed,
+            }
+        };
+        revoke_verification(entry, &mut ctx.accounts.registry_config);
+        emit!(VerificationRevoked {
+            registry: entry.registry,
+            entry: *entry.to_account_info().key,
+            reason,
+        });
         Ok(())
     }
 
     pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.authority.to_account_info()).unwrap();
+        let entry = &ctx.accounts.entry;
+        let authority = &ctx.accounts.authority;
+        let moderated = entry.creator != *authority.key
+            && !is_delegate(entry.to_account_info().key, entry.delegate_generation, &ctx.accounts.entry_delegate, authority.key, DelegateScope::EditAndRemove);
+        if *ctx.accounts.rent_recipient.key != rent_recipient(&ctx.accounts.registry_config, &entry.rent_payer, moderated) {
+            return Err(ErrorCode::InvalidRentRecipient.into());
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.entry_count = registry_config.entry_count.saturating_sub(1);
+        next_delegate_generation(registry_config);
+        ctx.accounts.entry.close(ctx.accounts.rent_recipient.to_account_info()).unwrap();
         Ok(())
     }
 }
@@ -64,8 +527,19 @@ pub mod governance_registry {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitIx {
     pub bump: u8,
+    pub name: String,
     pub realm_seed: String,
     pub permissionless_add: bool,
+    pub governance_program_id: Option<Pubkey>,
+    pub strict_realm_validation: bool,
+    pub require_proof: bool,
+    pub require_executable: bool,
+    pub authority_can_update: bool,
+    pub max_entries: u32,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub verification_threshold: u8,
+    pub verification_ttl: i64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -74,6 +548,7 @@ pub struct AddEntryIx {
     pub address: Pubkey,
     pub schema_version: u8,
     pub data: Realm,
+    pub proof: ProofMethod,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -81,6 +556,71 @@ pub struct UpdateEntryIx {
     pub data: Realm,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GrantRoleIx {
+    pub bump: u8,
+    pub role: Role,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AddDelegateIx {
+    pub bump: u8,
+    pub scope: DelegateScope,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VerifyEntryIx {
+    pub bump: u8,
+    pub badge: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RenewVerificationIx {
+    pub badge: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AssertVerifiedIx {
+    pub badges: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitBadgesIx {
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct DefineBadgeIx {
+    pub name: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ClaimEntryIx {
+    pub proof: ProofMethod,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetMultisigIx {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateConfigIx {
+    pub permissionless_add: Option<bool>,
+    pub realm_seed: Option<String>,
+    pub strict_realm_validation: Option<bool>,
+    pub require_proof: Option<bool>,
+    pub require_executable: Option<bool>,
+    pub authority_can_update: Option<bool>,
+    pub max_entries: Option<u32>,
+    pub max_entry_size: Option<u32>,
+    pub moderation_treasury: Option<Option<Pubkey>>,
+    pub verification_threshold: Option<u8>,
+    pub verification_ttl: Option<i64>,
+}
+
 ///////////////// Contexts /////////////////
 
 #[derive(Accounts)]
@@ -90,8 +630,8 @@ pub struct Init<'info> {
         init,
         payer = authority,
         // extra space for future upgrades
-        space = 128,
-        seeds = [CONFIG_PREFIX.as_ref()],
+        space = 1024,
+        seeds = [CONFIG_PREFIX.as_ref(), ix.name.as_ref()],
         bump = ix.bump,
     )]
     pub registry_config: Account<'info, RegistryConfig>,
@@ -101,76 +641,396 @@ pub struct Init<'info> {
 
 #[derive(Accounts)]
 pub struct TransferAuthority<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(constraint = registry_config.pending_authority == Some(*new_authority.to_account_info().key) @ ErrorCode::InvalidPendingAuthority)]
     pub new_authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMultisig<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: GrantRoleIx)]
+pub struct GrantRole<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [ROLE_PREFIX.as_ref(), registry_config.to_account_info().key.as_ref(), &[ix.role as u8], member.key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub role_member: Account<'info, RoleMember>,
+    pub member: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub admin_role: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [ROLE_PREFIX.as_ref(), registry_config.to_account_info().key.as_ref(), &[role_member.role as u8], role_member.member.as_ref()],
+        bump = role_member.bump,
+        constraint = role_member.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub role_member: Account<'info, RoleMember>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub admin_role: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(ix: AddEntryIx)]
 pub struct AddEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
     #[account(
         init,
         payer = creator,
-        // extra space for future upgrades
-        space = 1024,
-        seeds = [registry_config.realm_seed.as_ref(), ix.address.as_ref()],
+        // sized to the payload, update_entry reallocs when it changes
+        space = ENTRY_BASE_SIZE + ix.data.try_to_vec().unwrap().len(),
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), ix.address.as_ref()],
         bump = ix.bump,
     )]
     pub entry: ProgramAccount<'info, Entry>,
-    #[account(constraint = registry_config.permissionless_add || (registry_config.authority == *creator.to_account_info().key) @ ErrorCode::InsufficientAuthority)]
     pub creator: Signer<'info>,
+    pub realm: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> AddEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.permissionless_add {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.creator, remaining_accounts)
+    }
+}
+
 #[derive(Accounts)]
 #[instruction(ix: UpdateEntryIx)]
 pub struct UpdateEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, Entry>,
-    #[account(constraint =
-        registry_config.permissionless_add
-        || (registry_config.authority == *updater.to_account_info().key)
-        || (entry.creator == *updater.to_account_info().key)
-        @ ErrorCode::InsufficientAuthority)]
+    #[account(mut)]
     pub updater: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
+    pub realm: AccountInfo<'info>,
+    pub entry_delegate: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> UpdateEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.entry.update_authority == *self.updater.key
+            || is_delegate(self.entry.to_account_info().key, self.entry.delegate_generation, &self.entry_delegate, self.updater.key, DelegateScope::Edit)
+        {
+            return Ok(());
+        }
+        if !self.registry_config.authority_can_update {
+            return Err(ErrorCode::InsufficientAuthority.into());
+        }
+        authorized(&self.registry_config, &self.updater, remaining_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetUpdateAuthority<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    #[account(constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
+    pub new_update_authority: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
+#[instruction(ix: AddDelegateIx)]
+pub struct AddDelegate<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    #[account(
+        init,
+        payer = update_authority,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [DELEGATE_PREFIX.as_ref(), entry.to_account_info().key.as_ref(), delegate.key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub entry_delegate: Account<'info, EntryDelegate>,
+    pub delegate: AccountInfo<'info>,
+    #[account(mut, constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveDelegate<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    #[account(
+        mut,
+        seeds = [DELEGATE_PREFIX.as_ref(), entry.to_account_info().key.as_ref(), entry_delegate.delegate.as_ref()],
+        bump = entry_delegate.bump,
+        constraint = entry_delegate.entry == *entry.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry_delegate: Account<'info, EntryDelegate>,
+    #[account(mut, constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEntry<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    pub claimant: Signer<'info>,
+    pub realm: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: VerifyEntryIx)]
 pub struct VerifyEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    #[account(
+        seeds = [BADGES_PREFIX.as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = badge_definitions.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [ATTESTATION_PREFIX.as_ref(), entry.to_account_info().key.as_ref(), verifier.key.as_ref(), badge_seed(&ix.badge)],
+        bump = ix.bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+    #[account(constraint = has_role(&registry_config, &verifier_role, verifier.key, Role::Verifier) @ ErrorCode::InsufficientAuthority)]
+    pub verifier: Signer<'info>,
+    pub verifier_role: AccountInfo<'info>,
+    #[account(constraint = *program.key == entry.data.program_id @ ErrorCode::InvalidProgram)]
+    pub program: AccountInfo<'info>,
+    pub program_data: AccountInfo<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnverifyEntry<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, Entry>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
+    #[account(
+        mut,
+        seeds = [ATTESTATION_PREFIX.as_ref(), entry.to_account_info().key.as_ref(), verifier.key.as_ref(), badge_seed(&attestation.badge)],
+        bump = attestation.bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+    pub verifier: Signer<'info>,
+    #[account(mut, constraint = attestation.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: InitBadgesIx)]
+pub struct InitBadges<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = BADGE_DEFINITIONS_SIZE,
+        seeds = [BADGES_PREFIX.as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UnverifyEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+pub struct DefineBadge<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [BADGES_PREFIX.as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = badge_definitions.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenewVerification<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+    pub attestation: AccountInfo<'info>,
+    #[account(constraint = has_role(&registry_config, &verifier_role, verifier.key, Role::Verifier) @ ErrorCode::InsufficientAuthority)]
+    pub verifier: Signer<'info>,
+    pub verifier_role: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssertVerified<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, Entry>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEntry<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(mut)]
+    pub entry: AccountInfo<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckVerification<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, Entry>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
-    pub authority: Signer<'info>,
+    #[account(constraint = *program.key == entry.data.program_id @ ErrorCode::InvalidProgram)]
+    pub program: AccountInfo<'info>,
+    pub program_data: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
 pub struct RemoveEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.realm_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, Entry>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key || entry.creator == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
+    #[account(constraint =
+        entry.creator == *authority.to_account_info().key
+        || has_role(&registry_config, &moderator_role, authority.key, Role::Moderator)
+        || is_delegate(entry.to_account_info().key, entry.delegate_generation, &entry_delegate, authority.key, DelegateScope::EditAndRemove)
+        @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub rent_recipient: AccountInfo<'info>,
+    pub moderator_role: AccountInfo<'info>,
+    pub entry_delegate: AccountInfo<'info>,
 }
 
 ///////////////// DATA /////////////////
@@ -179,20 +1039,98 @@ pub struct RemoveEntry<'info> {
 #[derive(Default)]
 pub struct RegistryConfig {
     pub bump: u8,
+    pub name: String,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub multisig_signers: Vec<Pubkey>,
+    pub multisig_threshold: u8,
     pub realm_seed: String,
     pub permissionless_add: bool,
+    pub governance_program_id: Option<Pubkey>,
+    pub realm: Option<Pubkey>,
+    pub strict_realm_validation: bool,
+    pub require_proof: bool,
+    pub require_executable: bool,
+    pub authority_can_update: bool,
+    pub max_entries: u32,
+    pub entry_count: u64,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub verification_threshold: u8,
+    pub verification_ttl: i64,
+    pub delegate_generation: u64,
+    pub verification_round: u64,
+}
+
+#[account]
+pub struct RoleMember {
+    pub bump: u8,
+    pub registry: Pubkey,
+    pub role: Role,
+    pub member: Pubkey,
+    pub granted_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum Role {
+    Admin,
+    Verifier,
+    Moderator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum ProofMethod {
+    None,
+    AddressSignature,
+    UpgradeAuthority,
+    RealmAuthority,
 }
 
 #[account]
 pub struct Entry {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub address: Pubkey,
     pub creator: Pubkey,
     pub created_at: i64,
-    pub is_verified: bool,
+    pub version: u8,
     pub verified_at: Option<i64>,
     pub schema_version: u8,
     pub data: Realm,
+    pub community_mint: Option<Pubkey>,
+    pub proof: ProofMethod,
+    pub deployment: Option<ProgramDeployment>,
+    pub verified_deploy_slot: Option<u64>,
+    pub verified_hash: Option<[u8; 32]>,
+    pub update_authority: Pubkey,
+    pub rent_payer: Pubkey,
+    pub attestation_count: u8,
+    pub verification_round: u64,
+    pub badges: u32,
+    pub badge_grants: Vec<BadgeGrant>,
+    pub delegate_generation: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct BadgeGrant {
+    pub badge: u8,
+    pub attestations: u8,
+    pub granted_by: Option<Pubkey>,
+    pub granted_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+#[account]
+pub struct BadgeDefinitions {
+    pub bump: u8,
+    pub registry: Pubkey,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ProgramDeployment {
+    pub upgrade_authority: Option<Pubkey>,
+    pub deploy_slot: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, AnchorSerialize, AnchorDeserialize)]
@@ -207,10 +1145,455 @@ pub struct Realm {
     pub attributes: Vec<Vec<String>>,
 }
 
+#[derive(AnchorDeserialize)]
+pub struct SplGovernanceAccountHeader {
+    pub account_type: u8,
+    pub realm: Pubkey,
+}
+
+// leading fields shared by spl-governance v1 and v2 Realm accounts
+#[derive(AnchorDeserialize)]
+pub struct SplGovernanceRealm {
+    pub account_type: u8,
+    pub community_mint: Pubkey,
+    pub config_reserved: [u8; 8],
+    pub min_community_weight_to_create_governance: u64,
+    pub community_mint_max_vote_weight_source: (u8, u64),
+    pub council_mint: Option<Pubkey>,
+    pub reserved: [u8; 8],
+    pub authority: Option<Pubkey>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum RevocationReason {
+    ProgramRedeployed,
+    ProgramClosed,
+    ContentChanged,
+}
+
+#[account]
+pub struct EntryDelegate {
+    pub bump: u8,
+    pub entry: Pubkey,
+    pub delegate: Pubkey,
+    pub scope: DelegateScope,
+    pub expires_at: Option<i64>,
+    pub generation: u64,
+}
+
+#[account]
+pub struct Attestation {
+    pub bump: u8,
+    pub entry: Pubkey,
+    pub verifier: Pubkey,
+    pub round: u64,
+    pub attested_at: i64,
+    pub rent_payer: Pubkey,
+    pub badge: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum DelegateScope {
+    Edit,
+    EditAndRemove,
+}
+
+#[event]
+pub struct EntryClaimed {
+    pub registry: Pubkey,
+    pub entry: Pubkey,
+    pub address: Pubkey,
+    pub previous_creator: Pubkey,
+    pub new_creator: Pubkey,
+}
+
+#[event]
+pub struct VerificationRevoked {
+    pub registry: Pubkey,
+    pub entry: Pubkey,
+    pub reason: RevocationReason,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub registry: Pubkey,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn authorized(registry_config: &RegistryConfig, signer: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    if signer.is_signer && registry_config.authority == *signer.key {
+        return Ok(());
+    }
+    if registry_config.multisig_threshold > 0 {
+        let mut approvals: Vec<&Pubkey> = Vec::new();
+        let signers = remaining_accounts.iter().map(|account| (account.key, account.is_signer));
+        for (key, is_signer) in std::iter::once((signer.key, signer.is_signer)).chain(signers) {
+            if is_signer && registry_config.multisig_signers.contains(key) && !approvals.contains(&key) {
+                approvals.push(key);
+            }
+        }
+        if approvals.len() >= registry_config.multisig_threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(ErrorCode::InsufficientAuthority.into())
+}
+
+// signers approved by the previous authority do not carry over to the next one
+pub fn clear_multisig(registry_config: &mut RegistryConfig) {
+    registry_config.multisig_signers.clear();
+    registry_config.multisig_threshold = 0;
+}
+
+pub fn config_changed<T: ToString>(registry: Pubkey, field: &str, old_value: &T, new_value: &T) {
+    emit!(ConfigUpdated {
+        registry,
+        field: field.to_string(),
+        old_value: old_value.to_string(),
+        new_value: new_value.to_string(),
+    });
+}
+
+pub fn verification_expiry(registry_config: &RegistryConfig, granted_at: i64) -> Option<i64> {
+    if registry_config.verification_ttl > 0 {
+        Some(granted_at + registry_config.verification_ttl)
+    } else {
+        None
+    }
+}
+
+// `badges` keeps the bits of expired grants, readers should go through these helpers
+pub fn active_badges(entry: &Entry, now: i64) -> u32 {
+    entry.badge_grants.iter()
+        .filter(|grant| grant.granted_at.is_some() && !matches!(grant.expires_at, Some(expires_at) if expires_at <= now))
+        .fold(0, |badges, grant| badges | 1 << grant.badge)
+}
+
+pub fn is_verified_at(entry: &Entry, now: i64) -> bool {
+    active_badges(entry, now) != 0
+}
+
+// returns the signing verifier when `attestation` is its attestation of `badge` in the current round
+pub fn renewing_verifier(entry: &Pubkey, badge: u8, round: u64, attestation: &AccountInfo, verifier: &AccountInfo) -> std::result::Result<Pubkey, ProgramError> {
+    if attestation.owner != &ID || !verifier.is_signer {
+        return Err(ErrorCode::InvalidAttestation.into());
+    }
+    let record = Attestation::try_deserialize(&mut attestation.try_borrow_data()?.as_ref())?;
+    let address = Pubkey::create_program_address(
+        &[ATTESTATION_PREFIX.as_ref(), entry.as_ref(), verifier.key.as_ref(), badge_seed(&badge), &[record.bump]],
+        &ID,
+    )
+    .map_err(|_| ErrorCode::InvalidAttestation)?;
+    if address != *attestation.key || record.badge != badge || record.round != round {
+        return Err(ErrorCode::InvalidAttestation.into());
+    }
+    Ok(*verifier.key)
+}
+
+// the legacy badge keeps the attestation address used before badges existed, so old attestations stay withdrawable
+pub fn badge_seed(badge: &u8) -> &[u8] {
+    if *badge == LEGACY_VERIFIED_BADGE {
+        &[]
+    } else {
+        std::slice::from_ref(badge)
+    }
+}
+
+// entries created before registry-bound seeds start with their address and sit at [seed, address]
+pub fn predates_registry_seeds(entry: &AccountInfo, seed: &str) -> std::result::Result<bool, ProgramError> {
+    let data = entry.try_borrow_data()?;
+    let address = match data.get(8..40) {
+        Some(address) => Pubkey::new(address),
+        None => return Ok(false),
+    };
+    Ok(Pubkey::find_program_address(&[seed.as_ref(), address.as_ref()], &ID).0 == *entry.key)
+}
+
+// drawn from the registry so generations keep increasing when an entry is removed and re-created
+pub fn next_delegate_generation(registry_config: &mut RegistryConfig) -> u64 {
+    registry_config.delegate_generation += 1;
+    registry_config.delegate_generation
+}
+
+// drawn from the registry so a re-created entry never reuses the round of its stale attestations
+pub fn next_verification_round(registry_config: &mut RegistryConfig) -> u64 {
+    registry_config.verification_round += 1;
+    registry_config.verification_round
+}
+
+pub fn rent_recipient(registry_config: &RegistryConfig, rent_payer: &Pubkey, moderated: bool) -> Pubkey {
+    match registry_config.moderation_treasury {
+        Some(treasury) if moderated => treasury,
+        _ => *rent_payer,
+    }
+}
+
+pub fn is_delegate(entry: &Pubkey, delegate_generation: u64, delegate_account: &AccountInfo, delegate: &Pubkey, scope: DelegateScope) -> bool {
+    if delegate_account.owner != &ID {
+        return false;
+    }
+    let data = match delegate_account.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    match EntryDelegate::try_deserialize(&mut data.as_ref()) {
+        Ok(entry_delegate) => {
+            entry_delegate.entry == *entry
+                && entry_delegate.generation == delegate_generation
+                && entry_delegate.delegate == *delegate
+                && (entry_delegate.scope == scope || entry_delegate.scope == DelegateScope::EditAndRemove)
+                && !matches!(entry_delegate.expires_at, Some(expires_at) if expires_at <= Clock::get().unwrap().unix_timestamp)
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn has_role(registry_config: &ProgramAccount<RegistryConfig>, role_account: &AccountInfo, member: &Pubkey, role: Role) -> bool {
+    if role_account.owner != &ID {
+        return false;
+    }
+    let data = match role_account.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    match RoleMember::try_deserialize(&mut data.as_ref()) {
+        Ok(role_member) => {
+            role_member.registry == *registry_config.to_account_info().key
+                && role_member.member == *member
+                && role_member.role == role
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn admin_authorized(
+    registry_config: &ProgramAccount<RegistryConfig>,
+    role_account: &AccountInfo,
+    admin: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+) -> ProgramResult {
+    if admin.is_signer && has_role(registry_config, role_account, admin.key, Role::Admin) {
+        return Ok(());
+    }
+    authorized(registry_config, admin, remaining_accounts)
+}
+
+pub fn assert_realm_governance(governance_program_id: &Pubkey, realm: &AccountInfo, governance: &AccountInfo) -> ProgramResult {
+    if realm.owner != governance_program_id || governance.owner != governance_program_id {
+        return Err(ErrorCode::InvalidGovernanceAccount.into());
+    }
+    let realm_type = *realm.try_borrow_data()?.first().ok_or(ErrorCode::InvalidGovernanceAccount)?;
+    let governance_header = SplGovernanceAccountHeader::deserialize(&mut governance.try_borrow_data()?.as_ref())
+        .map_err(|_| ErrorCode::InvalidGovernanceAccount)?;
+    if !SPL_GOVERNANCE_REALM_TYPES.contains(&realm_type)
+        || !SPL_GOVERNANCE_GOVERNANCE_TYPES.contains(&governance_header.account_type)
+        || governance_header.realm != *realm.key
+    {
+        return Err(ErrorCode::InvalidGovernanceAccount.into());
+    }
+    Ok(())
+}
+
+pub fn assert_realm_entry(realm: &AccountInfo, address: &Pubkey, data: &Realm) -> std::result::Result<Pubkey, ProgramError> {
+    if realm.key != address || realm.owner != &data.program_id {
+        return Err(ErrorCode::InvalidRealm.into());
+    }
+    let realm_data = SplGovernanceRealm::deserialize(&mut realm.try_borrow_data()?.as_ref())
+        .map_err(|_| ErrorCode::InvalidRealm)?;
+    if !SPL_GOVERNANCE_REALM_TYPES.contains(&realm_data.account_type) || realm_data.name != data.name {
+        return Err(ErrorCode::InvalidRealm.into());
+    }
+    Ok(realm_data.community_mint)
+}
+
+pub fn assert_proof(proof: ProofMethod, address: &Pubkey, creator: &AccountInfo, data: &Realm, realm: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    let proven = match proof {
+        ProofMethod::None => true,
+        ProofMethod::AddressSignature => std::iter::once((creator.key, creator.is_signer))
+            .chain(remaining_accounts.iter().map(|account| (account.key, account.is_signer)))
+            .any(|(key, is_signer)| key == address && is_signer),
+        ProofMethod::UpgradeAuthority => {
+            let (program_data, _) = Pubkey::find_program_address(&[address.as_ref()], &bpf_loader_upgradeable::id());
+            match remaining_accounts.iter().find(|account| *account.key == program_data) {
+                Some(account) => creator.is_signer && program_data_header(account)?.1 == Some(*creator.key),
+                None => false,
+            }
+        }
+        ProofMethod::RealmAuthority => {
+            realm.key == address
+                && realm.owner == &data.program_id
+                && creator.is_signer
+                && SplGovernanceRealm::deserialize(&mut realm.try_borrow_data()?.as_ref())
+                    .map(|realm_data| realm_data.authority == Some(*creator.key))
+                    .unwrap_or(false)
+        }
+    };
+    if !proven {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    Ok(())
+}
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProgramData.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+// None for programs that cannot be upgraded
+pub fn programdata_address(program: &AccountInfo) -> std::result::Result<Option<Pubkey>, ProgramError> {
+    if program.owner != &bpf_loader_upgradeable::id() {
+        return Ok(None);
+    }
+    // UpgradeableLoaderState::Program { programdata_address: Pubkey }
+    let data = program.try_borrow_data()?;
+    if data.len() < 36 || data[0..4] != [2, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    Ok(Some(Pubkey::new_from_array(data[4..36].try_into().unwrap())))
+}
+
+pub fn program_deployment(program_id: &Pubkey, remaining_accounts: &[AccountInfo]) -> std::result::Result<ProgramDeployment, ProgramError> {
+    let program = remaining_accounts
+        .iter()
+        .find(|account| account.key == program_id)
+        .ok_or(ErrorCode::InvalidProgram)?;
+    let program_data = match programdata_address(program)? {
+        Some(address) => remaining_accounts
+            .iter()
+            .find(|account| *account.key == address)
+            .ok_or(ErrorCode::InvalidProgramData)?,
+        None => program,
+    };
+    checked_deployment(program, program_data)
+}
+
+// program_data has to be the account the program points at, it is ignored for programs that cannot be upgraded
+pub fn checked_deployment(program: &AccountInfo, program_data: &AccountInfo) -> std::result::Result<ProgramDeployment, ProgramError> {
+    if !program.executable {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    match programdata_address(program)? {
+        None => Ok(ProgramDeployment { upgrade_authority: None, deploy_slot: None }),
+        Some(address) if address != *program_data.key => Err(ErrorCode::InvalidProgramData.into()),
+        Some(_) => {
+            let (slot, upgrade_authority) = program_data_header(program_data)?;
+            Ok(ProgramDeployment { upgrade_authority, deploy_slot: Some(slot) })
+        }
+    }
+}
+
+pub fn content_hash<T: AnchorSerialize>(schema_version: u8, data: &T) -> [u8; 32] {
+    hashv(&[&[schema_version], &data.try_to_vec().unwrap()]).to_bytes()
+}
+
+// existing attestations stop counting, verifiers have to attest again
+pub fn revoke_verification(entry: &mut Entry, registry_config: &mut RegistryConfig) {
+    entry.verified_at = None;
+    entry.verified_deploy_slot = None;
+    entry.verified_hash = None;
+    entry.attestation_count = 0;
+    entry.verification_round = next_verification_round(registry_config);
+    entry.badges = 0;
+    entry.badge_grants.clear();
+}
+
+pub fn entry_size<T: AnchorSerialize>(registry_config: &RegistryConfig, data: &T, badge_grants: usize) -> std::result::Result<usize, ProgramError> {
+    let size = ENTRY_BASE_SIZE + data.try_to_vec().unwrap().len();
+    if registry_config.max_entry_size > 0 && size > registry_config.max_entry_size as usize {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    // badge grants do not count towards the payload limit
+    Ok(size + badge_grants * BADGE_GRANT_SIZE)
+}
+
+// growth is paid by `payer`, a shrink refunds `rent_payer`
+pub fn resize_entry<'info>(entry: &AccountInfo<'info>, size: usize, payer: &AccountInfo<'info>, rent_payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> ProgramResult {
+    let current_size = entry.data_len();
+    if size == current_size {
+        return Ok(());
+    }
+    if size > current_size + MAX_PERMITTED_DATA_INCREASE {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    let rent = Rent::get()?.minimum_balance(size);
+    if rent > entry.lamports() {
+        invoke(
+            &system_instruction::transfer(payer.key, entry.key, rent - entry.lamports()),
+            &[payer.clone(), entry.clone(), system_program.clone()],
+        )?;
+    } else {
+        let refund = entry.lamports() - rent;
+        **entry.try_borrow_mut_lamports()? -= refund;
+        **rent_payer.try_borrow_mut_lamports()? += refund;
+    }
+    // zero the grown tail, it may still hold bytes from an earlier shrink in this transaction
+    entry.realloc(size, true)
+}
+
 ///////////////// ERRORS /////////////////
 
 #[error]
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry entry")]
     InsufficientAuthority,
+    #[msg("Signer is not the pending authority for this registry")]
+    InvalidPendingAuthority,
+    #[msg("Entry does not belong to this registry")]
+    InvalidRegistry,
+    #[msg("Multisig signers or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Realm or governance account is not a valid spl-governance account")]
+    InvalidGovernanceAccount,
+    #[msg("Entry does not match the on-chain spl-governance realm")]
+    InvalidRealm,
+    #[msg("Creator did not prove control of the entry address")]
+    InvalidProof,
+    #[msg("Referenced account is not an executable program")]
+    InvalidProgram,
+    #[msg("Program data account does not match the program")]
+    InvalidProgramData,
+    #[msg("Seed fields cannot change once the registry has entries")]
+    SeedLocked,
+    #[msg("Registry has reached its maximum number of entries")]
+    RegistryFull,
+    #[msg("Entry data exceeds the maximum entry size for this registry")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer or registry treasury")]
+    InvalidRentRecipient,
+    #[msg("Badge is not defined for this registry or the badge limit is reached")]
+    InvalidBadge,
+    #[msg("Entry already uses the badge layout")]
+    EntryMigrated,
+    #[msg("Entry does not hold the requested badge")]
+    BadgeNotGranted,
+    #[msg("Entry verification has expired")]
+    VerificationExpired,
+    #[msg("Verifier already attested the badge in the current round")]
+    AlreadyAttested,
+    #[msg("Attestation is not counted towards this entry")]
+    InvalidAttestation,
+    #[msg("Not enough verifiers renewed the badge")]
+    InsufficientAttestations,
+    #[msg("Entry has reached the maximum number of attestations")]
+    TooManyAttestations,
+    #[msg("Entry predates registry-bound seeds and cannot be migrated")]
+    UnsupportedEntryLayout,
 }
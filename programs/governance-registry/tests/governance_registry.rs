@@ -0,0 +1,1397 @@
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use governance_registry::{
+    accounts, instruction, AddDelegateIx, AddEntryIx, AssertVerifiedIx, Attestation, ClaimEntryIx, DefineBadgeIx, DelegateScope, Entry,
+    ErrorCode, GrantRoleIx, InitBadgesIx, InitIx, ProgramDeployment, ProofMethod, Realm, RegistryConfig, RenewVerificationIx, Role,
+    RoleMember, SetMultisigIx, UpdateConfigIx, UpdateEntryIx, VerifyEntryIx,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 1 + 9 + 1 + 33 + 1 + 43 + 9 + 33 + 32 + 32 + 1 + 8 + 4 + 4 + 8;
+const BADGE_GRANT_SIZE: usize = 1 + 1 + 33 + 9 + 9;
+const REALM_SEED: &str = "realm";
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new("governance_registry", governance_registry::id(), processor!(governance_registry::entry))
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn account_size(context: &mut ProgramTestContext, address: Pubkey) -> (usize, u64) {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    (account.data.len(), account.lamports)
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &governance_registry::id())
+}
+
+fn init_ix(authority: Pubkey, configure: impl FnOnce(&mut InitIx)) -> (Pubkey, Instruction) {
+    let mut ix = InitIx {
+        bump: 0,
+        name: "registry".to_string(),
+        realm_seed: REALM_SEED.to_string(),
+        permissionless_add: true,
+        governance_program_id: None,
+        strict_realm_validation: false,
+        require_proof: false,
+        require_executable: false,
+        authority_can_update: false,
+        max_entries: 0,
+        max_entry_size: 0,
+        moderation_treasury: None,
+        verification_threshold: 1,
+        verification_ttl: 0,
+    };
+    configure(&mut ix);
+    let (registry_config, bump) = pda(&[b"registry-config", ix.name.as_bytes()]);
+    ix.bump = bump;
+    let init = Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::Init { registry_config, authority, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::Init { ix }.data(),
+    };
+    (registry_config, init)
+}
+
+async fn init(context: &mut ProgramTestContext, configure: impl FnOnce(&mut InitIx)) -> Pubkey {
+    let (registry_config, init) = init_ix(context.payer.pubkey(), configure);
+    process(context, &[init], &[]).await.unwrap();
+    registry_config
+}
+
+// entries point at this program so verification has an executable to snapshot
+fn realm(description: &str) -> Realm {
+    Realm {
+        name: "realm".to_string(),
+        description: description.to_string(),
+        program_id: governance_registry::id(),
+        ..Realm::default()
+    }
+}
+
+fn entry_address(registry_config: &Pubkey, address: &Pubkey) -> (Pubkey, u8) {
+    pda(&[REALM_SEED.as_bytes(), registry_config.as_ref(), address.as_ref()])
+}
+
+fn add_entry_ix(registry_config: Pubkey, creator: Pubkey, address: Pubkey, data: Realm) -> Instruction {
+    let (entry, bump) = entry_address(&registry_config, &address);
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::AddEntry { registry_config, entry, creator, realm: address, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::AddEntry { ix: AddEntryIx { bump, address, schema_version: 0, data, proof: ProofMethod::None } }.data(),
+    }
+}
+
+async fn add_entry(context: &mut ProgramTestContext, registry_config: Pubkey, data: Realm) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let ix = add_entry_ix(registry_config, context.payer.pubkey(), address, data);
+    process(context, &[ix], &[]).await.unwrap();
+    entry_address(&registry_config, &address).0
+}
+
+fn update_entry_ix(registry_config: Pubkey, entry: Pubkey, updater: Pubkey, rent_payer: Pubkey, data: Realm) -> Instruction {
+    delegated_update_entry_ix(registry_config, entry, updater, rent_payer, system_program::id(), data)
+}
+
+fn delegated_update_entry_ix(registry_config: Pubkey, entry: Pubkey, updater: Pubkey, rent_payer: Pubkey, entry_delegate: Pubkey, data: Realm) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::UpdateEntry {
+            registry_config,
+            entry,
+            updater,
+            rent_payer,
+            realm: system_program::id(),
+            entry_delegate,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateEntry { ix: UpdateEntryIx { data } }.data(),
+    }
+}
+
+fn delegate_address(entry: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    pda(&[b"delegate", entry.as_ref(), delegate.as_ref()])
+}
+
+fn add_delegate_ix(registry_config: Pubkey, entry: Pubkey, delegate: Pubkey, update_authority: Pubkey, scope: DelegateScope, expires_at: Option<i64>) -> Instruction {
+    let (entry_delegate, bump) = delegate_address(&entry, &delegate);
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::AddDelegate { registry_config, entry, entry_delegate, delegate, update_authority, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::AddDelegate { ix: AddDelegateIx { bump, scope, expires_at } }.data(),
+    }
+}
+
+fn set_update_authority_ix(registry_config: Pubkey, entry: Pubkey, update_authority: Pubkey, new_update_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::SetUpdateAuthority { registry_config, entry, update_authority, new_update_authority }.to_account_metas(None),
+        data: instruction::SetUpdateAuthority {}.data(),
+    }
+}
+
+fn remove_delegate_ix(registry_config: Pubkey, entry: Pubkey, entry_delegate: Pubkey, update_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::RemoveDelegate { registry_config, entry, entry_delegate, update_authority }.to_account_metas(None),
+        data: instruction::RemoveDelegate {}.data(),
+    }
+}
+
+fn remove_entry_ix(registry_config: Pubkey, entry: Pubkey, authority: Pubkey, rent_recipient: Pubkey, entry_delegate: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::RemoveEntry {
+            registry_config,
+            entry,
+            authority,
+            rent_recipient,
+            moderator_role: system_program::id(),
+            entry_delegate,
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveEntry {}.data(),
+    }
+}
+
+fn update_config_ix(registry_config: Pubkey, authority: Pubkey, ix: UpdateConfigIx) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::UpdateConfig { registry_config, authority }.to_account_metas(None),
+        data: instruction::UpdateConfig { ix }.data(),
+    }
+}
+
+fn config_update(configure: impl FnOnce(&mut UpdateConfigIx)) -> UpdateConfigIx {
+    let mut ix = UpdateConfigIx {
+        permissionless_add: None,
+        realm_seed: None,
+        strict_realm_validation: None,
+        require_proof: None,
+        require_executable: None,
+        authority_can_update: None,
+        max_entries: None,
+        max_entry_size: None,
+        moderation_treasury: None,
+        verification_threshold: None,
+        verification_ttl: None,
+    };
+    configure(&mut ix);
+    ix
+}
+
+fn claim_entry_ix(registry_config: Pubkey, entry: Pubkey, claimant: Pubkey, realm: Pubkey, proof: ProofMethod) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::ClaimEntry { registry_config, entry, claimant, realm }.to_account_metas(None),
+        data: instruction::ClaimEntry { ix: ClaimEntryIx { proof } }.data(),
+    }
+}
+
+fn assert_verified_ix(registry_config: Pubkey, entry: Pubkey, badges: u32) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::AssertVerified { registry_config, entry }.to_account_metas(None),
+        data: instruction::AssertVerified { ix: AssertVerifiedIx { badges } }.data(),
+    }
+}
+
+fn role_address(registry_config: &Pubkey, role: Role, member: &Pubkey) -> (Pubkey, u8) {
+    pda(&[b"role", registry_config.as_ref(), &[role as u8], member.as_ref()])
+}
+
+fn grant_role_ix(registry_config: Pubkey, role: Role, member: Pubkey, admin: Pubkey, payer: Pubkey) -> Instruction {
+    let (role_member, bump) = role_address(&registry_config, role, &member);
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::GrantRole {
+            registry_config,
+            role_member,
+            member,
+            admin,
+            admin_role: system_program::id(),
+            payer,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::GrantRole { ix: GrantRoleIx { bump, role } }.data(),
+    }
+}
+
+async fn grant_role(context: &mut ProgramTestContext, registry_config: Pubkey, role: Role, member: Pubkey) -> Pubkey {
+    let payer = context.payer.pubkey();
+    process(context, &[grant_role_ix(registry_config, role, member, payer, payer)], &[]).await.unwrap();
+    role_address(&registry_config, role, &member).0
+}
+
+fn init_badges_ix(registry_config: Pubkey, authority: Pubkey, payer: Pubkey, names: &[&str]) -> Vec<Instruction> {
+    let (badge_definitions, bump) = pda(&[b"badges", registry_config.as_ref()]);
+    let mut instructions = vec![Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::InitBadges { registry_config, badge_definitions, authority, payer, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::InitBadges { ix: InitBadgesIx { bump } }.data(),
+    }];
+    for name in names {
+        instructions.push(Instruction {
+            program_id: governance_registry::id(),
+            accounts: accounts::DefineBadge { registry_config, badge_definitions, authority }.to_account_metas(None),
+            data: instruction::DefineBadge { ix: DefineBadgeIx { name: name.to_string() } }.data(),
+        });
+    }
+    instructions
+}
+
+async fn init_badges(context: &mut ProgramTestContext, registry_config: Pubkey, names: &[&str]) -> Pubkey {
+    let payer = context.payer.pubkey();
+    process(context, &init_badges_ix(registry_config, payer, payer, names), &[]).await.unwrap();
+    pda(&[b"badges", registry_config.as_ref()]).0
+}
+
+fn revoke_role_ix(registry_config: Pubkey, role_member: Pubkey, admin: Pubkey, admin_role: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::RevokeRole { registry_config, role_member, admin, admin_role }.to_account_metas(None),
+        data: instruction::RevokeRole {}.data(),
+    }
+}
+
+fn attestation_address(entry: &Pubkey, verifier: &Pubkey, badge: u8) -> (Pubkey, u8) {
+    // the legacy badge keeps the attestation address used before badges existed
+    let badge_seed: &[u8] = if badge == 0 { &[] } else { std::slice::from_ref(&badge) };
+    pda(&[b"attestation", entry.as_ref(), verifier.as_ref(), badge_seed])
+}
+
+fn verify_entry_ix(registry_config: Pubkey, entry: Pubkey, verifier: Pubkey, payer: Pubkey, badge: u8) -> Instruction {
+    verify_program_ix(registry_config, entry, verifier, payer, badge, governance_registry::id(), governance_registry::id())
+}
+
+fn verify_program_ix(registry_config: Pubkey, entry: Pubkey, verifier: Pubkey, payer: Pubkey, badge: u8, program: Pubkey, program_data: Pubkey) -> Instruction {
+    let (badge_definitions, _) = pda(&[b"badges", registry_config.as_ref()]);
+    let (attestation, bump) = attestation_address(&entry, &verifier, badge);
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::VerifyEntry {
+            registry_config,
+            entry,
+            badge_definitions,
+            attestation,
+            verifier,
+            verifier_role: role_address(&registry_config, Role::Verifier, &verifier).0,
+            program,
+            program_data,
+            payer,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::VerifyEntry { ix: VerifyEntryIx { bump, badge } }.data(),
+    }
+}
+
+fn unverify_entry_ix(registry_config: Pubkey, entry: Pubkey, verifier: Pubkey, badge: u8, rent_payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::UnverifyEntry { registry_config, entry, attestation: attestation_address(&entry, &verifier, badge).0, verifier, rent_payer }
+            .to_account_metas(None),
+        data: instruction::UnverifyEntry {}.data(),
+    }
+}
+
+// co-signers follow the first verifier as (attestation, verifier, verifier_role) triples
+fn renew_verification_ix(registry_config: Pubkey, entry: Pubkey, verifiers: &[Pubkey], badge: u8) -> Instruction {
+    let mut accounts = accounts::RenewVerification {
+        registry_config,
+        entry,
+        attestation: attestation_address(&entry, &verifiers[0], badge).0,
+        verifier: verifiers[0],
+        verifier_role: role_address(&registry_config, Role::Verifier, &verifiers[0]).0,
+    }
+    .to_account_metas(None);
+    for verifier in &verifiers[1..] {
+        accounts.push(AccountMeta::new_readonly(attestation_address(&entry, verifier, badge).0, false));
+        accounts.push(AccountMeta::new_readonly(*verifier, true));
+        accounts.push(AccountMeta::new_readonly(role_address(&registry_config, Role::Verifier, verifier).0, false));
+    }
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts,
+        data: instruction::RenewVerification { ix: RenewVerificationIx { badge } }.data(),
+    }
+}
+
+fn migrate_entry_ix(registry_config: Pubkey, entry: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::MigrateEntry { registry_config, entry, payer, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::MigrateEntry {}.data(),
+    }
+}
+
+fn program_account(data: Vec<u8>) -> Account {
+    Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: governance_registry::id(), executable: false, rent_epoch: 0 }
+}
+
+fn check_verification_ix(registry_config: Pubkey, entry: Pubkey, program: Pubkey, program_data: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::CheckVerification { registry_config, entry, program, program_data }.to_account_metas(None),
+        data: instruction::CheckVerification {}.data(),
+    }
+}
+
+fn program_data_account(deploy_slot: u64, upgrade_authority: Pubkey) -> Account {
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&deploy_slot.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: bpf_loader_upgradeable::id(), executable: false, rent_epoch: 0 }
+}
+
+// an upgradeable program with its program data, neither is ever invoked
+fn add_upgradeable_program(program_test: &mut ProgramTest, deploy_slot: u64) -> (Pubkey, Pubkey) {
+    let program = Pubkey::new_unique();
+    let (program_data, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    let mut data = vec![2, 0, 0, 0];
+    data.extend_from_slice(program_data.as_ref());
+    program_test.add_account(
+        program,
+        Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: bpf_loader_upgradeable::id(), executable: true, rent_epoch: 0 },
+    );
+    program_test.add_account(program_data, program_data_account(deploy_slot, Pubkey::new_unique()));
+    (program, program_data)
+}
+
+fn propose_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::ProposeAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::ProposeAuthority {}.data(),
+    }
+}
+
+fn accept_authority_ix(registry_config: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::AcceptAuthority { registry_config, new_authority }.to_account_metas(None),
+        data: instruction::AcceptAuthority {}.data(),
+    }
+}
+
+fn cancel_authority_transfer_ix(registry_config: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::CancelAuthorityTransfer { registry_config, authority }.to_account_metas(None),
+        data: instruction::CancelAuthorityTransfer {}.data(),
+    }
+}
+
+fn set_multisig_ix(registry_config: Pubkey, authority: Pubkey, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::SetMultisig { registry_config, authority }.to_account_metas(None),
+        data: instruction::SetMultisig { ix: SetMultisigIx { signers, threshold } }.data(),
+    }
+}
+
+fn transfer_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: governance_registry::id(),
+        accounts: accounts::TransferAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::TransferAuthority {}.data(),
+    }
+}
+
+// co-signers approve through the remaining accounts
+fn with_signers(mut ix: Instruction, signers: &[&Keypair]) -> Instruction {
+    ix.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    ix
+}
+
+fn spl_governance_id() -> Pubkey {
+    "GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw".parse().unwrap()
+}
+
+// spl-governance accounts are mocked with the fields this program reads, so no program binary is needed
+fn add_spl_governance_account(program_test: &mut ProgramTest, address: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: spl_governance_id(), executable: false, rent_epoch: 0 },
+    );
+}
+
+// RealmV2 up to its name, followed by the config spl-governance appends
+fn realm_account_data(name: &str, community_mint: Pubkey, authority: Option<Pubkey>) -> Vec<u8> {
+    let mut data = (16u8, community_mint, [0u8; 8], 1u64, (0u8, 10_000_000_000u64), None::<Pubkey>, [0u8; 8], authority, name.to_string())
+        .try_to_vec()
+        .unwrap();
+    data.extend_from_slice(&[0; 64]);
+    data
+}
+
+// GovernanceV2 header, followed by a config this program never reads
+fn governance_account_data(realm: Pubkey) -> Vec<u8> {
+    let mut data = (18u8, realm).try_to_vec().unwrap();
+    data.extend_from_slice(&[0; 128]);
+    data
+}
+
+// the governance signs with a keypair here, on chain it signs for executed proposals
+fn governed_program_test() -> (ProgramTest, Pubkey, Keypair) {
+    let mut program_test = program_test();
+    let realm = Pubkey::new_unique();
+    let governance = Keypair::new();
+    add_spl_governance_account(&mut program_test, realm, realm_account_data("realm", Pubkey::new_unique(), None));
+    add_spl_governance_account(&mut program_test, governance.pubkey(), governance_account_data(realm));
+    (program_test, realm, governance)
+}
+
+fn governed_init_ix(payer: Pubkey, realm: Pubkey, governance: Pubkey) -> (Pubkey, Instruction) {
+    let (registry_config, mut ix) = init_ix(payer, |ix| ix.governance_program_id = Some(spl_governance_id()));
+    ix.accounts.push(AccountMeta::new_readonly(realm, false));
+    ix.accounts.push(AccountMeta::new_readonly(governance, false));
+    (registry_config, ix)
+}
+
+#[tokio::test]
+async fn update_entry_grows_and_shrinks_the_account() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("short")).await;
+
+    let grown = realm(&"x".repeat(600));
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, grown.clone())], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + grown.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data.description, grown.description);
+
+    let shrunk = realm("tiny");
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, shrunk.clone())], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + shrunk.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data.description, "tiny");
+}
+
+#[tokio::test]
+async fn shrink_then_grow_in_one_transaction_zeroes_the_tail() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm(&"x".repeat(300))).await;
+
+    let regrown = realm(&"y".repeat(300));
+    process(
+        &mut context,
+        &[update_entry_ix(registry_config, entry, payer, payer, realm("")), update_entry_ix(registry_config, entry, payer, payer, regrown.clone())],
+        &[],
+    )
+    .await
+    .unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data.description, regrown.description);
+}
+
+#[tokio::test]
+async fn update_entry_rejects_data_above_the_max_entry_size() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entry_size = (ENTRY_BASE_SIZE + 200) as u32).await;
+    let entry = add_entry(&mut context, registry_config, realm("short")).await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm(&"x".repeat(200)))], &[]).await;
+    assert_error(result, ErrorCode::DataTooLarge);
+}
+
+#[tokio::test]
+async fn shrink_refunds_only_the_rent_payer() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm(&"x".repeat(300))).await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, Pubkey::new_unique(), realm("tiny"))], &[]).await;
+    assert_error(result, ErrorCode::InvalidRentRecipient);
+}
+
+#[tokio::test]
+async fn verify_entry_grows_the_account_for_each_new_badge() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified", "audited"]).await;
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    let entry = add_entry(&mut context, registry_config, realm("short")).await;
+    let (base_size, _) = account_size(&mut context, entry).await;
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 1)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, base_size + BADGE_GRANT_SIZE);
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await.unwrap();
+    let (size, _) = account_size(&mut context, entry).await;
+    assert_eq!(size, base_size + 2 * BADGE_GRANT_SIZE);
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.badge_grants.len(), 2);
+    assert_eq!(entry_data.badges, 0b11);
+}
+
+#[tokio::test]
+async fn proposed_authority_takes_over_once_it_accepts() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, payer);
+    assert_eq!(config.pending_authority, Some(new_authority.pubkey()));
+
+    process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.pending_authority, None);
+
+    // the previous authority lost its rights
+    let result = process(&mut context, &[propose_authority_ix(registry_config, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn only_the_pending_authority_can_accept() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+    let impostor = Keypair::new();
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let result = process(&mut context, &[accept_authority_ix(registry_config, impostor.pubkey())], &[&impostor]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.authority, payer);
+}
+
+#[tokio::test]
+async fn cancelled_handoff_cannot_be_accepted() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    process(&mut context, &[cancel_authority_transfer_ix(registry_config, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, None);
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+}
+
+#[tokio::test]
+async fn only_the_authority_can_propose_or_cancel() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let stranger = Keypair::new();
+
+    let result = process(&mut context, &[propose_authority_ix(registry_config, stranger.pubkey(), stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[cancel_authority_transfer_ix(registry_config, stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority.is_some());
+}
+
+#[tokio::test]
+async fn revoked_verifier_can_no_longer_attest() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified", "audited"]).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+
+    let (_, role_lamports) = account_size(&mut context, role_member).await;
+    let balance = context.banks_client.get_balance(payer).await.unwrap();
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, payer, system_program::id())], &[]).await.unwrap();
+    assert!(context.banks_client.get_account(role_member).await.unwrap().is_none());
+    // the role rent goes back to the admin, minus the fee it paid as fee payer
+    assert_eq!(context.banks_client.get_balance(payer).await.unwrap(), balance + role_lamports - 5000);
+
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 1)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn admin_role_members_can_revoke_roles() {
+    let mut context = program_test().start_with_context().await;
+    let registry_config = init(&mut context, |_| {}).await;
+    let admin = Keypair::new();
+    let admin_role = grant_role(&mut context, registry_config, Role::Admin, admin.pubkey()).await;
+    let role_member = grant_role(&mut context, registry_config, Role::Moderator, Pubkey::new_unique()).await;
+
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, admin.pubkey(), admin_role)], &[&admin]).await.unwrap();
+    assert!(context.banks_client.get_account(role_member).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn revoke_role_requires_an_admin() {
+    let mut context = program_test().start_with_context().await;
+    let registry_config = init(&mut context, |_| {}).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+
+    // holding another role is not enough
+    let result = process(&mut context, &[revoke_role_ix(registry_config, role_member, verifier.pubkey(), role_member)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let role: RoleMember = fetch(&mut context, role_member).await;
+    assert_eq!(role.member, verifier.pubkey());
+    assert_eq!(role.role, Role::Verifier);
+}
+
+#[tokio::test]
+async fn multisig_members_act_once_the_threshold_is_met() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let (member_a, member_b) = (Keypair::new(), Keypair::new());
+    let signers = vec![member_a.pubkey(), member_b.pubkey()];
+    process(&mut context, &[set_multisig_ix(registry_config, payer, signers.clone(), 2)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.multisig_signers, signers);
+    assert_eq!(config.multisig_threshold, 2);
+
+    let new_authority = Pubkey::new_unique();
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member_a.pubkey(), new_authority)], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // a member listed twice still counts once
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_a]);
+    let result = process(&mut context, &[ix], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_b]);
+    process(&mut context, &[ix], &[&member_a, &member_b]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, Some(new_authority));
+}
+
+#[tokio::test]
+async fn set_multisig_rejects_invalid_signer_sets() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Pubkey::new_unique();
+
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member], 2)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member, member], 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let too_many = (0..11).map(|_| Pubkey::new_unique()).collect();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, too_many, 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, stranger.pubkey(), vec![stranger.pubkey()], 1)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn handing_over_authority_clears_the_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Keypair::new();
+    process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member.pubkey()], 1)], &[]).await.unwrap();
+
+    let new_authority = Keypair::new();
+    process(&mut context, &[transfer_authority_ix(registry_config, payer, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), member.pubkey())], &[&member]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // the same holds for the two step handoff
+    let authority = new_authority;
+    process(&mut context, &[set_multisig_ix(registry_config, authority.pubkey(), vec![member.pubkey()], 1)], &[&authority]).await.unwrap();
+    let next_authority = Keypair::new();
+    process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), next_authority.pubkey())], &[&member]).await.unwrap();
+    process(&mut context, &[accept_authority_ix(registry_config, next_authority.pubkey())], &[&next_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, next_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+}
+
+#[tokio::test]
+async fn governance_owned_registry_is_administered_through_the_governance() {
+    let (program_test, realm_address, governance) = governed_program_test();
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let (registry_config, init) = governed_init_ix(payer, realm_address, governance.pubkey());
+    process(&mut context, &[init], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, governance.pubkey());
+    assert_eq!(config.realm, Some(realm_address));
+    assert_eq!(config.governance_program_id, Some(spl_governance_id()));
+
+    // the wallet that created the registry holds no authority over it
+    let result = process(&mut context, &init_badges_ix(registry_config, payer, payer, &["audited"]), &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let entry = add_entry(&mut context, registry_config, realm("governed")).await;
+    let mut instructions = vec![grant_role_ix(registry_config, Role::Verifier, governance.pubkey(), governance.pubkey(), payer)];
+    instructions.extend(init_badges_ix(registry_config, governance.pubkey(), payer, &["audited"]));
+    instructions.push(verify_entry_ix(registry_config, entry, governance.pubkey(), payer, 0));
+    process(&mut context, &instructions, &[&governance]).await.unwrap();
+
+    let role: RoleMember = fetch(&mut context, role_address(&registry_config, Role::Verifier, &governance.pubkey()).0).await;
+    assert_eq!(role.member, governance.pubkey());
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert!(entry_data.verified_at.is_some());
+    assert_eq!(entry_data.attestation_count, 1);
+    assert_eq!(entry_data.badges, 1);
+    assert_eq!(entry_data.badge_grants[0].granted_by, Some(governance.pubkey()));
+    assert_eq!(entry_data.badge_grants[0].expires_at, None);
+    process(&mut context, &[assert_verified_ix(registry_config, entry, 1)], &[]).await.unwrap();
+
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn governed_init_rejects_accounts_not_owned_by_spl_governance() {
+    let (mut program_test, realm_address, governance) = governed_program_test();
+    let other_realm = Pubkey::new_unique();
+    let foreign_governance = Pubkey::new_unique();
+    add_spl_governance_account(&mut program_test, other_realm, realm_account_data("other", Pubkey::new_unique(), None));
+    add_spl_governance_account(&mut program_test, foreign_governance, governance_account_data(other_realm));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let (_, init) = governed_init_ix(payer, realm_address, payer);
+    assert_error(process(&mut context, &[init], &[]).await, ErrorCode::InvalidGovernanceAccount);
+
+    // a governance of another realm cannot take over
+    let (_, init) = governed_init_ix(payer, realm_address, foreign_governance);
+    assert_error(process(&mut context, &[init], &[]).await, ErrorCode::InvalidGovernanceAccount);
+
+    let (_, mut init) = governed_init_ix(payer, realm_address, governance.pubkey());
+    init.accounts.truncate(init.accounts.len() - 2);
+    assert_error(process(&mut context, &[init], &[]).await, ErrorCode::InvalidGovernanceAccount);
+}
+
+#[tokio::test]
+async fn strict_registry_checks_realm_entries_against_spl_governance() {
+    let mut program_test = program_test();
+    let realm_address = Pubkey::new_unique();
+    let community_mint = Pubkey::new_unique();
+    add_spl_governance_account(&mut program_test, realm_address, realm_account_data("realm", community_mint, None));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.strict_realm_validation = true).await;
+    let realm_entry = |name: &str, program_id: Pubkey| Realm { name: name.to_string(), program_id, ..Realm::default() };
+
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, realm_address, realm_entry("not-the-realm-name", spl_governance_id()))], &[]).await;
+    assert_error(result, ErrorCode::InvalidRealm);
+    // the realm must be owned by the governance program the entry names
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, realm_address, realm_entry("realm", Pubkey::new_unique()))], &[]).await;
+    assert_error(result, ErrorCode::InvalidRealm);
+
+    process(&mut context, &[add_entry_ix(registry_config, payer, realm_address, realm_entry("realm", spl_governance_id()))], &[]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry_address(&registry_config, &realm_address).0).await;
+    assert_eq!(entry_data.community_mint, Some(community_mint));
+}
+
+#[tokio::test]
+async fn address_owner_can_claim_an_entry() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let program_instance = Keypair::new();
+    process(&mut context, &[add_entry_ix(registry_config, payer, program_instance.pubkey(), realm("data"))], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &program_instance.pubkey()).0;
+
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, program_instance.pubkey(), system_program::id(), ProofMethod::None)], &[&program_instance]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+
+    process(&mut context, &[claim_entry_ix(registry_config, entry, program_instance.pubkey(), system_program::id(), ProofMethod::AddressSignature)], &[&program_instance])
+        .await
+        .unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.creator, program_instance.pubkey());
+    assert_eq!(entry_data.update_authority, program_instance.pubkey());
+    assert_eq!(entry_data.proof, ProofMethod::AddressSignature);
+    assert_eq!(entry_data.badges, 0);
+}
+
+#[tokio::test]
+async fn update_keeps_verification_only_while_content_is_unchanged() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["audited"]).await;
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await.unwrap();
+
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("data"))], &[]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.badges, 1);
+
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("swapped after review"))], &[]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.verified_at, None);
+    assert_eq!(entry_data.verified_hash, None);
+    assert_eq!(entry_data.attestation_count, 0);
+    assert_eq!(entry_data.badges, 0);
+    assert!(entry_data.badge_grants.is_empty());
+    assert_error(process(&mut context, &[assert_verified_ix(registry_config, entry, 0)], &[]).await, ErrorCode::BadgeNotGranted);
+}
+
+#[tokio::test]
+async fn delegates_can_update_entries_others_cannot() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    let delegate = Keypair::new();
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, delegate.pubkey(), payer, realm("data"))], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), payer, DelegateScope::Edit, None)], &[]).await.unwrap();
+    let entry_delegate = delegate_address(&entry, &delegate.pubkey()).0;
+    // the updater pays for the entry growth
+    process(&mut context, &[system_instruction::transfer(&payer, &delegate.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let maintained = realm("maintained by a delegate");
+    process(&mut context, &[delegated_update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, maintained)], &[&delegate])
+        .await
+        .unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.data.description, "maintained by a delegate");
+}
+
+#[tokio::test]
+async fn realm_authority_can_claim_its_realm_entry() {
+    let mut program_test = program_test();
+    let realm_address = Pubkey::new_unique();
+    let realm_authority = Keypair::new();
+    add_spl_governance_account(&mut program_test, realm_address, realm_account_data("realm", Pubkey::new_unique(), Some(realm_authority.pubkey())));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let data = Realm { name: "realm".to_string(), program_id: spl_governance_id(), ..Realm::default() };
+    process(&mut context, &[add_entry_ix(registry_config, payer, realm_address, data)], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &realm_address).0;
+    let stranger = Keypair::new();
+
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, stranger.pubkey(), realm_address, ProofMethod::RealmAuthority)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, stranger.pubkey(), realm_address, ProofMethod::AddressSignature)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+    // the realm account has to be the one the entry registers
+    let result = process(
+        &mut context,
+        &[claim_entry_ix(registry_config, entry, realm_authority.pubkey(), system_program::id(), ProofMethod::RealmAuthority)],
+        &[&realm_authority],
+    )
+    .await;
+    assert_error(result, ErrorCode::InvalidProof);
+
+    process(&mut context, &[claim_entry_ix(registry_config, entry, realm_authority.pubkey(), realm_address, ProofMethod::RealmAuthority)], &[&realm_authority])
+        .await
+        .unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.creator, realm_authority.pubkey());
+    assert_eq!(entry_data.proof, ProofMethod::RealmAuthority);
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, entry_data.data)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn executable_registry_checks_the_program_on_add_and_update() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.require_executable = true).await;
+    let address = Pubkey::new_unique();
+
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, address, realm("data"))], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+    let not_a_program = Realm { program_id: payer, ..realm("data") };
+    let mut ix = add_entry_ix(registry_config, payer, address, not_a_program);
+    ix.accounts.push(AccountMeta::new_readonly(payer, false));
+    assert_error(process(&mut context, &[ix], &[]).await, ErrorCode::InvalidProgram);
+
+    let mut ix = add_entry_ix(registry_config, payer, address, realm("data"));
+    ix.accounts.push(AccountMeta::new_readonly(governance_registry::id(), false));
+    process(&mut context, &[ix], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &address).0;
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.deployment, Some(ProgramDeployment { upgrade_authority: None, deploy_slot: None }));
+
+    // updates are checked again since they may point the entry at another program
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("updated"))], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+}
+
+#[tokio::test]
+async fn check_verification_revokes_redeployed_and_closed_programs() {
+    let mut program_test = program_test();
+    let (program, program_data) = add_upgradeable_program(&mut program_test, 42);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    let entry = add_entry(&mut context, registry_config, Realm { program_id: program, ..realm("data") }).await;
+
+    let result = process(&mut context, &[verify_program_ix(registry_config, entry, payer, payer, 0, program, Pubkey::new_unique())], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    process(&mut context, &[verify_program_ix(registry_config, entry, payer, payer, 0, program, program_data)], &[]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.verified_deploy_slot, Some(42));
+
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.badges, 1);
+    let result = process(&mut context, &[check_verification_ix(registry_config, entry, program, Pubkey::new_unique())], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    let result = process(&mut context, &[check_verification_ix(registry_config, entry, governance_registry::id(), program_data)], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+
+    context.set_account(&program_data, &program_data_account(43, Pubkey::new_unique()).into());
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.verified_at, None);
+    assert_eq!(entry_data.verified_deploy_slot, None);
+    assert_eq!(entry_data.badges, 0);
+    assert!(entry_data.badge_grants.is_empty());
+
+    // closing the program empties its program data
+    let verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    process(&mut context, &[verify_program_ix(registry_config, entry, verifier.pubkey(), payer, 0, program, program_data)], &[&verifier]).await.unwrap();
+    let closed = Account { lamports: 0, data: vec![], owner: system_program::id(), executable: false, rent_epoch: 0 };
+    context.set_account(&program_data, &closed.into());
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.verified_at, None);
+}
+
+#[tokio::test]
+async fn update_authority_can_be_handed_over() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    let maintainer = Keypair::new();
+
+    let result = process(&mut context, &[set_update_authority_ix(registry_config, entry, maintainer.pubkey(), maintainer.pubkey())], &[&maintainer]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, maintainer.pubkey())], &[]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.update_authority, maintainer.pubkey());
+    assert_eq!(entry_data.creator, payer);
+
+    // same sized data so the maintainer does not pay for growth
+    process(&mut context, &[update_entry_ix(registry_config, entry, maintainer.pubkey(), payer, realm("atad"))], &[&maintainer]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.data.description, "atad");
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("data"))], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn registry_authority_updates_entries_only_when_allowed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("moderated"))], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let registry_config = init(&mut context, |ix| {
+        ix.name = "moderated".to_string();
+        ix.authority_can_update = true;
+    })
+    .await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, realm("moderated"))], &[]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.data.description, "moderated");
+
+    // other signers still need the update authority
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, stranger.pubkey(), payer, realm("squatted"))], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn removed_delegates_lose_their_rights() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    let delegate = Keypair::new();
+    let stranger = Keypair::new();
+    let entry_delegate = delegate_address(&entry, &delegate.pubkey()).0;
+
+    // funded so the record can be paid for and only the authority check fails
+    process(&mut context, &[system_instruction::transfer(&payer, &stranger.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let result = process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), stranger.pubkey(), DelegateScope::Edit, None)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), payer, DelegateScope::Edit, None)], &[]).await.unwrap();
+    // same sized data so the delegate does not pay for growth
+    process(&mut context, &[delegated_update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, realm("atad"))], &[&delegate]).await.unwrap();
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.data.description, "atad");
+
+    let result = process(&mut context, &[remove_delegate_ix(registry_config, entry, entry_delegate, delegate.pubkey())], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let (_, delegate_lamports) = account_size(&mut context, entry_delegate).await;
+    let balance = context.banks_client.get_balance(payer).await.unwrap();
+    process(&mut context, &[remove_delegate_ix(registry_config, entry, entry_delegate, payer)], &[]).await.unwrap();
+    assert!(context.banks_client.get_account(entry_delegate).await.unwrap().is_none());
+    // the record rent goes back to the update authority, minus the fee it paid as fee payer
+    assert_eq!(context.banks_client.get_balance(payer).await.unwrap(), balance + delegate_lamports - 5000);
+
+    let result = process(&mut context, &[delegated_update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, realm("data"))], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn delegate_scope_and_expiry_are_enforced() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    let (editor, remover, expired) = (Keypair::new(), Keypair::new(), Keypair::new());
+    process(
+        &mut context,
+        &[
+            add_delegate_ix(registry_config, entry, editor.pubkey(), payer, DelegateScope::Edit, None),
+            add_delegate_ix(registry_config, entry, remover.pubkey(), payer, DelegateScope::EditAndRemove, None),
+            add_delegate_ix(registry_config, entry, expired.pubkey(), payer, DelegateScope::EditAndRemove, Some(1)),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let editor_delegate = delegate_address(&entry, &editor.pubkey()).0;
+    let remover_delegate = delegate_address(&entry, &remover.pubkey()).0;
+    let expired_delegate = delegate_address(&entry, &expired.pubkey()).0;
+
+    let result = process(&mut context, &[delegated_update_entry_ix(registry_config, entry, expired.pubkey(), payer, expired_delegate, realm("data"))], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    // a delegate record only speaks for its own delegate
+    let result = process(&mut context, &[delegated_update_entry_ix(registry_config, entry, expired.pubkey(), payer, editor_delegate, realm("data"))], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[remove_entry_ix(registry_config, entry, editor.pubkey(), payer, editor_delegate)], &[&editor]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[remove_entry_ix(registry_config, entry, expired.pubkey(), payer, expired_delegate)], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[remove_entry_ix(registry_config, entry, remover.pubkey(), payer, remover_delegate)], &[&remover]).await.unwrap();
+    assert!(context.banks_client.get_account(entry).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn update_config_changes_only_the_given_fields() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entry_size = 500).await;
+
+    let treasury = Pubkey::new_unique();
+    let ix = config_update(|ix| {
+        ix.realm_seed = Some("renamed".to_string());
+        ix.strict_realm_validation = Some(true);
+        ix.max_entries = Some(7);
+        ix.moderation_treasury = Some(Some(treasury));
+        ix.verification_threshold = Some(2);
+        ix.verification_ttl = Some(3600);
+    });
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.realm_seed, "renamed");
+    assert!(config.strict_realm_validation);
+    assert!(config.permissionless_add);
+    assert_eq!(config.max_entries, 7);
+    assert_eq!(config.max_entry_size, 500);
+    assert_eq!(config.moderation_treasury, Some(treasury));
+    assert_eq!(config.verification_threshold, 2);
+    assert_eq!(config.verification_ttl, 3600);
+
+    let stranger = Keypair::new();
+    let ix = config_update(|ix| ix.verification_threshold = Some(1));
+    let result = process(&mut context, &[update_config_ix(registry_config, stranger.pubkey(), ix)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.verification_threshold, 2);
+}
+
+#[tokio::test]
+async fn realm_seed_is_locked_once_the_registry_has_entries() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, realm("realm")).await;
+
+    let ix = config_update(|ix| ix.realm_seed = Some("renamed".to_string()));
+    let result = process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await;
+    assert_error(result, ErrorCode::SeedLocked);
+
+    // the seed is free again once the last entry is gone
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    let ix = config_update(|ix| ix.realm_seed = Some("moved".to_string()));
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.realm_seed, "moved");
+}
+
+#[tokio::test]
+async fn max_entries_caps_the_registry_until_an_entry_is_removed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entries = 1).await;
+    let entry = add_entry(&mut context, registry_config, realm("first")).await;
+
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, Pubkey::new_unique(), realm("second"))], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 0);
+    add_entry(&mut context, registry_config, realm("second")).await;
+
+    // raising the cap makes room without removing anything
+    process(&mut context, &[update_config_ix(registry_config, payer, config_update(|ix| ix.max_entries = Some(2)))], &[]).await.unwrap();
+    add_entry(&mut context, registry_config, realm("third")).await;
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, Pubkey::new_unique(), realm("fourth"))], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 2);
+}
+
+#[tokio::test]
+async fn verifier_attests_once_per_round() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+
+    // a second payer keeps the transaction distinct from the first attestation
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let ix = verify_entry_ix(registry_config, entry, verifier.pubkey(), other_payer.pubkey(), 0);
+    let result = process(&mut context, &[ix], &[&verifier, &other_payer]).await;
+    assert_error(result, ErrorCode::AlreadyAttested);
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.attestation_count, 1);
+}
+
+#[tokio::test]
+async fn re_created_entry_does_not_inherit_attestations() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let address = Pubkey::new_unique();
+    let (entry, _) = entry_address(&registry_config, &address);
+    process(&mut context, &[add_entry_ix(registry_config, payer, address, realm("first"))], &[]).await.unwrap();
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let first_round = fetch::<Entry>(&mut context, entry).await.verification_round;
+
+    // attested entries can be removed, their attestations stay behind until withdrawn
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    process(&mut context, &[add_entry_ix(registry_config, payer, address, realm("second"))], &[]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert!(entry_data.verification_round > first_round);
+    assert_eq!(entry_data.attestation_count, 0);
+    assert_eq!(entry_data.badges, 0);
+    let result = process(&mut context, &[assert_verified_ix(registry_config, entry, 0)], &[]).await;
+    assert_error(result, ErrorCode::BadgeNotGranted);
+
+    // withdrawing the stale attestation refunds its rent and leaves the new entry alone
+    let attestation = attestation_address(&entry, &verifier.pubkey(), 0).0;
+    process(&mut context, &[unverify_entry_ix(registry_config, entry, verifier.pubkey(), 0, payer)], &[&verifier]).await.unwrap();
+    assert!(context.banks_client.get_account(attestation).await.unwrap().is_none());
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.attestation_count, 0);
+
+    // a stale attestation left in place is taken over by the next attestation, its rent stays with the first payer
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let ix = verify_entry_ix(registry_config, entry, verifier.pubkey(), other_payer.pubkey(), 0);
+    process(&mut context, &[ix], &[&verifier, &other_payer]).await.unwrap();
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    process(&mut context, &[add_entry_ix(registry_config, payer, address, realm("third"))], &[]).await.unwrap();
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.attestation_count, 1);
+    assert_eq!(entry_data.badges, 1);
+    let record: Attestation = fetch(&mut context, attestation).await;
+    assert_eq!(record.round, entry_data.verification_round);
+    assert_eq!(record.rent_payer, other_payer.pubkey());
+}
+
+#[tokio::test]
+async fn pre_badge_entry_migrates_in_place() {
+    let mut program_test = program_test();
+    let (registry_config, _) = pda(&[b"registry-config", b"registry"]);
+    let address = Pubkey::new_unique();
+    let (entry, bump) = entry_address(&registry_config, &address);
+    let legacy = Entry {
+        bump,
+        registry: registry_config,
+        address,
+        creator: Pubkey::new_unique(),
+        created_at: 1,
+        // the version byte held `is_verified` before badges
+        version: 1,
+        verified_at: Some(2),
+        schema_version: 0,
+        data: realm("legacy"),
+        community_mint: None,
+        proof: ProofMethod::None,
+        deployment: None,
+        verified_deploy_slot: None,
+        verified_hash: None,
+        update_authority: Pubkey::new_unique(),
+        rent_payer: Pubkey::new_unique(),
+        attestation_count: 1,
+        verification_round: 7,
+        badges: 0,
+        badge_grants: vec![],
+        delegate_generation: 0,
+    };
+    let mut data = vec![];
+    legacy.try_serialize(&mut data).unwrap();
+    // the pre-badge layout ends with a u32 round, before the badge fields and the delegate generation
+    data.truncate(data.len() - 20);
+    let legacy_size = data.len();
+    program_test.add_account(entry, program_account(data));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    init(&mut context, |_| {}).await;
+
+    process(&mut context, &[migrate_entry_ix(registry_config, entry, payer)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, legacy_size + 20 + BADGE_GRANT_SIZE);
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: Entry = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.version, 2);
+    assert_eq!(entry_data.verification_round, 7);
+    assert_eq!(entry_data.badges, 1);
+    assert_eq!(entry_data.badge_grants[0].attestations, 1);
+    assert_eq!(entry_data.badge_grants[0].granted_at, Some(2));
+    assert_eq!(entry_data.data.description, "legacy");
+    // later rounds stay ahead of the round the entry kept on its own
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.verification_round, 7);
+
+    // a second payer keeps the transaction distinct from the migration
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let result = process(&mut context, &[migrate_entry_ix(registry_config, entry, other_payer.pubkey())], &[&other_payer]).await;
+    assert_error(result, ErrorCode::EntryMigrated);
+}
+
+#[tokio::test]
+async fn entries_from_before_registry_seeds_are_not_migrated() {
+    let mut program_test = program_test();
+    let address = Pubkey::new_unique();
+    // the baseline layout: no bump or registry, and the entry sits at [realm_seed, address]
+    let (entry, _) = pda(&[REALM_SEED.as_bytes(), address.as_ref()]);
+    let mut data = Entry::discriminator().to_vec();
+    (address, Pubkey::new_unique(), 1i64, true, Some(2i64), 0u8, realm("baseline")).serialize(&mut data).unwrap();
+    data.resize(1024, 0);
+    program_test.add_account(entry, program_account(data));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+
+    let result = process(&mut context, &[migrate_entry_ix(registry_config, entry, payer)], &[]).await;
+    assert_error(result, ErrorCode::UnsupportedEntryLayout);
+}
+
+#[tokio::test]
+async fn revoked_verifier_cannot_renew_a_badge() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.verification_ttl = 3600).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let expires_at = fetch::<Entry>(&mut context, entry).await.badge_grants[0].expires_at;
+
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, payer, system_program::id())], &[]).await.unwrap();
+    let result = process(&mut context, &[renew_verification_ix(registry_config, entry, &[verifier.pubkey()], 0)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<Entry>(&mut context, entry).await.badge_grants[0].expires_at, expires_at);
+}
+
+#[tokio::test]
+async fn revoked_co_signer_does_not_count_towards_a_renewal() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| {
+        ix.verification_threshold = 2;
+        ix.verification_ttl = 3600;
+    })
+    .await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let (first, second) = (Keypair::new(), Keypair::new());
+    grant_role(&mut context, registry_config, Role::Verifier, first.pubkey()).await;
+    let second_role = grant_role(&mut context, registry_config, Role::Verifier, second.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, realm("data")).await;
+    for verifier in [&first, &second] {
+        process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[verifier]).await.unwrap();
+    }
+    let ix = renew_verification_ix(registry_config, entry, &[second.pubkey(), first.pubkey()], 0);
+    process(&mut context, &[ix], &[&first, &second]).await.unwrap();
+
+    process(&mut context, &[revoke_role_ix(registry_config, second_role, payer, system_program::id())], &[]).await.unwrap();
+    let ix = renew_verification_ix(registry_config, entry, &[first.pubkey(), second.pubkey()], 0);
+    let result = process(&mut context, &[ix], &[&first, &second]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
@@ -1,7 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use std::convert::TryInto;
 
-declare_id!("govHvVVCZsdJLynaFJdqEWBU9AbJ4aHYdZsWno114V9");
+declare_id!("prqPLfifyac9Ei262fgHzd7XNTEPmfkCgMUDG6icaeG");
+// serialized size of every GovernanceProgramAccount field except `data`, with all options set
+const ENTRY_BASE_SIZE: usize = 8 + 8 + 8 + 43 + 33 + 32;
 
 #[program]
 pub mod permissionless_registry {
@@ -17,21 +24,60 @@ pub mod permissionless_registry {
         entry_account.data = entry_data;
         entry_account.created_at = timestamp;
         entry_account.updated_at = timestamp;
+        entry_account.rent_payer = *ctx.accounts.user.key;
+        if ix.validate_program {
+            entry_account.deployment = Some(program_deployment(&entry_account.data.program_address, ctx.remaining_accounts)?);
+        }
         Ok(())
     }
 
-    pub fn update_entry(ctx: Context<UpdateEntry>, entry_data: EntryData) -> ProgramResult {
+    pub fn update_entry(ctx: Context<UpdateEntry>, ix: UpdateEntryIx) -> ProgramResult {
+        resize_entry(
+            &ctx.accounts.entry.to_account_info(),
+            ENTRY_BASE_SIZE + ix.entry.try_to_vec().unwrap().len(),
+            &ctx.accounts.user,
+            &ctx.accounts.rent_payer,
+            &ctx.accounts.system_program,
+        )?;
         let clock = Clock::get().unwrap();
         let timestamp = clock.unix_timestamp;
 
+        let mut entry_data = ix.entry;
         let entry_account = &mut ctx.accounts.entry;
+        // ownership only changes through transfer_entry_authority
+        entry_data.authority = entry_account.data.authority;
+        if entry_account.data.program_address != entry_data.program_address {
+            // captured deployment no longer describes the referenced program
+            entry_account.deployment = None;
+        }
         entry_account.data = entry_data;
         entry_account.updated_at = timestamp;
+        if ix.validate_program {
+            entry_account.deployment = Some(program_deployment(&entry_account.data.program_address, ctx.remaining_accounts)?);
+        }
+        Ok(())
+    }
+
+    pub fn transfer_entry_authority(ctx: Context<TransferEntryAuthority>, ix: TransferEntryAuthorityIx) -> ProgramResult {
+        let entry_account = &mut ctx.accounts.entry;
+        if ix.require_acceptance {
+            entry_account.pending_authority = Some(*ctx.accounts.new_authority.key);
+        } else {
+            entry_account.data.authority = *ctx.accounts.new_authority.key;
+            entry_account.pending_authority = None;
+        }
+        Ok(())
+    }
+
+    pub fn accept_entry_authority(ctx: Context<AcceptEntryAuthority>) -> ProgramResult {
+        let entry_account = &mut ctx.accounts.entry;
+        entry_account.data.authority = *ctx.accounts.new_authority.key;
+        entry_account.pending_authority = None;
         Ok(())
     }
 
     pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.user.to_account_info()).unwrap();
+        ctx.accounts.entry.close(ctx.accounts.rent_payer.to_account_info()).unwrap();
         Ok(())
     }
 }
@@ -43,6 +89,18 @@ pub struct AddEntryIx {
     pub entry: EntryData,
     pub bump: u8,
     pub seed: [u8; 32],
+    pub validate_program: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateEntryIx {
+    pub entry: EntryData,
+    pub validate_program: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct TransferEntryAuthorityIx {
+    pub require_acceptance: bool,
 }
 
 ///////////////// Contexts /////////////////
@@ -53,7 +111,8 @@ pub struct AddEntry<'info> {
     #[account(
         init,
         payer = user,
-        space = 1024,
+        // sized to the payload, update_entry reallocs when it changes
+        space = ENTRY_BASE_SIZE + ix.entry.try_to_vec().unwrap().len(),
         seeds = [b"governance-program".as_ref(), ix.seed.as_ref()],
         bump = ix.bump,
     )]
@@ -64,9 +123,30 @@ pub struct AddEntry<'info> {
 
 #[derive(Accounts)]
 pub struct UpdateEntry<'info> {
+    #[account(mut)]
+    pub entry: Account<'info, GovernanceProgramAccount>,
+    #[account(mut, constraint = entry.data.authority == *user.key @ ErrorCode::InsufficientAuthority)]
+    pub user: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferEntryAuthority<'info> {
+    #[account(mut)]
     pub entry: Account<'info, GovernanceProgramAccount>,
     #[account(constraint = entry.data.authority == *user.key @ ErrorCode::InsufficientAuthority)]
     pub user: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptEntryAuthority<'info> {
+    #[account(mut)]
+    pub entry: Account<'info, GovernanceProgramAccount>,
+    #[account(constraint = entry.pending_authority == Some(*new_authority.key) @ ErrorCode::InvalidPendingAuthority)]
+    pub new_authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -75,6 +155,8 @@ pub struct RemoveEntry<'info> {
     pub entry: Account<'info, GovernanceProgramAccount>,
     #[account(constraint = entry.data.authority == *user.key @ ErrorCode::InsufficientAuthority)]
     pub user: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
 }
 
 ///////////////// DATA /////////////////
@@ -95,10 +177,100 @@ pub struct GovernanceProgramAccount {
     pub data: EntryData,
     pub created_at: i64,
     pub updated_at: i64,
+    pub deployment: Option<ProgramDeployment>,
+    pub pending_authority: Option<Pubkey>,
+    pub rent_payer: Pubkey,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ProgramDeployment {
+    pub upgrade_authority: Option<Pubkey>,
+    pub deploy_slot: Option<u64>,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProgramData.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+pub fn program_deployment(program_id: &Pubkey, remaining_accounts: &[AccountInfo]) -> std::result::Result<ProgramDeployment, ProgramError> {
+    let program = remaining_accounts
+        .iter()
+        .find(|account| account.key == program_id)
+        .ok_or(ErrorCode::InvalidProgram)?;
+    if !program.executable {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    if program.owner != &bpf_loader_upgradeable::id() {
+        return Ok(ProgramDeployment { upgrade_authority: None, deploy_slot: None });
+    }
+    // UpgradeableLoaderState::Program { programdata_address: Pubkey }
+    let programdata_address = {
+        let data = program.try_borrow_data()?;
+        if data.len() < 36 || data[0..4] != [2, 0, 0, 0] {
+            return Err(ErrorCode::InvalidProgram.into());
+        }
+        Pubkey::new_from_array(data[4..36].try_into().unwrap())
+    };
+    let program_data = remaining_accounts
+        .iter()
+        .find(|account| *account.key == programdata_address)
+        .ok_or(ErrorCode::InvalidProgramData)?;
+    let (slot, upgrade_authority) = program_data_header(program_data)?;
+    Ok(ProgramDeployment { upgrade_authority, deploy_slot: Some(slot) })
+}
+
+// growth is paid by `payer`, a shrink refunds `rent_payer`
+pub fn resize_entry<'info>(entry: &AccountInfo<'info>, size: usize, payer: &AccountInfo<'info>, rent_payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> ProgramResult {
+    let current_size = entry.data_len();
+    if size == current_size {
+        return Ok(());
+    }
+    if size > current_size + MAX_PERMITTED_DATA_INCREASE {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    let rent = Rent::get()?.minimum_balance(size);
+    if rent > entry.lamports() {
+        invoke(
+            &system_instruction::transfer(payer.key, entry.key, rent - entry.lamports()),
+            &[payer.clone(), entry.clone(), system_program.clone()],
+        )?;
+    } else {
+        let refund = entry.lamports() - rent;
+        **entry.try_borrow_mut_lamports()? -= refund;
+        **rent_payer.try_borrow_mut_lamports()? += refund;
+    }
+    // zero the grown tail, it may still hold bytes from an earlier shrink in this transaction
+    entry.realloc(size, true)
 }
 
 #[error]
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry")]
     InsufficientAuthority,
+    #[msg("Referenced account is not an executable program")]
+    InvalidProgram,
+    #[msg("Program data account does not match the program")]
+    InvalidProgramData,
+    #[msg("Signer is not the pending authority for this entry")]
+    InvalidPendingAuthority,
+    #[msg("Entry data exceeds the maximum entry size")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer")]
+    InvalidRentRecipient,
 }
\ No newline at end of file
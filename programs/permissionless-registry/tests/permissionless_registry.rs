@@ -0,0 +1,135 @@
+use anchor_lang::{AccountDeserialize, AnchorSerialize, InstructionData, ToAccountMetas};
+use permissionless_registry::{accounts, instruction, AddEntryIx, EntryData, ErrorCode, GovernanceProgramAccount, UpdateEntryIx};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const ENTRY_BASE_SIZE: usize = 8 + 8 + 8 + 43 + 33 + 32;
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new("permissionless_registry", permissionless_registry::id(), processor!(permissionless_registry::entry))
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+fn entry_data(description: &str) -> EntryData {
+    EntryData {
+        name: "program".to_string(),
+        description: description.to_string(),
+        program_address: Pubkey::new_unique(),
+        ..EntryData::default()
+    }
+}
+
+async fn add_entry(context: &mut ProgramTestContext, data: EntryData) -> Pubkey {
+    let seed = Pubkey::new_unique().to_bytes();
+    let (entry, bump) = Pubkey::find_program_address(&[b"governance-program", &seed], &permissionless_registry::id());
+    let ix = Instruction {
+        program_id: permissionless_registry::id(),
+        accounts: accounts::AddEntry { entry, user: context.payer.pubkey(), system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::AddEntry { ix: AddEntryIx { entry: data, bump, seed, validate_program: false } }.data(),
+    };
+    process(context, &[ix], &[]).await.unwrap();
+    entry
+}
+
+fn update_entry(entry: Pubkey, user: Pubkey, rent_payer: Pubkey, data: EntryData) -> Instruction {
+    Instruction {
+        program_id: permissionless_registry::id(),
+        accounts: accounts::UpdateEntry { entry, user, rent_payer, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::UpdateEntry { ix: UpdateEntryIx { entry: data, validate_program: false } }.data(),
+    }
+}
+
+async fn entry_size(context: &mut ProgramTestContext, entry: Pubkey) -> (usize, u64) {
+    let account = context.banks_client.get_account(entry).await.unwrap().unwrap();
+    (account.data.len(), account.lamports)
+}
+
+#[tokio::test]
+async fn update_entry_grows_and_shrinks_the_account() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let entry = add_entry(&mut context, entry_data("short")).await;
+
+    let grown = entry_data(&"x".repeat(600));
+    process(&mut context, &[update_entry(entry, payer, payer, grown.clone())], &[]).await.unwrap();
+    let (size, lamports) = entry_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + grown.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let account: GovernanceProgramAccount = fetch(&mut context, entry).await;
+    assert_eq!(account.data.description, grown.description);
+
+    let shrunk = entry_data("tiny");
+    process(&mut context, &[update_entry(entry, payer, payer, shrunk.clone())], &[]).await.unwrap();
+    let (size, lamports) = entry_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + shrunk.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let account: GovernanceProgramAccount = fetch(&mut context, entry).await;
+    assert_eq!(account.data.description, "tiny");
+}
+
+#[tokio::test]
+async fn shrink_then_grow_in_one_transaction_zeroes_the_tail() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let entry = add_entry(&mut context, entry_data(&"x".repeat(300))).await;
+
+    let regrown = entry_data(&"y".repeat(300));
+    process(
+        &mut context,
+        &[update_entry(entry, payer, payer, entry_data("")), update_entry(entry, payer, payer, regrown.clone())],
+        &[],
+    )
+    .await
+    .unwrap();
+    let account: GovernanceProgramAccount = fetch(&mut context, entry).await;
+    assert_eq!(account.data.description, regrown.description);
+    let (size, _) = entry_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + regrown.try_to_vec().unwrap().len());
+}
+
+#[tokio::test]
+async fn shrink_refunds_only_the_rent_payer() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let entry = add_entry(&mut context, entry_data(&"x".repeat(300))).await;
+
+    let result = process(&mut context, &[update_entry(entry, payer, Pubkey::new_unique(), entry_data("tiny"))], &[]).await;
+    assert_error(result, ErrorCode::InvalidRentRecipient);
+}
+
+#[tokio::test]
+async fn update_entry_requires_the_entry_authority() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let entry = add_entry(&mut context, entry_data("short")).await;
+
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[update_entry(entry, stranger.pubkey(), payer, entry_data("longer description"))], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
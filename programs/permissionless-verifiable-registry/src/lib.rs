@@ -1,8 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    system_instruction,
+};
+use std::convert::TryInto;
 
-declare_id!("tkJqbNU3dk3eCwtT4EjSFisxza8JcuKSbDNbTZDQv76");
+declare_id!("govHvVVCZsdJLynaFJdqEWBU9AbJ4aHYdZsWno114V9");
 const CONFIG_PREFIX: &str = "registry-config";
+const MAX_MULTISIG_SIGNERS: usize = 10;
+// serialized size of every EntryData field except `data`, with all options set
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 1 + 9 + 1 + 1 + 32 + 1 + 8 + 8;
+const CHALLENGE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 4 + 1 + 1 + 8 + 8 + 1;
+const VOTE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 1 + 8;
 
 #[program]
 pub mod permissionless_verifiable_registry {
@@ -10,28 +22,181 @@ pub mod permissionless_verifiable_registry {
 
     pub fn init(ctx: Context<Init>, ix: InitIx) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.bump = ix.bump;
+        registry_config.name = ix.name;
         registry_config.authority = *ctx.accounts.authority.key;
         registry_config.entry_seed = ix.entry_seed;
+        registry_config.require_proof = ix.require_proof;
+        registry_config.permissionless_add = ix.permissionless_add;
+        registry_config.max_entries = ix.max_entries;
+        registry_config.max_entry_size = ix.max_entry_size;
+        registry_config.moderation_treasury = ix.moderation_treasury;
+        if ix.voter_reward_pct > 100 {
+            return Err(ErrorCode::InvalidRewardShare.into());
+        }
+        if ix.vote_period <= 0 {
+            return Err(ErrorCode::InvalidVotePeriod.into());
+        }
+        registry_config.challenge_stake = ix.challenge_stake;
+        registry_config.vote_period = ix.vote_period;
+        registry_config.vote_quorum = ix.vote_quorum;
+        registry_config.voter_reward_pct = ix.voter_reward_pct;
+        registry_config.vote_mint = ix.vote_mint;
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
         registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn propose_authority(ctx: Context<ProposeAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = Some(*ctx.accounts.new_authority.key);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = None;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn set_multisig(ctx: Context<SetMultisig>, ix: SetMultisigIx) -> ProgramResult {
+        if ix.signers.len() > MAX_MULTISIG_SIGNERS || ix.threshold as usize > ix.signers.len() {
+            return Err(ErrorCode::InvalidMultisig.into());
+        }
+        for (i, signer) in ix.signers.iter().enumerate() {
+            if ix.signers[..i].contains(signer) {
+                return Err(ErrorCode::InvalidMultisig.into());
+            }
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.multisig_signers = ix.signers;
+        registry_config.multisig_threshold = ix.threshold;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn update_config(ctx: Context<UpdateConfig>, ix: UpdateConfigIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if let Some(permissionless_add) = ix.permissionless_add {
+            config_changed(registry, "permissionless_add", &registry_config.permissionless_add, &permissionless_add);
+            registry_config.permissionless_add = permissionless_add;
+        }
+        if let Some(entry_seed) = ix.entry_seed {
+            if registry_config.entry_count > 0 {
+                return Err(ErrorCode::SeedLocked.into());
+            }
+            config_changed(registry, "entry_seed", &registry_config.entry_seed.clone(), &entry_seed);
+            registry_config.entry_seed = entry_seed;
+        }
+        if let Some(require_proof) = ix.require_proof {
+            config_changed(registry, "require_proof", &registry_config.require_proof, &require_proof);
+            registry_config.require_proof = require_proof;
+        }
+        if let Some(max_entries) = ix.max_entries {
+            config_changed(registry, "max_entries", &registry_config.max_entries, &max_entries);
+            registry_config.max_entries = max_entries;
+        }
+        if let Some(max_entry_size) = ix.max_entry_size {
+            config_changed(registry, "max_entry_size", &registry_config.max_entry_size, &max_entry_size);
+            registry_config.max_entry_size = max_entry_size;
+        }
+        if let Some(moderation_treasury) = ix.moderation_treasury {
+            config_changed(
+                registry,
+                "moderation_treasury",
+                &format!("{:?}", registry_config.moderation_treasury),
+                &format!("{:?}", moderation_treasury),
+            );
+            registry_config.moderation_treasury = moderation_treasury;
+        }
+        if let Some(challenge_stake) = ix.challenge_stake {
+            config_changed(registry, "challenge_stake", &registry_config.challenge_stake, &challenge_stake);
+            registry_config.challenge_stake = challenge_stake;
+        }
+        if let Some(vote_period) = ix.vote_period {
+            if vote_period <= 0 {
+                return Err(ErrorCode::InvalidVotePeriod.into());
+            }
+            config_changed(registry, "vote_period", &registry_config.vote_period, &vote_period);
+            registry_config.vote_period = vote_period;
+        }
+        if let Some(vote_quorum) = ix.vote_quorum {
+            config_changed(registry, "vote_quorum", &registry_config.vote_quorum, &vote_quorum);
+            registry_config.vote_quorum = vote_quorum;
+        }
+        if let Some(voter_reward_pct) = ix.voter_reward_pct {
+            if voter_reward_pct > 100 {
+                return Err(ErrorCode::InvalidRewardShare.into());
+            }
+            config_changed(registry, "voter_reward_pct", &registry_config.voter_reward_pct, &voter_reward_pct);
+            registry_config.voter_reward_pct = voter_reward_pct;
+        }
+        if let Some(vote_mint) = ix.vote_mint {
+            config_changed(registry, "vote_mint", &format!("{:?}", registry_config.vote_mint), &format!("{:?}", vote_mint));
+            registry_config.vote_mint = vote_mint;
+        }
         Ok(())
     }
     
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn add_entry(ctx: Context<AddEntry>, ix: AddEntryIx) -> ProgramResult {
+        entry_size(&ctx.accounts.registry_config, &ix.data)?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if registry_config.max_entries > 0 && registry_config.entry_count >= registry_config.max_entries as u64 {
+            return Err(ErrorCode::RegistryFull.into());
+        }
+        registry_config.entry_count += 1;
         let entry = &mut ctx.accounts.entry;
+        entry.bump = ix.bump;
+        entry.registry = *ctx.accounts.registry_config.to_account_info().key;
         entry.address = ix.address;
         entry.data = ix.data;
         entry.schema_version = ix.schema_version;
         entry.creator = *ctx.accounts.creator.key;
+        entry.rent_payer = *ctx.accounts.creator.key;
         entry.created_at = Clock::get().unwrap().unix_timestamp;
         entry.is_verified = false; // default to false
+        if ctx.accounts.registry_config.require_proof && ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        assert_proof(ix.proof, &entry.address, &ctx.accounts.creator, ctx.remaining_accounts)?;
+        entry.proof = ix.proof;
+        // the entry side stakes what a challenger has to, so whichever side loses a challenge forfeits its stake
+        let deposit = ctx.accounts.registry_config.challenge_stake;
+        if deposit > 0 {
+            invoke(
+                &system_instruction::transfer(ctx.accounts.creator.key, ctx.accounts.entry.to_account_info().key, deposit),
+                &[
+                    ctx.accounts.creator.to_account_info(),
+                    ctx.accounts.entry.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+        ctx.accounts.entry.deposit = deposit;
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn verify_entry(ctx: Context<VerifyEntry>) -> ProgramResult {
         let entry = &mut ctx.accounts.entry;
         entry.is_verified = true;
@@ -39,6 +204,7 @@ pub mod permissionless_verifiable_registry {
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn unverify_entry(ctx: Context<UnverifyEntry>) -> ProgramResult {
         let entry = &mut ctx.accounts.entry;
         entry.is_verified = false;
@@ -46,8 +212,215 @@ pub mod permissionless_verifiable_registry {
         Ok(())
     }
 
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.authority.to_account_info()).unwrap();
+        let entry = &ctx.accounts.entry;
+        if entry.challenged {
+            return Err(ErrorCode::EntryChallenged.into());
+        }
+        let authority = &ctx.accounts.authority;
+        let moderated = entry.creator != *authority.key;
+        if *ctx.accounts.rent_recipient.key != rent_recipient(&ctx.accounts.registry_config, &entry.rent_payer, moderated) {
+            return Err(ErrorCode::InvalidRentRecipient.into());
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.entry_count = registry_config.entry_count.saturating_sub(1);
+        ctx.accounts.entry.close(ctx.accounts.rent_recipient.to_account_info()).unwrap();
+        Ok(())
+    }
+
+    pub fn challenge_entry(ctx: Context<ChallengeEntry>, ix: ChallengeEntryIx) -> ProgramResult {
+        let stake = ctx.accounts.registry_config.challenge_stake;
+        if stake == 0 {
+            return Err(ErrorCode::ChallengesDisabled.into());
+        }
+        invoke(
+            &system_instruction::transfer(ctx.accounts.challenger.key, ctx.accounts.challenge.to_account_info().key, stake),
+            &[
+                ctx.accounts.challenger.to_account_info(),
+                ctx.accounts.challenge.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        // votes are escrowed in a token account owned by the challenge so a balance can only be counted once
+        let challenge_key = ctx.accounts.challenge.key();
+        let (vault, vault_bump) = Pubkey::find_program_address(&[b"challenge-vault".as_ref(), challenge_key.as_ref()], ctx.program_id);
+        if *ctx.accounts.vault.key != vault {
+            return Err(ErrorCode::InvalidChallengeAccount.into());
+        }
+        create_pda_account(
+            &ctx.accounts.challenger.to_account_info(),
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN,
+            &spl_token::id(),
+            &[b"challenge-vault".as_ref(), challenge_key.as_ref(), &[vault_bump]],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_account(&spl_token::id(), &vault, ctx.accounts.vote_mint.key, &challenge_key)?,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.vote_mint.to_account_info(),
+                ctx.accounts.challenge.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+        let now = Clock::get().unwrap().unix_timestamp;
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.bump = ix.bump;
+        challenge.registry = *ctx.accounts.registry_config.to_account_info().key;
+        challenge.entry = *ctx.accounts.entry.to_account_info().key;
+        challenge.challenger = *ctx.accounts.challenger.key;
+        challenge.stake = stake;
+        challenge.created_at = now;
+        challenge.voting_ends_at = now + ctx.accounts.registry_config.vote_period;
+        challenge.index = ctx.accounts.entry.challenge_count;
+        challenge.vault_bump = vault_bump;
+        let entry = &mut ctx.accounts.entry;
+        entry.challenged = true;
+        entry.challenge_count += 1;
+        Ok(())
+    }
+
+    pub fn vote(ctx: Context<Vote>, ix: VoteIx) -> ProgramResult {
+        if ctx.accounts.challenge.resolved || Clock::get().unwrap().unix_timestamp >= ctx.accounts.challenge.voting_ends_at {
+            return Err(ErrorCode::VotingClosed.into());
+        }
+        if ix.weight == 0 {
+            return Err(ErrorCode::InvalidVoteWeight.into());
+        }
+        // the vault only accepts the registry vote mint, so the weight is backed by governance tokens
+        invoke(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                ctx.accounts.voter_token_account.key,
+                ctx.accounts.vault.key,
+                ctx.accounts.voter.key,
+                &[],
+                ix.weight,
+            )?,
+            &[
+                ctx.accounts.voter_token_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.voter.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+        let vote = &mut ctx.accounts.vote;
+        vote.bump = ix.bump;
+        vote.challenge = *ctx.accounts.challenge.to_account_info().key;
+        vote.voter = *ctx.accounts.voter.key;
+        vote.token_account = *ctx.accounts.voter_token_account.key;
+        vote.support = ix.support;
+        vote.weight = ix.weight;
+        let challenge = &mut ctx.accounts.challenge;
+        if ix.support {
+            challenge.votes_for += ix.weight;
+        } else {
+            challenge.votes_against += ix.weight;
+        }
+        challenge.open_votes += 1;
+        Ok(())
+    }
+
+    pub fn resolve_challenge(ctx: Context<ResolveChallenge>) -> ProgramResult {
+        let now = Clock::get().unwrap().unix_timestamp;
+        if ctx.accounts.challenge.resolved {
+            return Err(ErrorCode::ChallengeResolved.into());
+        }
+        if now < ctx.accounts.challenge.voting_ends_at {
+            return Err(ErrorCode::VotingOpen.into());
+        }
+        // tokens sent to the vault outside of a vote keep it open rather than blocking the resolution
+        if ctx.accounts.challenge.open_votes == 0 && token_amount(&ctx.accounts.vault)? == 0 {
+            close_vault(&ctx.accounts.challenge, &ctx.accounts.vault, &ctx.accounts.challenger, &ctx.accounts.token_program)?;
+        }
+        let challenge = &mut ctx.accounts.challenge;
+        // ties keep the entry
+        let succeeded = challenge.votes_for > challenge.votes_against;
+        // the losing side forfeits its stake, split between the winner and the voters who sided with them
+        let (forfeited, winning_votes) = if succeeded {
+            let entry = &mut ctx.accounts.entry;
+            let deposit = entry.deposit;
+            pay_out(&entry.to_account_info(), &challenge.to_account_info(), deposit)?;
+            entry.deposit = 0;
+            (deposit, challenge.votes_for)
+        } else {
+            (challenge.stake, challenge.votes_against)
+        };
+        let reward_pool = if winning_votes > 0 {
+            (forfeited as u128 * ctx.accounts.registry_config.voter_reward_pct as u128 / 100) as u64
+        } else {
+            0
+        };
+        if succeeded {
+            pay_out(&challenge.to_account_info(), &ctx.accounts.challenger, challenge.stake + forfeited - reward_pool)?;
+        } else {
+            pay_out(&challenge.to_account_info(), &ctx.accounts.entry_creator, forfeited - reward_pool)?;
+        }
+        challenge.resolved = true;
+        challenge.succeeded = succeeded;
+        challenge.reward_pool = reward_pool;
+
+        // the entry can be challenged again right away, outstanding votes are claimed from this challenge
+        let entry = &mut ctx.accounts.entry;
+        entry.challenged = false;
+        let turnout = challenge.votes_for + challenge.votes_against;
+        if succeeded {
+            entry.is_verified = false;
+            entry.verified_at = None;
+        } else if challenge.challenger != entry.creator
+            && challenge.votes_against > 0
+            && turnout >= ctx.accounts.registry_config.vote_quorum
+        {
+            // only token holders defending the entry against someone else's challenge verify it
+            entry.is_verified = true;
+            entry.verified_at = Some(now);
+        }
+        if challenge.open_votes == 0 {
+            challenge.close(ctx.accounts.challenger.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn claim_vote(ctx: Context<ClaimVote>) -> ProgramResult {
+        let challenge = &ctx.accounts.challenge;
+        if !challenge.resolved {
+            return Err(ErrorCode::ChallengeUnresolved.into());
+        }
+        let last_claim = challenge.open_votes == 1;
+        // the last claim sweeps the vault so it can be closed
+        let amount = if last_claim { token_amount(&ctx.accounts.vault)? } else { ctx.accounts.vote.weight };
+        let index = challenge.index.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"challenge".as_ref(), challenge.entry.as_ref(), &index, &[challenge.bump]];
+        invoke_signed(
+            &spl_token::instruction::transfer(&spl_token::id(), ctx.accounts.vault.key, ctx.accounts.voter_token_account.key, &challenge.key(), &[], amount)?,
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.voter_token_account.to_account_info(),
+                challenge.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[seeds],
+        )?;
+        if last_claim {
+            close_vault(challenge, &ctx.accounts.vault, &ctx.accounts.challenger, &ctx.accounts.token_program)?;
+        }
+        let vote = &ctx.accounts.vote;
+        if vote.support == challenge.succeeded {
+            let winning_votes = if challenge.succeeded { challenge.votes_for } else { challenge.votes_against };
+            let reward = (challenge.reward_pool as u128 * vote.weight as u128 / winning_votes as u128) as u64;
+            pay_out(&challenge.to_account_info(), &ctx.accounts.voter, reward)?;
+        }
+        ctx.accounts.vote.close(ctx.accounts.voter.to_account_info())?;
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.open_votes -= 1;
+        // rounding dust and the challenge rent go back to the challenger
+        if challenge.open_votes == 0 {
+            challenge.close(ctx.accounts.challenger.to_account_info())?;
+        }
         Ok(())
     }
 }
@@ -57,8 +430,18 @@ pub mod permissionless_verifiable_registry {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitIx {
     pub bump: u8,
+    pub name: String,
     pub entry_seed: String,
     pub permissionless_add: bool,
+    pub require_proof: bool,
+    pub max_entries: u32,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub challenge_stake: u64,
+    pub vote_period: i64,
+    pub vote_quorum: u64,
+    pub voter_reward_pct: u8,
+    pub vote_mint: Option<Pubkey>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -67,6 +450,40 @@ pub struct AddEntryIx {
     pub address: Pubkey,
     pub schema_version: u8,
     pub data: String,
+    pub proof: ProofMethod,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetMultisigIx {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateConfigIx {
+    pub permissionless_add: Option<bool>,
+    pub entry_seed: Option<String>,
+    pub require_proof: Option<bool>,
+    pub max_entries: Option<u32>,
+    pub max_entry_size: Option<u32>,
+    pub moderation_treasury: Option<Option<Pubkey>>,
+    pub challenge_stake: Option<u64>,
+    pub vote_period: Option<i64>,
+    pub vote_quorum: Option<u64>,
+    pub voter_reward_pct: Option<u8>,
+    pub vote_mint: Option<Option<Pubkey>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ChallengeEntryIx {
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VoteIx {
+    pub bump: u8,
+    pub support: bool,
+    pub weight: u64,
 }
 
 ///////////////// Contexts /////////////////
@@ -78,8 +495,8 @@ pub struct Init<'info> {
         init,
         payer = authority,
         // extra space for future upgrades
-        space = 128,
-        seeds = [CONFIG_PREFIX.as_ref()],
+        space = 1024,
+        seeds = [CONFIG_PREFIX.as_ref(), ix.name.as_ref()],
         bump = ix.bump,
     )]
     pub registry_config: Account<'info, RegistryConfig>,
@@ -89,60 +506,242 @@ pub struct Init<'info> {
 
 #[derive(Accounts)]
 pub struct TransferAuthority<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
     pub new_authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(constraint = registry_config.pending_authority == Some(*new_authority.to_account_info().key) @ ErrorCode::InvalidPendingAuthority)]
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMultisig<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(ix: AddEntryIx)]
 pub struct AddEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
     #[account(
         init,
         payer = creator,
-        // extra space for future upgrades
-        space = 1024,
-        seeds = [registry_config.entry_seed.as_ref(), ix.address.as_ref()],
+        // sized to the payload
+        space = ENTRY_BASE_SIZE + ix.data.try_to_vec().unwrap().len(),
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), ix.address.as_ref()],
         bump = ix.bump,
     )]
     pub entry: ProgramAccount<'info, EntryData>,
-    #[account(constraint = registry_config.permissionless_add || (registry_config.authority == *creator.to_account_info().key) @ ErrorCode::InsufficientAuthority)]
+    #[account(mut)]
     pub creator: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> AddEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.permissionless_add {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.creator, remaining_accounts)
+    }
+}
+
 #[derive(Accounts)]
 pub struct VerifyEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct UnverifyEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct RemoveEntry<'info> {
-    #[account(mut, seeds = [CONFIG_PREFIX.as_ref()], bump = registry_config.bump)]
+    #[account(mut, seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: ProgramAccount<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key || entry.creator == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub rent_recipient: AccountInfo<'info>,
+}
+
+impl<'info> RemoveEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.entry.creator == *self.authority.key {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.authority, remaining_accounts)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(ix: ChallengeEntryIx)]
+pub struct ChallengeEntry<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+        constraint = !entry.challenged @ ErrorCode::EntryChallenged,
+    )]
+    pub entry: ProgramAccount<'info, EntryData>,
+    #[account(
+        init,
+        payer = challenger,
+        space = CHALLENGE_SIZE,
+        seeds = [b"challenge".as_ref(), entry.to_account_info().key.as_ref(), entry.challenge_count.to_le_bytes().as_ref()],
+        bump = ix.bump,
+    )]
+    pub challenge: Account<'info, ChallengeData>,
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    #[account(constraint = registry_config.vote_mint == Some(*vote_mint.key) @ ErrorCode::ChallengesDisabled)]
+    pub vote_mint: AccountInfo<'info>,
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidChallengeAccount)]
+    pub token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: VoteIx)]
+pub struct Vote<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), challenge.entry.as_ref(), challenge.index.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+    )]
+    pub challenge: Account<'info, ChallengeData>,
+    #[account(
+        init,
+        payer = voter,
+        space = VOTE_SIZE,
+        seeds = [b"vote".as_ref(), challenge.to_account_info().key.as_ref(), voter.key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub vote: Account<'info, VoteData>,
+    #[account(mut, seeds = [b"challenge-vault".as_ref(), challenge.to_account_info().key.as_ref()], bump = challenge.vault_bump)]
+    pub vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub voter_token_account: AccountInfo<'info>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidChallengeAccount)]
+    pub token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveChallenge<'info> {
+    #[account(seeds = [CONFIG_PREFIX.as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: ProgramAccount<'info, EntryData>,
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), entry.to_account_info().key.as_ref(), challenge.index.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+    )]
+    pub challenge: Account<'info, ChallengeData>,
+    #[account(mut, seeds = [b"challenge-vault".as_ref(), challenge.to_account_info().key.as_ref()], bump = challenge.vault_bump)]
+    pub vault: AccountInfo<'info>,
+    #[account(mut, constraint = challenge.challenger == *challenger.key @ ErrorCode::InvalidChallengeAccount)]
+    pub challenger: AccountInfo<'info>,
+    #[account(mut, constraint = entry.creator == *entry_creator.key @ ErrorCode::InvalidChallengeAccount)]
+    pub entry_creator: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidChallengeAccount)]
+    pub token_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), challenge.entry.as_ref(), challenge.index.to_le_bytes().as_ref()],
+        bump = challenge.bump,
+    )]
+    pub challenge: Account<'info, ChallengeData>,
+    #[account(
+        mut,
+        seeds = [b"vote".as_ref(), challenge.to_account_info().key.as_ref(), vote.voter.as_ref()],
+        bump = vote.bump,
+    )]
+    pub vote: Account<'info, VoteData>,
+    #[account(mut, seeds = [b"challenge-vault".as_ref(), challenge.to_account_info().key.as_ref()], bump = challenge.vault_bump)]
+    pub vault: AccountInfo<'info>,
+    #[account(mut, constraint = vote.token_account == *voter_token_account.key @ ErrorCode::InvalidChallengeAccount)]
+    pub voter_token_account: AccountInfo<'info>,
+    #[account(mut, constraint = vote.voter == *voter.key @ ErrorCode::InvalidChallengeAccount)]
+    pub voter: AccountInfo<'info>,
+    #[account(mut, constraint = challenge.challenger == *challenger.key @ ErrorCode::InvalidChallengeAccount)]
+    pub challenger: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidChallengeAccount)]
+    pub token_program: AccountInfo<'info>,
 }
 
 ///////////////// DATA /////////////////
@@ -151,13 +750,36 @@ pub struct RemoveEntry<'info> {
 #[derive(Default)]
 pub struct RegistryConfig{
     pub bump: u8,
+    pub name: String,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub multisig_signers: Vec<Pubkey>,
+    pub multisig_threshold: u8,
     pub entry_seed: String,
     pub permissionless_add: bool,
+    pub require_proof: bool,
+    pub max_entries: u32,
+    pub entry_count: u64,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub challenge_stake: u64,
+    pub vote_period: i64,
+    pub vote_quorum: u64,
+    pub voter_reward_pct: u8,
+    pub vote_mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum ProofMethod {
+    None,
+    AddressSignature,
+    UpgradeAuthority,
 }
 
 #[account]
 pub struct EntryData {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub address: Pubkey,
     pub creator: Pubkey,
     pub created_at: i64,
@@ -165,6 +787,192 @@ pub struct EntryData {
     pub verified_at: Option<i64>,
     pub schema_version: u8,
     pub data: String,
+    pub proof: ProofMethod,
+    pub rent_payer: Pubkey,
+    pub challenged: bool,
+    pub challenge_count: u64,
+    pub deposit: u64,
+}
+
+#[account]
+pub struct ChallengeData {
+    pub bump: u8,
+    pub registry: Pubkey,
+    pub entry: Pubkey,
+    pub challenger: Pubkey,
+    pub stake: u64,
+    pub created_at: i64,
+    pub voting_ends_at: i64,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub open_votes: u32,
+    pub resolved: bool,
+    pub succeeded: bool,
+    pub reward_pool: u64,
+    pub index: u64,
+    pub vault_bump: u8,
+}
+
+#[account]
+pub struct VoteData {
+    pub bump: u8,
+    pub challenge: Pubkey,
+    pub voter: Pubkey,
+    pub token_account: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub registry: Pubkey,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn authorized(registry_config: &RegistryConfig, signer: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    if signer.is_signer && registry_config.authority == *signer.key {
+        return Ok(());
+    }
+    if registry_config.multisig_threshold > 0 {
+        let mut approvals: Vec<&Pubkey> = Vec::new();
+        let signers = remaining_accounts.iter().map(|account| (account.key, account.is_signer));
+        for (key, is_signer) in std::iter::once((signer.key, signer.is_signer)).chain(signers) {
+            if is_signer && registry_config.multisig_signers.contains(key) && !approvals.contains(&key) {
+                approvals.push(key);
+            }
+        }
+        if approvals.len() >= registry_config.multisig_threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(ErrorCode::InsufficientAuthority.into())
+}
+
+// signers approved by the previous authority do not carry over to the next one
+pub fn clear_multisig(registry_config: &mut RegistryConfig) {
+    registry_config.multisig_signers.clear();
+    registry_config.multisig_threshold = 0;
+}
+
+pub fn config_changed<T: ToString>(registry: Pubkey, field: &str, old_value: &T, new_value: &T) {
+    emit!(ConfigUpdated {
+        registry,
+        field: field.to_string(),
+        old_value: old_value.to_string(),
+        new_value: new_value.to_string(),
+    });
+}
+
+pub fn rent_recipient(registry_config: &RegistryConfig, rent_payer: &Pubkey, moderated: bool) -> Pubkey {
+    match registry_config.moderation_treasury {
+        Some(treasury) if moderated => treasury,
+        _ => *rent_payer,
+    }
+}
+
+pub fn assert_proof(proof: ProofMethod, address: &Pubkey, creator: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    let proven = match proof {
+        ProofMethod::None => true,
+        ProofMethod::AddressSignature => std::iter::once((creator.key, creator.is_signer))
+            .chain(remaining_accounts.iter().map(|account| (account.key, account.is_signer)))
+            .any(|(key, is_signer)| key == address && is_signer),
+        ProofMethod::UpgradeAuthority => {
+            let (program_data, _) = Pubkey::find_program_address(&[address.as_ref()], &bpf_loader_upgradeable::id());
+            match remaining_accounts.iter().find(|account| *account.key == program_data) {
+                Some(account) => creator.is_signer && program_data_header(account)?.1 == Some(*creator.key),
+                None => false,
+            }
+        }
+    };
+    if !proven {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    Ok(())
+}
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProof.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+pub fn entry_size<T: AnchorSerialize>(registry_config: &RegistryConfig, data: &T) -> std::result::Result<usize, ProgramError> {
+    let size = ENTRY_BASE_SIZE + data.try_to_vec().unwrap().len();
+    if registry_config.max_entry_size > 0 && size > registry_config.max_entry_size as usize {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    Ok(size)
+}
+
+pub fn pay_out(source: &AccountInfo, recipient: &AccountInfo, amount: u64) -> ProgramResult {
+    **source.try_borrow_mut_lamports()? -= amount;
+    **recipient.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// creates a PDA owned by `owner`, topping up instead of failing when someone already sent it lamports
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, lamports, space as u64, owner),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+    let shortfall = lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(&system_instruction::allocate(account.key, space as u64), &[account.clone(), system_program.clone()], &[seeds])?;
+    invoke_signed(&system_instruction::assign(account.key, owner), &[account.clone(), system_program.clone()], &[seeds])
+}
+
+pub fn token_amount(token_account: &AccountInfo) -> std::result::Result<u64, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+    let account = spl_token::state::Account::unpack(&data).map_err(|_| ErrorCode::InvalidChallengeAccount)?;
+    Ok(account.amount)
+}
+
+// returns the rent of an emptied vote vault to the challenger
+pub fn close_vault<'info>(
+    challenge: &Account<'info, ChallengeData>,
+    vault: &AccountInfo<'info>,
+    challenger: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> ProgramResult {
+    let index = challenge.index.to_le_bytes();
+    invoke_signed(
+        &spl_token::instruction::close_account(&spl_token::id(), vault.key, challenger.key, &challenge.key(), &[])?,
+        &[vault.clone(), challenger.clone(), challenge.to_account_info(), token_program.clone()],
+        &[&[b"challenge".as_ref(), challenge.entry.as_ref(), &index, &[challenge.bump]]],
+    )
 }
 
 ///////////////// ERRORS /////////////////
@@ -173,4 +981,40 @@ pub struct EntryData {
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry entry")]
     InsufficientAuthority,
-}
\ No newline at end of file
+    #[msg("Signer is not the pending authority for this registry")]
+    InvalidPendingAuthority,
+    #[msg("Entry does not belong to this registry")]
+    InvalidRegistry,
+    #[msg("Multisig signers or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Creator did not prove control of the entry address")]
+    InvalidProof,
+    #[msg("Seed fields cannot change once the registry has entries")]
+    SeedLocked,
+    #[msg("Registry has reached its maximum number of entries")]
+    RegistryFull,
+    #[msg("Entry data exceeds the maximum entry size for this registry")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer or registry treasury")]
+    InvalidRentRecipient,
+    #[msg("Entry has an open challenge")]
+    EntryChallenged,
+    #[msg("Challenges are disabled for this registry")]
+    ChallengesDisabled,
+    #[msg("Voting period for this challenge has ended")]
+    VotingClosed,
+    #[msg("Voting period for this challenge has not ended")]
+    VotingOpen,
+    #[msg("Challenge has already been resolved")]
+    ChallengeResolved,
+    #[msg("Challenge has not been resolved")]
+    ChallengeUnresolved,
+    #[msg("Voter reward share must be a percentage between 0 and 100")]
+    InvalidRewardShare,
+    #[msg("Account does not match the challenge")]
+    InvalidChallengeAccount,
+    #[msg("Vote period must be positive")]
+    InvalidVotePeriod,
+    #[msg("Vote weight must be positive")]
+    InvalidVoteWeight,
+}
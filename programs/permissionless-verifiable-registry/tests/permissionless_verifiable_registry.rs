@@ -0,0 +1,383 @@
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use permissionless_verifiable_registry::{
+    accounts, instruction, AddEntryIx, ChallengeData, ChallengeEntryIx, EntryData, ErrorCode, InitIx, ProofMethod, VoteIx,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+const ENTRY_SEED: &str = "entry";
+const STAKE: u64 = 1_000_000;
+const VOTE_PERIOD: i64 = 60;
+
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "permissionless_verifiable_registry",
+        permissionless_verifiable_registry::id(),
+        processor!(permissionless_verifiable_registry::entry),
+    );
+    program_test.add_program("spl_token", spl_token::id(), processor!(spl_token::processor::Processor::process));
+    program_test
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context.banks_client.get_balance(address).await.unwrap()
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &permissionless_verifiable_registry::id())
+}
+
+async fn funded_keypair(context: &mut ProgramTestContext) -> Keypair {
+    let keypair = Keypair::new();
+    let ix = system_instruction::transfer(&context.payer.pubkey(), &keypair.pubkey(), 1_000_000_000);
+    process(context, &[ix], &[]).await.unwrap();
+    keypair
+}
+
+async fn create_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Keypair::new();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer, None, 0).unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_account(context: &mut ProgramTestContext, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let account = Keypair::new();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &account.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), &mint, &owner).unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint, &account.pubkey(), &payer, &[], amount).unwrap(),
+    ];
+    process(context, &instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+async fn token_balance(context: &mut ProgramTestContext, account: Pubkey) -> u64 {
+    let account = context.banks_client.get_account(account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+async fn init(context: &mut ProgramTestContext, vote_mint: Pubkey) -> Pubkey {
+    let (registry_config, bump) = pda(&[b"registry-config", b"registry"]);
+    let ix = InitIx {
+        bump,
+        name: "registry".to_string(),
+        entry_seed: ENTRY_SEED.to_string(),
+        permissionless_add: true,
+        require_proof: false,
+        max_entries: 0,
+        max_entry_size: 0,
+        moderation_treasury: None,
+        challenge_stake: STAKE,
+        vote_period: VOTE_PERIOD,
+        vote_quorum: 0,
+        voter_reward_pct: 50,
+        vote_mint: Some(vote_mint),
+    };
+    let init = Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::Init { registry_config, authority: context.payer.pubkey(), system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::Init { ix }.data(),
+    };
+    process(context, &[init], &[]).await.unwrap();
+    registry_config
+}
+
+async fn add_entry(context: &mut ProgramTestContext, registry_config: Pubkey, creator: &Keypair) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let (entry, bump) = pda(&[ENTRY_SEED.as_bytes(), registry_config.as_ref(), address.as_ref()]);
+    let ix = Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::AddEntry { registry_config, entry, creator: creator.pubkey(), system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::AddEntry {
+            ix: AddEntryIx { bump, address, schema_version: 0, data: "data".to_string(), proof: ProofMethod::None },
+        }
+        .data(),
+    };
+    process(context, &[ix], &[creator]).await.unwrap();
+    entry
+}
+
+fn challenge_address(entry: &Pubkey, index: u64) -> (Pubkey, u8) {
+    pda(&[b"challenge", entry.as_ref(), &index.to_le_bytes()])
+}
+
+fn vault_address(challenge: &Pubkey) -> Pubkey {
+    pda(&[b"challenge-vault", challenge.as_ref()]).0
+}
+
+async fn challenge_entry(
+    context: &mut ProgramTestContext,
+    registry_config: Pubkey,
+    entry: Pubkey,
+    vote_mint: Pubkey,
+    challenger: &Keypair,
+) -> Pubkey {
+    let index = fetch::<EntryData>(context, entry).await.challenge_count;
+    let (challenge, bump) = challenge_address(&entry, index);
+    let ix = Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::ChallengeEntry {
+            registry_config,
+            entry,
+            challenge,
+            vault: vault_address(&challenge),
+            vote_mint,
+            challenger: challenger.pubkey(),
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::ChallengeEntry { ix: ChallengeEntryIx { bump } }.data(),
+    };
+    process(context, &[ix], &[challenger]).await.unwrap();
+    challenge
+}
+
+async fn vote(context: &mut ProgramTestContext, challenge: Pubkey, voter: &Keypair, voter_token_account: Pubkey, support: bool, weight: u64) -> Pubkey {
+    let (vote, bump) = pda(&[b"vote", challenge.as_ref(), voter.pubkey().as_ref()]);
+    let ix = Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::Vote {
+            challenge,
+            vote,
+            vault: vault_address(&challenge),
+            voter_token_account,
+            voter: voter.pubkey(),
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::Vote { ix: VoteIx { bump, support, weight } }.data(),
+    };
+    process(context, &[ix], &[voter]).await.unwrap();
+    vote
+}
+
+fn resolve_challenge_ix(registry_config: Pubkey, entry: Pubkey, challenge: Pubkey, challenger: Pubkey, entry_creator: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::ResolveChallenge {
+            registry_config,
+            entry,
+            challenge,
+            vault: vault_address(&challenge),
+            challenger,
+            entry_creator,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::ResolveChallenge {}.data(),
+    }
+}
+
+fn claim_vote_ix(challenge: Pubkey, vote: Pubkey, voter_token_account: Pubkey, voter: Pubkey, challenger: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_registry::id(),
+        accounts: accounts::ClaimVote {
+            challenge,
+            vote,
+            vault: vault_address(&challenge),
+            voter_token_account,
+            voter,
+            challenger,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::ClaimVote {}.data(),
+    }
+}
+
+async fn end_voting(context: &mut ProgramTestContext) {
+    // a new slot also gives a new blockhash, so a resolve retried after the warp isn't answered from the earlier result
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 2).unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += VOTE_PERIOD + 1;
+    context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+async fn add_entry_escrows_the_challenge_stake() {
+    let mut context = program_test().start_with_context().await;
+    let vote_mint = create_mint(&mut context).await;
+    let registry_config = init(&mut context, vote_mint).await;
+    let creator = funded_keypair(&mut context).await;
+
+    let entry = add_entry(&mut context, registry_config, &creator).await;
+    let account = context.banks_client.get_account(entry).await.unwrap().unwrap();
+    assert_eq!(account.lamports, Rent::default().minimum_balance(account.data.len()) + STAKE);
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.deposit, STAKE);
+}
+
+#[tokio::test]
+async fn failed_challenge_pays_the_entry_creator_and_defending_voters() {
+    let mut context = program_test().start_with_context().await;
+    let vote_mint = create_mint(&mut context).await;
+    let registry_config = init(&mut context, vote_mint).await;
+    let creator = funded_keypair(&mut context).await;
+    let challenger = funded_keypair(&mut context).await;
+    let voter = funded_keypair(&mut context).await;
+    let voter_token_account = create_token_account(&mut context, vote_mint, voter.pubkey(), 100).await;
+    let entry = add_entry(&mut context, registry_config, &creator).await;
+
+    let challenger_balance = balance(&mut context, challenger.pubkey()).await;
+    let challenge = challenge_entry(&mut context, registry_config, entry, vote_mint, &challenger).await;
+    let vote = vote(&mut context, challenge, &voter, voter_token_account, false, 40).await;
+    assert_eq!(token_balance(&mut context, voter_token_account).await, 60);
+
+    let resolve = resolve_challenge_ix(registry_config, entry, challenge, challenger.pubkey(), creator.pubkey());
+    assert_error(process(&mut context, std::slice::from_ref(&resolve), &[]).await, ErrorCode::VotingOpen);
+    end_voting(&mut context).await;
+    let creator_balance = balance(&mut context, creator.pubkey()).await;
+    process(&mut context, &[resolve], &[]).await.unwrap();
+    // the challenger's stake goes half to the creator and half to the defending voters
+    assert_eq!(balance(&mut context, creator.pubkey()).await, creator_balance + STAKE / 2);
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(entry_data.is_verified);
+    assert!(!entry_data.challenged);
+    assert_eq!(entry_data.deposit, STAKE);
+    let challenge_data: ChallengeData = fetch(&mut context, challenge).await;
+    assert!(!challenge_data.succeeded);
+    assert_eq!(challenge_data.reward_pool, STAKE / 2);
+
+    let voter_balance = balance(&mut context, voter.pubkey()).await;
+    let vote_rent = balance(&mut context, vote).await;
+    process(&mut context, &[claim_vote_ix(challenge, vote, voter_token_account, voter.pubkey(), challenger.pubkey())], &[]).await.unwrap();
+    assert_eq!(balance(&mut context, voter.pubkey()).await, voter_balance + vote_rent + STAKE / 2);
+    assert_eq!(token_balance(&mut context, voter_token_account).await, 100);
+    // the challenge and vault rent return to the challenger once every vote is claimed
+    assert_eq!(balance(&mut context, challenge).await, 0);
+    assert_eq!(balance(&mut context, vault_address(&challenge)).await, 0);
+    assert_eq!(balance(&mut context, challenger.pubkey()).await, challenger_balance - STAKE);
+}
+
+#[tokio::test]
+async fn successful_challenge_pays_the_challenger_and_supporting_voters() {
+    let mut context = program_test().start_with_context().await;
+    let vote_mint = create_mint(&mut context).await;
+    let registry_config = init(&mut context, vote_mint).await;
+    let creator = funded_keypair(&mut context).await;
+    let challenger = funded_keypair(&mut context).await;
+    let voter = funded_keypair(&mut context).await;
+    let voter_token_account = create_token_account(&mut context, vote_mint, voter.pubkey(), 100).await;
+    let entry = add_entry(&mut context, registry_config, &creator).await;
+    let entry_balance = balance(&mut context, entry).await;
+
+    let challenger_balance = balance(&mut context, challenger.pubkey()).await;
+    let challenge = challenge_entry(&mut context, registry_config, entry, vote_mint, &challenger).await;
+    let vote = vote(&mut context, challenge, &voter, voter_token_account, true, 40).await;
+    end_voting(&mut context).await;
+    let creator_balance = balance(&mut context, creator.pubkey()).await;
+    let resolve = resolve_challenge_ix(registry_config, entry, challenge, challenger.pubkey(), creator.pubkey());
+    process(&mut context, &[resolve], &[]).await.unwrap();
+    // the entry deposit is forfeited, half to the challenger and half to the supporting voters
+    assert_eq!(balance(&mut context, creator.pubkey()).await, creator_balance);
+    assert_eq!(balance(&mut context, entry).await, entry_balance - STAKE);
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(!entry_data.is_verified);
+    assert_eq!(entry_data.deposit, 0);
+    let challenge_data: ChallengeData = fetch(&mut context, challenge).await;
+    assert!(challenge_data.succeeded);
+    assert_eq!(challenge_data.reward_pool, STAKE / 2);
+
+    let voter_balance = balance(&mut context, voter.pubkey()).await;
+    let vote_rent = balance(&mut context, vote).await;
+    process(&mut context, &[claim_vote_ix(challenge, vote, voter_token_account, voter.pubkey(), challenger.pubkey())], &[]).await.unwrap();
+    assert_eq!(balance(&mut context, voter.pubkey()).await, voter_balance + vote_rent + STAKE / 2);
+    assert_eq!(token_balance(&mut context, voter_token_account).await, 100);
+    assert_eq!(balance(&mut context, challenge).await, 0);
+    assert_eq!(balance(&mut context, challenger.pubkey()).await, challenger_balance + STAKE / 2);
+}
+
+#[tokio::test]
+async fn unopposed_winner_keeps_the_whole_forfeited_stake() {
+    let mut context = program_test().start_with_context().await;
+    let vote_mint = create_mint(&mut context).await;
+    let registry_config = init(&mut context, vote_mint).await;
+    let creator = funded_keypair(&mut context).await;
+    let challenger = funded_keypair(&mut context).await;
+    let entry = add_entry(&mut context, registry_config, &creator).await;
+
+    let challenger_balance = balance(&mut context, challenger.pubkey()).await;
+    let challenge = challenge_entry(&mut context, registry_config, entry, vote_mint, &challenger).await;
+    end_voting(&mut context).await;
+    let creator_balance = balance(&mut context, creator.pubkey()).await;
+    let resolve = resolve_challenge_ix(registry_config, entry, challenge, challenger.pubkey(), creator.pubkey());
+    process(&mut context, &[resolve], &[]).await.unwrap();
+    // ties keep the entry and with no voters on the winning side the creator takes the full stake
+    assert_eq!(balance(&mut context, creator.pubkey()).await, creator_balance + STAKE);
+    assert_eq!(balance(&mut context, challenge).await, 0);
+    assert_eq!(balance(&mut context, vault_address(&challenge)).await, 0);
+    assert_eq!(balance(&mut context, challenger.pubkey()).await, challenger_balance - STAKE);
+}
+
+#[tokio::test]
+async fn lamports_sent_to_the_vault_address_do_not_block_a_challenge() {
+    let mut context = program_test().start_with_context().await;
+    let vote_mint = create_mint(&mut context).await;
+    let registry_config = init(&mut context, vote_mint).await;
+    let creator = funded_keypair(&mut context).await;
+    let challenger = funded_keypair(&mut context).await;
+    let entry = add_entry(&mut context, registry_config, &creator).await;
+
+    let (challenge, _) = challenge_address(&entry, 0);
+    let vault = vault_address(&challenge);
+    let ix = system_instruction::transfer(&context.payer.pubkey(), &vault, Rent::default().minimum_balance(0));
+    process(&mut context, &[ix], &[]).await.unwrap();
+
+    challenge_entry(&mut context, registry_config, entry, vote_mint, &challenger).await;
+    let account = context.banks_client.get_account(vault).await.unwrap().unwrap();
+    assert_eq!(account.owner, spl_token::id());
+    assert_eq!(account.lamports, Rent::default().minimum_balance(spl_token::state::Account::LEN));
+    assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().owner, challenge);
+}
@@ -1,11 +1,28 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
 use anchor_lang::solana_program::{
-    program::{invoke},
+    bpf_loader_upgradeable,
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
+    pubkey::MAX_SEED_LEN,
     system_instruction,
 };
+use std::convert::TryInto;
 
 declare_id!("tkJqbNU3dk3eCwtT4EjSFisxza8JcuKSbDNbTZDQv76");
+const MAX_MULTISIG_SIGNERS: usize = 10;
+// serialized size of every EntryData field except `primary_key`, `data` and `badge_grants` entries
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 1 + 8 + 1 + 1 + 32 + 1 + 4 + 4 + 8;
+const BOND_VAULT_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 8;
+const MAX_BADGES: usize = 32;
+const MAX_BADGE_NAME_LEN: usize = 32;
+const BADGE_DEFINITIONS_SIZE: usize = 8 + 1 + 32 + 4 + MAX_BADGES * (4 + MAX_BADGE_NAME_LEN);
+const BADGE_GRANT_SIZE: usize = 1 + 1 + 33 + 9 + 9;
+// entries verified before badges existed migrate to this badge
+const LEGACY_VERIFIED_BADGE: u8 = 0;
+// entries written before badges kept `is_verified` in the version byte, so 0 and 1 are the legacy layout
+const ENTRY_VERSION: u8 = 2;
 
 #[program]
 pub mod permissionless_verifiable_schema_registry {
@@ -13,69 +30,561 @@ pub mod permissionless_verifiable_schema_registry {
 
     pub fn init(ctx: Context<Init>, ix: InitIx) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.bump = ix.bump;
+        registry_config.name = ix.name;
         registry_config.authority = *ctx.accounts.authority.key;
         registry_config.entry_seed = ix.entry_seed;
         registry_config.schema_version = 0;
         registry_config.permissionless_add = ix.permissionless_add;
         registry_config.add_fee = ix.add_fee;
+        registry_config.require_proof = ix.require_proof;
+        registry_config.max_entries = ix.max_entries;
+        registry_config.max_entry_size = ix.max_entry_size;
+        registry_config.moderation_treasury = ix.moderation_treasury;
+        registry_config.treasurer = ix.treasurer;
+        registry_config.bond_amount = ix.bond_amount;
+        registry_config.bond_lock_period = ix.bond_lock_period;
+        registry_config.verification_threshold = ix.verification_threshold;
+        registry_config.verification_ttl = ix.verification_ttl;
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.bump = ix.treasury_bump;
+        treasury.registry = registry_config.key();
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
         registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
         Ok(())
     }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn propose_authority(ctx: Context<ProposeAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = Some(*ctx.accounts.new_authority.key);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = None;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn set_multisig(ctx: Context<SetMultisig>, ix: SetMultisigIx) -> ProgramResult {
+        if ix.signers.len() > MAX_MULTISIG_SIGNERS || ix.threshold as usize > ix.signers.len() {
+            return Err(ErrorCode::InvalidMultisig.into());
+        }
+        for (i, signer) in ix.signers.iter().enumerate() {
+            if ix.signers[..i].contains(signer) {
+                return Err(ErrorCode::InvalidMultisig.into());
+            }
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.multisig_signers = ix.signers;
+        registry_config.multisig_threshold = ix.threshold;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn update_config(ctx: Context<UpdateConfig>, ix: UpdateConfigIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if let Some(permissionless_add) = ix.permissionless_add {
+            config_changed(registry, "permissionless_add", &registry_config.permissionless_add, &permissionless_add);
+            registry_config.permissionless_add = permissionless_add;
+        }
+        if let Some(entry_seed) = ix.entry_seed {
+            if registry_config.entry_count > 0 {
+                return Err(ErrorCode::SeedLocked.into());
+            }
+            config_changed(registry, "entry_seed", &registry_config.entry_seed.clone(), &entry_seed);
+            registry_config.entry_seed = entry_seed;
+        }
+        if let Some(add_fee) = ix.add_fee {
+            config_changed(registry, "add_fee", &registry_config.add_fee, &add_fee);
+            registry_config.add_fee = add_fee;
+        }
+        if let Some(require_proof) = ix.require_proof {
+            config_changed(registry, "require_proof", &registry_config.require_proof, &require_proof);
+            registry_config.require_proof = require_proof;
+        }
+        if let Some(max_entries) = ix.max_entries {
+            config_changed(registry, "max_entries", &registry_config.max_entries, &max_entries);
+            registry_config.max_entries = max_entries;
+        }
+        if let Some(max_entry_size) = ix.max_entry_size {
+            config_changed(registry, "max_entry_size", &registry_config.max_entry_size, &max_entry_size);
+            registry_config.max_entry_size = max_entry_size;
+        }
+        if let Some(moderation_treasury) = ix.moderation_treasury {
+            config_changed(
+                registry,
+                "moderation_treasury",
+                &format!("{:?}", registry_config.moderation_treasury),
+                &format!("{:?}", moderation_treasury),
+            );
+            registry_config.moderation_treasury = moderation_treasury;
+        }
+        if let Some(treasurer) = ix.treasurer {
+            config_changed(registry, "treasurer", &format!("{:?}", registry_config.treasurer), &format!("{:?}", treasurer));
+            registry_config.treasurer = treasurer;
+        }
+        if let Some(token_fee) = ix.token_fee {
+            config_changed(registry, "token_fee", &registry_config.token_fee, &token_fee);
+            registry_config.token_fee = token_fee;
+        }
+        if let Some(bond_amount) = ix.bond_amount {
+            config_changed(registry, "bond_amount", &registry_config.bond_amount, &bond_amount);
+            registry_config.bond_amount = bond_amount;
+        }
+        if let Some(bond_lock_period) = ix.bond_lock_period {
+            config_changed(registry, "bond_lock_period", &registry_config.bond_lock_period, &bond_lock_period);
+            registry_config.bond_lock_period = bond_lock_period;
+        }
+        if let Some(verification_threshold) = ix.verification_threshold {
+            config_changed(registry, "verification_threshold", &registry_config.verification_threshold, &verification_threshold);
+            registry_config.verification_threshold = verification_threshold;
+        }
+        if let Some(verification_ttl) = ix.verification_ttl {
+            config_changed(registry, "verification_ttl", &registry_config.verification_ttl, &verification_ttl);
+            registry_config.verification_ttl = verification_ttl;
+        }
+        Ok(())
+    }
+
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.admin_role, &ctx.accounts.admin, ctx.remaining_accounts))]
+    pub fn grant_role(ctx: Context<GrantRole>, ix: GrantRoleIx) -> ProgramResult {
+        let role_member = &mut ctx.accounts.role_member;
+        role_member.bump = ix.bump;
+        role_member.registry = *ctx.accounts.registry_config.to_account_info().key;
+        role_member.role = ix.role;
+        role_member.member = *ctx.accounts.member.key;
+        role_member.granted_at = Clock::get().unwrap().unix_timestamp;
+        Ok(())
+    }
+
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.admin_role, &ctx.accounts.admin, ctx.remaining_accounts))]
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> ProgramResult {
+        ctx.accounts.role_member.close(ctx.accounts.admin.to_account_info())
+    }
     
-    pub fn add_entry(ctx: Context<AddEntry>, ix: AddEntryIx) -> ProgramResult {
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
+    pub fn add_entry<'info>(ctx: Context<'_, '_, '_, 'info, AddEntry<'info>>, ix: AddEntryIx) -> ProgramResult {
+        entry_size(&ctx.accounts.registry_config, &ix.primary_key, &ix.data, 0)?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if registry_config.max_entries > 0 && registry_config.entry_count >= registry_config.max_entries as u64 {
+            return Err(ErrorCode::RegistryFull.into());
+        }
+        registry_config.entry_count += 1;
+        let verification_round = next_verification_round(registry_config);
         let entry = &mut ctx.accounts.entry;
+        entry.bump = ix.bump;
+        entry.verification_round = verification_round;
+        entry.registry = *ctx.accounts.registry_config.to_account_info().key;
         entry.primary_key = ix.primary_key;
         entry.data = ix.data;
         entry.schema_version = ix.schema_version;
         entry.creator = *ctx.accounts.creator.key;
+        entry.rent_payer = *ctx.accounts.creator.key;
         entry.created_at = Clock::get().unwrap().unix_timestamp;
-        entry.is_verified = false; // default to false
+        entry.version = ENTRY_VERSION;
+        if ctx.accounts.registry_config.require_proof && ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        if ix.proof != ProofMethod::None {
+            let address = entry.primary_key.as_slice().try_into().map(Pubkey::new_from_array).map_err(|_| ErrorCode::InvalidProof)?;
+            assert_proof(ix.proof, &address, &ctx.accounts.creator, ctx.remaining_accounts)?;
+        }
+        entry.proof = ix.proof;
 
         if ctx.accounts.creator.lamports() < ctx.accounts.registry_config.add_fee {
             return Err(ErrorCode::InsufficientBalance.into());
         }
         invoke(
             &system_instruction::transfer(
-                &ctx.accounts.creator.key,
-                &ctx.accounts.authority.key,
+                ctx.accounts.creator.key,
+                ctx.accounts.treasury.to_account_info().key,
                 ctx.accounts.registry_config.add_fee,
             ),
             &[
                 ctx.accounts.creator.to_account_info(),
-                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.treasury.to_account_info(),
                 ctx.accounts.system_program.to_account_info(),
             ],
         )?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.fees_collected += registry_config.add_fee;
+
+        // token fee accounts, then the bond vault, lead the remaining accounts only when the registry charges them
+        let mut charged_accounts = ctx.remaining_accounts.iter();
+        if let Some(fee_mint) = registry_config.fee_mint {
+            if registry_config.token_fee > 0 {
+                let (creator_token_account, fee_vault, token_program) =
+                    match (charged_accounts.next(), charged_accounts.next(), charged_accounts.next()) {
+                        (Some(creator_token_account), Some(fee_vault), Some(token_program)) => (creator_token_account, fee_vault, token_program),
+                        _ => return Err(ErrorCode::InvalidFeeAccount.into()),
+                    };
+                if *fee_vault.key != fee_vault_address(registry_config.to_account_info().key, &fee_mint, registry_config.fee_vault_bump)?
+                    || *token_program.key != spl_token::id()
+                {
+                    return Err(ErrorCode::InvalidFeeAccount.into());
+                }
+                invoke(
+                    &spl_token::instruction::transfer(
+                        &spl_token::id(),
+                        creator_token_account.key,
+                        fee_vault.key,
+                        ctx.accounts.creator.key,
+                        &[],
+                        registry_config.token_fee,
+                    )?,
+                    &[creator_token_account.clone(), fee_vault.clone(), ctx.accounts.creator.to_account_info(), token_program.clone()],
+                )?;
+                registry_config.token_fees_collected += registry_config.token_fee;
+            }
+        }
+
+        if registry_config.bond_amount > 0 {
+            let entry = ctx.accounts.entry.key();
+            let bond_vault = charged_accounts.next().ok_or(ErrorCode::InvalidBondVault)?;
+            // the bump is derived here so every bond lives at the canonical address `load_bond` checks
+            let (address, bond_bump) = Pubkey::find_program_address(&[b"bond".as_ref(), entry.as_ref()], &ID);
+            if *bond_vault.key != address {
+                return Err(ErrorCode::InvalidBondVault.into());
+            }
+            create_pda_account(
+                &ctx.accounts.creator.to_account_info(),
+                bond_vault,
+                &ctx.accounts.system_program.to_account_info(),
+                Rent::get()?.minimum_balance(BOND_VAULT_SIZE) + registry_config.bond_amount,
+                BOND_VAULT_SIZE,
+                &[b"bond".as_ref(), entry.as_ref(), &[bond_bump]],
+            )?;
+            let bond = BondVault {
+                bump: bond_bump,
+                entry,
+                depositor: *ctx.accounts.creator.key,
+                amount: registry_config.bond_amount,
+                locked_until: Clock::get()?.unix_timestamp + registry_config.bond_lock_period,
+            };
+            bond.try_serialize(&mut &mut bond_vault.try_borrow_mut_data()?[..])?;
+        }
 
         Ok(())
     }
 
-    pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.authority.to_account_info()).unwrap();
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, ix: WithdrawFeesIx) -> ProgramResult {
+        let treasury = ctx.accounts.treasury.to_account_info();
+        // the treasury keeps enough lamports to stay rent exempt
+        let available = treasury.lamports().saturating_sub(Rent::get()?.minimum_balance(treasury.data_len()));
+        if ix.amount > available {
+            return Err(ErrorCode::InsufficientTreasuryBalance.into());
+        }
+        **treasury.try_borrow_mut_lamports()? -= ix.amount;
+        **ctx.accounts.destination.try_borrow_mut_lamports()? += ix.amount;
         Ok(())
     }
 
-    pub fn verify_entry(ctx: Context<VerifyEntry>) -> ProgramResult {
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn init_fee_vault(ctx: Context<InitFeeVault>, ix: InitFeeVaultIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let fee_mint = *ctx.accounts.fee_mint.key;
+        invoke_signed(
+            &system_instruction::create_account(
+                ctx.accounts.payer.key,
+                ctx.accounts.fee_vault.key,
+                ctx.accounts.rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.fee_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[&[b"fee-vault".as_ref(), registry.as_ref(), fee_mint.as_ref(), &[ix.vault_bump]]],
+        )?;
+        // the treasury PDA owns the vault so only this program can move the fees
+        invoke(
+            &spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                ctx.accounts.fee_vault.key,
+                &fee_mint,
+                ctx.accounts.treasury.to_account_info().key,
+            )?,
+            &[
+                ctx.accounts.fee_vault.to_account_info(),
+                ctx.accounts.fee_mint.to_account_info(),
+                ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        config_changed(registry, "fee_mint", &format!("{:?}", registry_config.fee_mint), &format!("{:?}", Some(fee_mint)));
+        config_changed(registry, "token_fee", &registry_config.token_fee, &ix.token_fee);
+        registry_config.fee_mint = Some(fee_mint);
+        registry_config.fee_vault_bump = ix.vault_bump;
+        registry_config.token_fee = ix.token_fee;
+        Ok(())
+    }
+
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
+    pub fn withdraw_token_fees(ctx: Context<WithdrawTokenFees>, ix: WithdrawFeesIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let fee_mint = ctx.accounts.registry_config.fee_mint.ok_or(ErrorCode::InvalidFeeAccount)?;
+        if *ctx.accounts.fee_vault.key != fee_vault_address(&registry, &fee_mint, ctx.accounts.registry_config.fee_vault_bump)? {
+            return Err(ErrorCode::InvalidFeeAccount.into());
+        }
+        let treasury = ctx.accounts.treasury.to_account_info();
+        invoke_signed(
+            &spl_token::instruction::transfer(&spl_token::id(), ctx.accounts.fee_vault.key, ctx.accounts.destination.key, treasury.key, &[], ix.amount)?,
+            &[
+                ctx.accounts.fee_vault.to_account_info(),
+                ctx.accounts.destination.to_account_info(),
+                treasury.clone(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            &[&[b"treasury".as_ref(), registry.as_ref(), &[ctx.accounts.treasury.bump]]],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_entry(ctx: Context<RemoveEntry>, ix: RemoveEntryIx) -> ProgramResult {
+        let entry = &ctx.accounts.entry;
+        let authority = &ctx.accounts.authority;
+        let moderated = entry.creator != *authority.key;
+        if ix.slash_bond && !moderated {
+            return Err(ErrorCode::InsufficientAuthority.into());
+        }
+        if *ctx.accounts.rent_recipient.key != rent_recipient(&ctx.accounts.registry_config, &entry.rent_payer, moderated) {
+            return Err(ErrorCode::InvalidRentRecipient.into());
+        }
+        if let Some(bond) = load_bond(&entry.key(), &ctx.accounts.bond_vault)? {
+            if *ctx.accounts.bond_depositor.key != bond.depositor {
+                return Err(ErrorCode::InvalidBondVault.into());
+            }
+            let treasury = ctx.accounts.treasury.to_account_info();
+            // a slashed bond goes to the treasury, the vault rent always returns to the depositor
+            let slash_to = if ix.slash_bond { Some(&treasury) } else { None };
+            release_bond(&ctx.accounts.bond_vault, &bond, &ctx.accounts.bond_depositor, slash_to)?;
+            if ix.slash_bond {
+                ctx.accounts.registry_config.bonds_slashed += bond.amount;
+            }
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.entry_count = registry_config.entry_count.saturating_sub(1);
+        ctx.accounts.entry.close(ctx.accounts.rent_recipient.to_account_info()).unwrap();
+        Ok(())
+    }
+
+    pub fn reclaim_bond(ctx: Context<ReclaimBond>) -> ProgramResult {
+        let bond = load_bond(&ctx.accounts.entry.key(), &ctx.accounts.bond_vault)?.ok_or(ErrorCode::InvalidBondVault)?;
+        if bond.depositor != *ctx.accounts.depositor.key {
+            return Err(ErrorCode::InsufficientAuthority.into());
+        }
+        if Clock::get()?.unix_timestamp < bond.locked_until {
+            return Err(ErrorCode::BondLocked.into());
+        }
+        release_bond(&ctx.accounts.bond_vault, &bond, &ctx.accounts.depositor, None)
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn init_badges(ctx: Context<InitBadges>, ix: InitBadgesIx) -> ProgramResult {
+        let badge_definitions = &mut ctx.accounts.badge_definitions;
+        badge_definitions.bump = ix.bump;
+        badge_definitions.registry = *ctx.accounts.registry_config.to_account_info().key;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn define_badge(ctx: Context<DefineBadge>, ix: DefineBadgeIx) -> ProgramResult {
+        let badge_definitions = &mut ctx.accounts.badge_definitions;
+        if badge_definitions.names.len() >= MAX_BADGES || ix.name.len() > MAX_BADGE_NAME_LEN {
+            return Err(ErrorCode::InvalidBadge.into());
+        }
+        badge_definitions.names.push(ix.name);
+        Ok(())
+    }
+
+    pub fn verify_entry(ctx: Context<VerifyEntry>, ix: VerifyEntryIx) -> ProgramResult {
+        if ix.badge as usize >= ctx.accounts.badge_definitions.names.len() {
+            return Err(ErrorCode::InvalidBadge.into());
+        }
+        let now = Clock::get().unwrap().unix_timestamp;
+        let threshold = ctx.accounts.registry_config.verification_threshold.max(1);
         let entry = &mut ctx.accounts.entry;
-        entry.is_verified = true;
-        entry.verified_at = Clock::get().unwrap().unix_timestamp;
+        if !entry.badge_grants.iter().any(|grant| grant.badge == ix.badge) {
+            let size = entry_size(&ctx.accounts.registry_config, &entry.primary_key, &entry.data, entry.badge_grants.len() + 1)?;
+            if size > entry.to_account_info().data_len() {
+                resize_entry(&entry.to_account_info(), size, &ctx.accounts.payer, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+            }
+            entry.badge_grants.push(BadgeGrant { badge: ix.badge, attestations: 0, granted_by: None, granted_at: None, expires_at: None });
+        }
+        let attestation = &mut ctx.accounts.attestation;
+        // an attestation left over from a removed entry no longer counts and is taken over,
+        // its rent stays with whoever paid for it
+        if attestation.rent_payer == Pubkey::default() {
+            attestation.rent_payer = *ctx.accounts.payer.key;
+        } else if attestation.round == entry.verification_round {
+            return Err(ErrorCode::AlreadyAttested.into());
+        }
+        attestation.bump = ix.bump;
+        attestation.entry = *entry.to_account_info().key;
+        attestation.verifier = *ctx.accounts.verifier.key;
+        attestation.attested_at = now;
+        attestation.badge = ix.badge;
+        attestation.round = entry.verification_round;
+        entry.attestation_count = entry.attestation_count.checked_add(1).ok_or(ErrorCode::TooManyAttestations)?;
+        let grant = entry.badge_grants.iter_mut().find(|grant| grant.badge == ix.badge).unwrap();
+        grant.attestations = grant.attestations.checked_add(1).ok_or(ErrorCode::TooManyAttestations)?;
+        if grant.granted_at.is_none() && grant.attestations >= threshold {
+            grant.granted_by = Some(*ctx.accounts.verifier.key);
+            grant.granted_at = Some(now);
+            grant.expires_at = verification_expiry(&ctx.accounts.registry_config, now);
+            if entry.badges == 0 {
+                entry.verified_at = now;
+            }
+            entry.badges |= 1 << ix.badge;
+        }
         Ok(())
     }
 
     pub fn unverify_entry(ctx: Context<UnverifyEntry>) -> ProgramResult {
+        let badge = ctx.accounts.attestation.badge;
+        let threshold = ctx.accounts.registry_config.verification_threshold.max(1);
         let entry = &mut ctx.accounts.entry;
-        entry.is_verified = false;
+        // attestations made for a removed entry were already discounted
+        if ctx.accounts.attestation.round == entry.verification_round {
+            entry.attestation_count = entry.attestation_count.checked_sub(1).ok_or(ErrorCode::InvalidAttestation)?;
+            if let Some(index) = entry.badge_grants.iter().position(|grant| grant.badge == badge) {
+                let grant = &mut entry.badge_grants[index];
+                grant.attestations = grant.attestations.checked_sub(1).ok_or(ErrorCode::InvalidAttestation)?;
+                let attestations = grant.attestations;
+                if grant.granted_at.is_some() && attestations < threshold {
+                    grant.granted_by = None;
+                    grant.granted_at = None;
+                    grant.expires_at = None;
+                    entry.badges &= !(1 << badge);
+                }
+                if attestations == 0 {
+                    entry.badge_grants.remove(index);
+                }
+            }
+            if entry.badges == 0 {
+                entry.verified_at = 0;
+            }
+        }
+        ctx.accounts.attestation.close(ctx.accounts.rent_payer.to_account_info())
+    }
+
+    pub fn renew_verification(ctx: Context<RenewVerification>, ix: RenewVerificationIx) -> ProgramResult {
+        // a renewal needs a fresh threshold of verifiers, co-signers follow as (attestation, verifier, verifier_role) triples
+        let entry = ctx.accounts.entry.key();
+        let mut verifiers = vec![renewing_verifier(&entry, ix.badge, ctx.accounts.entry.verification_round, &ctx.accounts.attestation, &ctx.accounts.verifier)?];
+        for accounts in ctx.remaining_accounts.chunks(3) {
+            let verifier = match accounts {
+                [_, verifier, verifier_role] if !has_role(&ctx.accounts.registry_config, verifier_role, verifier.key, Role::Verifier) => {
+                    return Err(ErrorCode::InsufficientAuthority.into())
+                }
+                [attestation, verifier, _] => renewing_verifier(&entry, ix.badge, ctx.accounts.entry.verification_round, attestation, verifier)?,
+                _ => return Err(ErrorCode::InvalidAttestation.into()),
+            };
+            if !verifiers.contains(&verifier) {
+                verifiers.push(verifier);
+            }
+        }
+        if verifiers.len() < ctx.accounts.registry_config.verification_threshold.max(1) as usize {
+            return Err(ErrorCode::InsufficientAttestations.into());
+        }
+        let expires_at = verification_expiry(&ctx.accounts.registry_config, Clock::get().unwrap().unix_timestamp);
+        let grant = ctx.accounts.entry.badge_grants.iter_mut()
+            .find(|grant| grant.badge == ix.badge && grant.granted_at.is_some())
+            .ok_or(ErrorCode::BadgeNotGranted)?;
+        grant.expires_at = expires_at;
         Ok(())
     }
+
+    pub fn assert_verified(ctx: Context<AssertVerified>, ix: AssertVerifiedIx) -> ProgramResult {
+        let entry = &ctx.accounts.entry;
+        // an empty badge set accepts any badge
+        let holds = |badges: u32| if ix.badges == 0 { badges != 0 } else { badges & ix.badges == ix.badges };
+        if holds(active_badges(entry, Clock::get().unwrap().unix_timestamp)) {
+            return Ok(());
+        }
+        if holds(entry.badges) {
+            return Err(ErrorCode::VerificationExpired.into());
+        }
+        Err(ErrorCode::BadgeNotGranted.into())
+    }
+
+    // rewrites entries from before badges in place. Entries from before registry-bound seeds sit at
+    // [entry_seed, primary_key] without a bump or registry and cannot be moved to their current address here
+    pub fn migrate_entry(ctx: Context<MigrateEntry>) -> ProgramResult {
+        let entry_info = &ctx.accounts.entry;
+        if entry_info.owner != &ID {
+            return Err(ErrorCode::InvalidRegistry.into());
+        }
+        if predates_registry_seeds(entry_info, &ctx.accounts.registry_config.entry_seed)? {
+            return Err(ErrorCode::UnsupportedEntryLayout.into());
+        }
+        let mut entry = {
+            let data = entry_info.try_borrow_data()?;
+            // entries written before badges end where the badge fields begin, zeroes decode as no badges
+            // and the round their attestations were made in
+            let mut padded = data.to_vec();
+            padded.extend_from_slice(&[0; 16]);
+            EntryData::try_deserialize(&mut padded.as_slice())?
+        };
+        if entry.version == ENTRY_VERSION {
+            return Err(ErrorCode::EntryMigrated.into());
+        }
+        if entry.registry != *ctx.accounts.registry_config.to_account_info().key {
+            return Err(ErrorCode::InvalidRegistry.into());
+        }
+        // existing attestations already sit at the legacy badge address and keep counting towards it
+        let verified = entry.version == 1;
+        if verified || entry.attestation_count > 0 {
+            let granted_at = if verified { Some(entry.verified_at) } else { None };
+            entry.badge_grants = vec![BadgeGrant {
+                badge: LEGACY_VERIFIED_BADGE,
+                attestations: entry.attestation_count,
+                granted_by: None,
+                granted_at,
+                expires_at: granted_at.and_then(|granted_at| verification_expiry(&ctx.accounts.registry_config, granted_at)),
+            }];
+        }
+        if verified {
+            entry.badges = 1 << LEGACY_VERIFIED_BADGE;
+        }
+        entry.version = ENTRY_VERSION;
+        let size = entry_info.data_len() + 16 + entry.badge_grants.len() * BADGE_GRANT_SIZE;
+        resize_entry(entry_info, size, &ctx.accounts.payer, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+        let mut data = entry_info.try_borrow_mut_data()?;
+        entry.try_serialize(&mut data.as_mut())
+    }
     
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.creator_role, &ctx.accounts.creator, ctx.remaining_accounts))]
     pub fn add_schema(ctx: Context<AddSchema>, ix: AddSchemaIx) -> ProgramResult {
         let schema_version = ctx.accounts.registry_config.schema_version + 1;
         let schema = &mut ctx.accounts.schema;
+        schema.bump = ix.bump;
+        schema.registry = *ctx.accounts.registry_config.to_account_info().key;
         schema.data = ix.data;
         schema.created_at = Clock::get().unwrap().unix_timestamp;
         schema.version = schema_version;
@@ -83,6 +592,7 @@ pub mod permissionless_verifiable_schema_registry {
         Ok(())
     }
 
+    #[access_control(admin_authorized(&ctx.accounts.registry_config, &ctx.accounts.authority_role, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn remove_schema(ctx: Context<RemoveSchema>) -> ProgramResult {
         ctx.accounts.schema.close(ctx.accounts.authority.to_account_info()).unwrap();
         Ok(())
@@ -95,9 +605,20 @@ pub mod permissionless_verifiable_schema_registry {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitIx {
     pub bump: u8,
+    pub name: String,
     pub entry_seed: String,
     pub permissionless_add: bool,
     pub add_fee: u64,
+    pub require_proof: bool,
+    pub max_entries: u32,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub treasury_bump: u8,
+    pub treasurer: Option<Pubkey>,
+    pub bond_amount: u64,
+    pub bond_lock_period: i64,
+    pub verification_threshold: u8,
+    pub verification_ttl: i64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -106,6 +627,49 @@ pub struct AddEntryIx {
     pub primary_key: Vec<u8>,
     pub schema_version: u8,
     pub data: Vec<u8>,
+    pub proof: ProofMethod,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RemoveEntryIx {
+    pub slash_bond: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VerifyEntryIx {
+    pub bump: u8,
+    pub badge: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RenewVerificationIx {
+    pub badge: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AssertVerifiedIx {
+    pub badges: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitBadgesIx {
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct DefineBadgeIx {
+    pub name: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct WithdrawFeesIx {
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitFeeVaultIx {
+    pub vault_bump: u8,
+    pub token_fee: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -114,6 +678,35 @@ pub struct AddSchemaIx {
     pub data: Vec<u8>,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GrantRoleIx {
+    pub bump: u8,
+    pub role: Role,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetMultisigIx {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateConfigIx {
+    pub permissionless_add: Option<bool>,
+    pub entry_seed: Option<String>,
+    pub add_fee: Option<u64>,
+    pub require_proof: Option<bool>,
+    pub max_entries: Option<u32>,
+    pub max_entry_size: Option<u32>,
+    pub moderation_treasury: Option<Option<Pubkey>>,
+    pub treasurer: Option<Option<Pubkey>>,
+    pub token_fee: Option<u64>,
+    pub bond_amount: Option<u64>,
+    pub bond_lock_period: Option<i64>,
+    pub verification_threshold: Option<u8>,
+    pub verification_ttl: Option<i64>,
+}
+
 ///////////////// Contexts /////////////////
 
 #[derive(Accounts)]
@@ -123,102 +716,412 @@ pub struct Init<'info> {
         init,
         payer = authority,
         // extra space for future upgrades
-        space = 128,
-        seeds = [b"registry-config".as_ref()],
+        space = 1024,
+        seeds = [b"registry-config".as_ref(), ix.name.as_ref()],
         bump = ix.bump,
     )]
     pub registry_config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 1 + 32,
+        seeds = [b"treasury".as_ref(), registry_config.key().as_ref()],
+        bump = ix.treasury_bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct TransferAuthority<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
     pub new_authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(constraint = registry_config.pending_authority == Some(*new_authority.to_account_info().key) @ ErrorCode::InvalidPendingAuthority)]
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMultisig<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: GrantRoleIx)]
+pub struct GrantRole<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [b"role".as_ref(), registry_config.to_account_info().key.as_ref(), &[ix.role as u8], member.key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub role_member: Account<'info, RoleMember>,
+    pub member: AccountInfo<'info>,
+    pub admin: Signer<'info>,
+    pub admin_role: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"role".as_ref(), registry_config.to_account_info().key.as_ref(), &[role_member.role as u8], role_member.member.as_ref()],
+        bump = role_member.bump,
+        constraint = role_member.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub role_member: Account<'info, RoleMember>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub admin_role: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(ix: AddEntryIx)]
 pub struct AddEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
     #[account(
         init,
         payer = creator,
-        // extra space for future upgrades
-        space = 1024,
-        seeds = [registry_config.entry_seed.as_ref(), ix.primary_key.as_ref()],
+        // sized to the payload
+        space = ENTRY_BASE_SIZE + ix.primary_key.try_to_vec().unwrap().len() + ix.data.try_to_vec().unwrap().len(),
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), ix.primary_key.as_ref()],
         bump = ix.bump,
     )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.permissionless_add || (registry_config.authority == *creator.to_account_info().key) @ ErrorCode::InsufficientAuthority)]
     pub creator: Signer<'info>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
-    pub authority: AccountInfo<'info>,
+    #[account(mut, seeds = [b"treasury".as_ref(), registry_config.to_account_info().key.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> AddEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.permissionless_add {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.creator, remaining_accounts)
+    }
+}
+
 #[derive(Accounts)]
-pub struct VerifyEntry<'info> {
-    #[account(mut)]
+pub struct WithdrawFees<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(mut, seeds = [b"treasury".as_ref(), registry_config.to_account_info().key.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    pub authority: Signer<'info>,
     #[account(mut)]
-    pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
+    pub destination: AccountInfo<'info>,
+}
+
+impl<'info> WithdrawFees<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.treasurer == Some(*self.authority.key) {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.authority, remaining_accounts)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(ix: InitFeeVaultIx)]
+pub struct InitFeeVault<'info> {
+    // the fee mint is fixed once its vault exists so collected fees cannot be stranded in an old vault
+    #[account(
+        mut,
+        seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()],
+        bump = registry_config.bump,
+        constraint = registry_config.fee_mint.is_none() @ ErrorCode::FeeVaultInitialized,
+    )]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(seeds = [b"treasury".as_ref(), registry_config.to_account_info().key.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [b"fee-vault".as_ref(), registry_config.to_account_info().key.as_ref(), fee_mint.key.as_ref()],
+        bump = ix.vault_bump,
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    #[account(constraint = fee_mint.owner == &spl_token::id() @ ErrorCode::InvalidFeeAccount)]
+    pub fee_mint: AccountInfo<'info>,
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidFeeAccount)]
+    pub token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct UnverifyEntry<'info> {
+pub struct WithdrawTokenFees<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(seeds = [b"treasury".as_ref(), registry_config.to_account_info().key.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
     #[account(mut)]
+    pub fee_vault: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub destination: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::id() @ ErrorCode::InvalidFeeAccount)]
+    pub token_program: AccountInfo<'info>,
+}
+
+impl<'info> WithdrawTokenFees<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.treasurer == Some(*self.authority.key) {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.authority, remaining_accounts)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(ix: VerifyEntryIx)]
+pub struct VerifyEntry<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(
+        seeds = [b"badges".as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = badge_definitions.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [b"attestation".as_ref(), entry.key().as_ref(), verifier.key.as_ref(), badge_seed(&ix.badge)],
+        bump = ix.bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+    #[account(constraint = has_role(&registry_config, &verifier_role, verifier.key, Role::Verifier) @ ErrorCode::InsufficientAuthority)]
+    pub verifier: Signer<'info>,
+    pub verifier_role: AccountInfo<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnverifyEntry<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
-    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"attestation".as_ref(), entry.key().as_ref(), verifier.key.as_ref(), badge_seed(&attestation.badge)],
+        bump = attestation.bump,
+    )]
+    pub attestation: Account<'info, Attestation>,
+    pub verifier: Signer<'info>,
+    #[account(mut, constraint = attestation.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveEntry<'info> {
+#[instruction(ix: InitBadgesIx)]
+pub struct InitBadges<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = BADGE_DEFINITIONS_SIZE,
+        seeds = [b"badges".as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
+    pub authority: Signer<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DefineBadge<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"badges".as_ref(), registry_config.to_account_info().key.as_ref()],
+        bump = badge_definitions.bump,
+    )]
+    pub badge_definitions: Account<'info, BadgeDefinitions>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenewVerification<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    pub attestation: AccountInfo<'info>,
+    #[account(constraint = has_role(&registry_config, &verifier_role, verifier.key, Role::Verifier) @ ErrorCode::InsufficientAuthority)]
+    pub verifier: Signer<'info>,
+    pub verifier_role: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssertVerified<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEntry<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(mut)]
+    pub entry: AccountInfo<'info>,
     #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveEntry<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key || entry.creator == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
+    #[account(constraint =
+        entry.creator == *authority.to_account_info().key
+        || has_role(&registry_config, &moderator_role, authority.key, Role::Moderator)
+        @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub rent_recipient: AccountInfo<'info>,
+    pub moderator_role: AccountInfo<'info>,
+    #[account(mut)]
+    pub bond_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub bond_depositor: AccountInfo<'info>,
+    #[account(mut, seeds = [b"treasury".as_ref(), registry_config.to_account_info().key.as_ref()], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimBond<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.primary_key.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(mut)]
+    pub bond_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(ix: AddSchemaIx)]
 pub struct AddSchema<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
     #[account(
         init,
         payer = creator,
         // extra space for future upgrades
         space = 1024,
-        seeds = [b"schema".as_ref(), &[registry_config.schema_version + 1]],
+        seeds = [b"schema".as_ref(), registry_config.to_account_info().key.as_ref(), &[registry_config.schema_version + 1]],
         bump = ix.bump,
     )]
     pub schema: Account<'info, SchemaData>,
-    #[account(constraint = registry_config.authority == *creator.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub creator: Signer<'info>,
+    pub creator_role: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct RemoveSchema<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"schema".as_ref(), registry_config.to_account_info().key.as_ref(), &[schema.version]],
+        bump = schema.bump,
+        constraint = schema.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub schema: Account<'info, SchemaData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    pub authority_role: AccountInfo<'info>,
 }
 
 ///////////////// DATA /////////////////
@@ -226,37 +1129,451 @@ pub struct RemoveSchema<'info> {
 #[account]
 #[derive(Default)]
 pub struct RegistryConfig{
+    pub bump: u8,
+    pub name: String,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub multisig_signers: Vec<Pubkey>,
+    pub multisig_threshold: u8,
     pub entry_seed: String,
     pub permissionless_add: bool,
     pub schema_version: u8,
     pub add_fee: u64,
+    pub require_proof: bool,
+    pub max_entries: u32,
+    pub entry_count: u64,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub treasurer: Option<Pubkey>,
+    pub fees_collected: u64,
+    pub fee_mint: Option<Pubkey>,
+    pub fee_vault_bump: u8,
+    pub token_fee: u64,
+    pub token_fees_collected: u64,
+    pub bond_amount: u64,
+    pub bond_lock_period: i64,
+    pub bonds_slashed: u64,
+    pub verification_threshold: u8,
+    pub verification_ttl: i64,
+    pub verification_round: u64,
+}
+
+#[account]
+pub struct Treasury {
+    pub bump: u8,
+    pub registry: Pubkey,
+}
+
+#[account]
+pub struct BondVault {
+    pub bump: u8,
+    pub entry: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub locked_until: i64,
+}
+
+#[account]
+pub struct RoleMember {
+    pub bump: u8,
+    pub registry: Pubkey,
+    pub role: Role,
+    pub member: Pubkey,
+    pub granted_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum Role {
+    Admin,
+    Verifier,
+    Moderator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum ProofMethod {
+    None,
+    AddressSignature,
+    UpgradeAuthority,
 }
 
 #[account]
 pub struct EntryData {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub primary_key: Vec<u8>,
     pub creator: Pubkey,
     pub created_at: i64,
-    pub is_verified: bool,
+    pub version: u8,
     pub verified_at: i64,
     pub schema_version: u8,
     pub data: Vec<u8>,
+    pub proof: ProofMethod,
+    pub rent_payer: Pubkey,
+    pub attestation_count: u8,
+    pub badges: u32,
+    pub badge_grants: Vec<BadgeGrant>,
+    pub verification_round: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct BadgeGrant {
+    pub badge: u8,
+    pub attestations: u8,
+    pub granted_by: Option<Pubkey>,
+    pub granted_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+#[account]
+pub struct BadgeDefinitions {
+    pub bump: u8,
+    pub registry: Pubkey,
+    pub names: Vec<String>,
+}
+
+#[account]
+pub struct Attestation {
+    pub bump: u8,
+    pub entry: Pubkey,
+    pub verifier: Pubkey,
+    pub attested_at: i64,
+    pub rent_payer: Pubkey,
+    pub badge: u8,
+    pub round: u64,
 }
 
 #[account]
 pub struct SchemaData {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub created_at: i64,
     pub version: u8,
     pub data: Vec<u8>,
 }
 
+#[event]
+pub struct ConfigUpdated {
+    pub registry: Pubkey,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn authorized(registry_config: &RegistryConfig, signer: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    if signer.is_signer && registry_config.authority == *signer.key {
+        return Ok(());
+    }
+    if registry_config.multisig_threshold > 0 {
+        let mut approvals: Vec<&Pubkey> = Vec::new();
+        let signers = remaining_accounts.iter().map(|account| (account.key, account.is_signer));
+        for (key, is_signer) in std::iter::once((signer.key, signer.is_signer)).chain(signers) {
+            if is_signer && registry_config.multisig_signers.contains(key) && !approvals.contains(&key) {
+                approvals.push(key);
+            }
+        }
+        if approvals.len() >= registry_config.multisig_threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(ErrorCode::InsufficientAuthority.into())
+}
+
+// signers approved by the previous authority do not carry over to the next one
+pub fn clear_multisig(registry_config: &mut RegistryConfig) {
+    registry_config.multisig_signers.clear();
+    registry_config.multisig_threshold = 0;
+}
+
+pub fn config_changed<T: ToString>(registry: Pubkey, field: &str, old_value: &T, new_value: &T) {
+    emit!(ConfigUpdated {
+        registry,
+        field: field.to_string(),
+        old_value: old_value.to_string(),
+        new_value: new_value.to_string(),
+    });
+}
+
+pub fn verification_expiry(registry_config: &RegistryConfig, granted_at: i64) -> Option<i64> {
+    if registry_config.verification_ttl > 0 {
+        Some(granted_at + registry_config.verification_ttl)
+    } else {
+        None
+    }
+}
+
+// `badges` keeps the bits of expired grants, readers should go through these helpers
+pub fn active_badges(entry: &EntryData, now: i64) -> u32 {
+    entry.badge_grants.iter()
+        .filter(|grant| grant.granted_at.is_some() && !matches!(grant.expires_at, Some(expires_at) if expires_at <= now))
+        .fold(0, |badges, grant| badges | 1 << grant.badge)
+}
+
+// entries created before registry-bound seeds start with their primary key and sit at [seed, primary_key]
+pub fn predates_registry_seeds(entry: &AccountInfo, seed: &str) -> std::result::Result<bool, ProgramError> {
+    let data = entry.try_borrow_data()?;
+    let primary_key = match data.get(8..12).map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize) {
+        Some(len) if len <= MAX_SEED_LEN => data.get(12..12 + len),
+        _ => None,
+    };
+    Ok(match primary_key {
+        Some(primary_key) => Pubkey::find_program_address(&[seed.as_ref(), primary_key], &ID).0 == *entry.key,
+        None => false,
+    })
+}
+
+// drawn from the registry so a re-created entry never reuses the round of its stale attestations
+pub fn next_verification_round(registry_config: &mut RegistryConfig) -> u64 {
+    registry_config.verification_round += 1;
+    registry_config.verification_round
+}
+
+pub fn is_verified_at(entry: &EntryData, now: i64) -> bool {
+    active_badges(entry, now) != 0
+}
+
+// returns the signing verifier when `attestation` is its attestation of `badge` in the current round
+pub fn renewing_verifier(entry: &Pubkey, badge: u8, round: u64, attestation: &AccountInfo, verifier: &AccountInfo) -> std::result::Result<Pubkey, ProgramError> {
+    if attestation.owner != &ID || !verifier.is_signer {
+        return Err(ErrorCode::InvalidAttestation.into());
+    }
+    let record = Attestation::try_deserialize(&mut attestation.try_borrow_data()?.as_ref())?;
+    let address = Pubkey::create_program_address(
+        &[b"attestation".as_ref(), entry.as_ref(), verifier.key.as_ref(), badge_seed(&badge), &[record.bump]],
+        &ID,
+    )
+    .map_err(|_| ErrorCode::InvalidAttestation)?;
+    if address != *attestation.key || record.badge != badge || record.round != round {
+        return Err(ErrorCode::InvalidAttestation.into());
+    }
+    Ok(*verifier.key)
+}
+
+// the legacy badge keeps the attestation address used before badges existed, so old attestations stay withdrawable
+pub fn badge_seed(badge: &u8) -> &[u8] {
+    if *badge == LEGACY_VERIFIED_BADGE {
+        &[]
+    } else {
+        std::slice::from_ref(badge)
+    }
+}
+
+pub fn rent_recipient(registry_config: &RegistryConfig, rent_payer: &Pubkey, moderated: bool) -> Pubkey {
+    match registry_config.moderation_treasury {
+        Some(treasury) if moderated => treasury,
+        _ => *rent_payer,
+    }
+}
+
+pub fn has_role(registry_config: &ProgramAccount<RegistryConfig>, role_account: &AccountInfo, member: &Pubkey, role: Role) -> bool {
+    if role_account.owner != &ID {
+        return false;
+    }
+    let data = match role_account.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    match RoleMember::try_deserialize(&mut data.as_ref()) {
+        Ok(role_member) => {
+            role_member.registry == *registry_config.to_account_info().key
+                && role_member.member == *member
+                && role_member.role == role
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn admin_authorized(
+    registry_config: &ProgramAccount<RegistryConfig>,
+    role_account: &AccountInfo,
+    admin: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+) -> ProgramResult {
+    if admin.is_signer && has_role(registry_config, role_account, admin.key, Role::Admin) {
+        return Ok(());
+    }
+    authorized(registry_config, admin, remaining_accounts)
+}
+
+pub fn assert_proof(proof: ProofMethod, address: &Pubkey, creator: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    let proven = match proof {
+        ProofMethod::None => true,
+        ProofMethod::AddressSignature => std::iter::once((creator.key, creator.is_signer))
+            .chain(remaining_accounts.iter().map(|account| (account.key, account.is_signer)))
+            .any(|(key, is_signer)| key == address && is_signer),
+        ProofMethod::UpgradeAuthority => {
+            let (program_data, _) = Pubkey::find_program_address(&[address.as_ref()], &bpf_loader_upgradeable::id());
+            match remaining_accounts.iter().find(|account| *account.key == program_data) {
+                Some(account) => creator.is_signer && program_data_header(account)?.1 == Some(*creator.key),
+                None => false,
+            }
+        }
+    };
+    if !proven {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    Ok(())
+}
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProof.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+pub fn entry_size(registry_config: &RegistryConfig, primary_key: &[u8], data: &[u8], badge_grants: usize) -> std::result::Result<usize, ProgramError> {
+    let size = ENTRY_BASE_SIZE + primary_key.try_to_vec().unwrap().len() + data.try_to_vec().unwrap().len();
+    if registry_config.max_entry_size > 0 && size > registry_config.max_entry_size as usize {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    // badge grants do not count towards the payload limit
+    Ok(size + badge_grants * BADGE_GRANT_SIZE)
+}
+
+// growth is paid by `payer`, a shrink refunds `rent_payer`
+pub fn resize_entry<'info>(entry: &AccountInfo<'info>, size: usize, payer: &AccountInfo<'info>, rent_payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> ProgramResult {
+    let current_size = entry.data_len();
+    if size == current_size {
+        return Ok(());
+    }
+    if size > current_size + MAX_PERMITTED_DATA_INCREASE {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    let rent = Rent::get()?.minimum_balance(size);
+    if rent > entry.lamports() {
+        invoke(
+            &system_instruction::transfer(payer.key, entry.key, rent - entry.lamports()),
+            &[payer.clone(), entry.clone(), system_program.clone()],
+        )?;
+    } else {
+        let refund = entry.lamports() - rent;
+        **entry.try_borrow_mut_lamports()? -= refund;
+        **rent_payer.try_borrow_mut_lamports()? += refund;
+    }
+    // zero the grown tail, it may still hold bytes from an earlier shrink in this transaction
+    entry.realloc(size, true)
+}
+
+pub fn fee_vault_address(registry: &Pubkey, fee_mint: &Pubkey, bump: u8) -> std::result::Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(&[b"fee-vault".as_ref(), registry.as_ref(), fee_mint.as_ref(), &[bump]], &ID)
+        .map_err(|_| ErrorCode::InvalidFeeAccount.into())
+}
+
+// creates a program owned PDA, topping up instead of failing when someone already sent it lamports
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, lamports, space as u64, &ID),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+    let shortfall = lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, shortfall),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(&system_instruction::allocate(account.key, space as u64), &[account.clone(), system_program.clone()], &[seeds])?;
+    invoke_signed(&system_instruction::assign(account.key, &ID), &[account.clone(), system_program.clone()], &[seeds])
+}
+
+pub fn load_bond(entry: &Pubkey, bond_vault: &AccountInfo) -> std::result::Result<Option<BondVault>, ProgramError> {
+    // the vault address is fixed per entry so a moderator cannot be handed a decoy to skip slashing
+    let (address, _) = Pubkey::find_program_address(&[b"bond".as_ref(), entry.as_ref()], &ID);
+    if *bond_vault.key != address {
+        return Err(ErrorCode::InvalidBondVault.into());
+    }
+    if bond_vault.owner != &ID || bond_vault.data_is_empty() {
+        return Ok(None);
+    }
+    let data = bond_vault.try_borrow_data()?;
+    Ok(Some(BondVault::try_deserialize(&mut data.as_ref())?))
+}
+
+pub fn release_bond(bond_vault: &AccountInfo, bond: &BondVault, depositor: &AccountInfo, slash_to: Option<&AccountInfo>) -> ProgramResult {
+    let mut refund = bond_vault.lamports();
+    if let Some(treasury) = slash_to {
+        **treasury.try_borrow_mut_lamports()? += bond.amount;
+        refund -= bond.amount;
+    }
+    **depositor.try_borrow_mut_lamports()? += refund;
+    **bond_vault.try_borrow_mut_lamports()? = 0;
+    bond_vault.try_borrow_mut_data()?.fill(0);
+    Ok(())
+}
+
 ///////////////// ERRORS /////////////////
 
 #[error]
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry entry")]
     InsufficientAuthority,
+    #[msg("Signer is not the pending authority for this registry")]
+    InvalidPendingAuthority,
     #[msg("User does not have enough sol to add an entry to this registry")]
     InsufficientBalance,
-}
\ No newline at end of file
+    #[msg("Entry does not belong to this registry")]
+    InvalidRegistry,
+    #[msg("Multisig signers or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Creator did not prove control of the entry address")]
+    InvalidProof,
+    #[msg("Seed fields cannot change once the registry has entries")]
+    SeedLocked,
+    #[msg("Registry has reached its maximum number of entries")]
+    RegistryFull,
+    #[msg("Entry data exceeds the maximum entry size for this registry")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer or registry treasury")]
+    InvalidRentRecipient,
+    #[msg("Treasury does not hold enough fees to withdraw this amount")]
+    InsufficientTreasuryBalance,
+    #[msg("Fee vault, mint or token program does not match the registry fee configuration")]
+    InvalidFeeAccount,
+    #[msg("Bond vault does not match the entry or its depositor")]
+    InvalidBondVault,
+    #[msg("Bond is still locked")]
+    BondLocked,
+    #[msg("Badge is not defined for this registry or the badge limit is reached")]
+    InvalidBadge,
+    #[msg("Entry already uses the badge layout")]
+    EntryMigrated,
+    #[msg("Entry does not hold the requested badge")]
+    BadgeNotGranted,
+    #[msg("Entry verification has expired")]
+    VerificationExpired,
+    #[msg("Registry fee vault is already initialized")]
+    FeeVaultInitialized,
+    #[msg("Verifier already attested the badge in the current round")]
+    AlreadyAttested,
+    #[msg("Attestation is not counted towards this entry")]
+    InvalidAttestation,
+    #[msg("Not enough verifiers renewed the badge")]
+    InsufficientAttestations,
+    #[msg("Entry has reached the maximum number of attestations")]
+    TooManyAttestations,
+    #[msg("Entry predates registry-bound seeds and cannot be migrated")]
+    UnsupportedEntryLayout,
+}
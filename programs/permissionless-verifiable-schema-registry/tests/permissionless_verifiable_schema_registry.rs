@@ -0,0 +1,1023 @@
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use permissionless_verifiable_schema_registry::{
+    accounts, instruction, AddEntryIx, Attestation, BondVault, DefineBadgeIx, EntryData, ErrorCode, GrantRoleIx, InitBadgesIx,
+    InitFeeVaultIx, InitIx, ProofMethod, RegistryConfig, RemoveEntryIx, RenewVerificationIx, Role, RoleMember, SetMultisigIx,
+    UpdateConfigIx, VerifyEntryIx, WithdrawFeesIx,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+const BADGE_GRANT_SIZE: usize = 1 + 1 + 33 + 9 + 9;
+const ENTRY_SEED: &str = "entry";
+
+fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "permissionless_verifiable_schema_registry",
+        permissionless_verifiable_schema_registry::id(),
+        processor!(permissionless_verifiable_schema_registry::entry),
+    );
+    program_test.add_program("spl_token", spl_token::id(), processor!(spl_token::processor::Processor::process));
+    program_test
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn account_size(context: &mut ProgramTestContext, address: Pubkey) -> (usize, u64) {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    (account.data.len(), account.lamports)
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &permissionless_verifiable_schema_registry::id())
+}
+
+async fn init(context: &mut ProgramTestContext, configure: impl FnOnce(&mut InitIx)) -> Pubkey {
+    let (registry_config, bump) = pda(&[b"registry-config", b"registry"]);
+    let (treasury, treasury_bump) = pda(&[b"treasury", registry_config.as_ref()]);
+    let mut ix = InitIx {
+        bump,
+        name: "registry".to_string(),
+        entry_seed: ENTRY_SEED.to_string(),
+        permissionless_add: true,
+        add_fee: 0,
+        require_proof: false,
+        max_entries: 0,
+        max_entry_size: 0,
+        moderation_treasury: None,
+        treasury_bump,
+        treasurer: None,
+        bond_amount: 0,
+        bond_lock_period: 0,
+        verification_threshold: 1,
+        verification_ttl: 0,
+    };
+    configure(&mut ix);
+    let init = Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::Init { registry_config, treasury, authority: context.payer.pubkey(), system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::Init { ix }.data(),
+    };
+    process(context, &[init], &[]).await.unwrap();
+    registry_config
+}
+
+fn entry_address(registry_config: &Pubkey, primary_key: &[u8]) -> (Pubkey, u8) {
+    pda(&[ENTRY_SEED.as_bytes(), registry_config.as_ref(), primary_key])
+}
+
+// registries without token fees or bonds take no further accounts
+fn add_entry_ix(registry_config: Pubkey, creator: Pubkey, primary_key: &[u8], data: &[u8]) -> Instruction {
+    let (entry, bump) = entry_address(&registry_config, primary_key);
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::AddEntry {
+            registry_config,
+            entry,
+            creator,
+            treasury: pda(&[b"treasury", registry_config.as_ref()]).0,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::AddEntry {
+            ix: AddEntryIx { bump, primary_key: primary_key.to_vec(), schema_version: 0, data: data.to_vec(), proof: ProofMethod::None },
+        }
+        .data(),
+    }
+}
+
+async fn add_entry(context: &mut ProgramTestContext, registry_config: Pubkey, data: &[u8]) -> Pubkey {
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let ix = add_entry_ix(registry_config, context.payer.pubkey(), &primary_key, data);
+    process(context, &[ix], &[]).await.unwrap();
+    entry_address(&registry_config, &primary_key).0
+}
+
+fn remove_entry_ix(registry_config: Pubkey, entry: Pubkey, authority: Pubkey, rent_recipient: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::RemoveEntry {
+            registry_config,
+            entry,
+            authority,
+            rent_recipient,
+            moderator_role: system_program::id(),
+            bond_vault: pda(&[b"bond", entry.as_ref()]).0,
+            bond_depositor: authority,
+            treasury: pda(&[b"treasury", registry_config.as_ref()]).0,
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveEntry { ix: RemoveEntryIx { slash_bond: false } }.data(),
+    }
+}
+
+fn update_config_ix(registry_config: Pubkey, authority: Pubkey, ix: UpdateConfigIx) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::UpdateConfig { registry_config, authority }.to_account_metas(None),
+        data: instruction::UpdateConfig { ix }.data(),
+    }
+}
+
+fn config_update(configure: impl FnOnce(&mut UpdateConfigIx)) -> UpdateConfigIx {
+    let mut ix = UpdateConfigIx {
+        permissionless_add: None,
+        entry_seed: None,
+        add_fee: None,
+        require_proof: None,
+        max_entries: None,
+        max_entry_size: None,
+        moderation_treasury: None,
+        treasurer: None,
+        token_fee: None,
+        bond_amount: None,
+        bond_lock_period: None,
+        verification_threshold: None,
+        verification_ttl: None,
+    };
+    configure(&mut ix);
+    ix
+}
+
+fn role_address(registry_config: &Pubkey, role: Role, member: &Pubkey) -> (Pubkey, u8) {
+    pda(&[b"role", registry_config.as_ref(), &[role as u8], member.as_ref()])
+}
+
+async fn grant_role(context: &mut ProgramTestContext, registry_config: Pubkey, role: Role, member: Pubkey) -> Pubkey {
+    let (role_member, bump) = role_address(&registry_config, role, &member);
+    let payer = context.payer.pubkey();
+    let ix = Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::GrantRole {
+            registry_config,
+            role_member,
+            member,
+            admin: payer,
+            admin_role: system_program::id(),
+            payer,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::GrantRole { ix: GrantRoleIx { bump, role } }.data(),
+    };
+    process(context, &[ix], &[]).await.unwrap();
+    role_member
+}
+
+async fn init_badges(context: &mut ProgramTestContext, registry_config: Pubkey, names: &[&str]) -> Pubkey {
+    let (badge_definitions, bump) = pda(&[b"badges", registry_config.as_ref()]);
+    let payer = context.payer.pubkey();
+    let mut instructions = vec![Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::InitBadges { registry_config, badge_definitions, authority: payer, payer, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::InitBadges { ix: InitBadgesIx { bump } }.data(),
+    }];
+    for name in names {
+        instructions.push(Instruction {
+            program_id: permissionless_verifiable_schema_registry::id(),
+            accounts: accounts::DefineBadge { registry_config, badge_definitions, authority: payer }.to_account_metas(None),
+            data: instruction::DefineBadge { ix: DefineBadgeIx { name: name.to_string() } }.data(),
+        });
+    }
+    process(context, &instructions, &[]).await.unwrap();
+    badge_definitions
+}
+
+fn revoke_role_ix(registry_config: Pubkey, role_member: Pubkey, admin: Pubkey, admin_role: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::RevokeRole { registry_config, role_member, admin, admin_role }.to_account_metas(None),
+        data: instruction::RevokeRole {}.data(),
+    }
+}
+
+fn attestation_address(entry: &Pubkey, verifier: &Pubkey, badge: u8) -> (Pubkey, u8) {
+    // the legacy badge keeps the attestation address used before badges existed
+    let badge_seed: &[u8] = if badge == 0 { &[] } else { std::slice::from_ref(&badge) };
+    pda(&[b"attestation", entry.as_ref(), verifier.as_ref(), badge_seed])
+}
+
+fn verify_entry_ix(registry_config: Pubkey, entry: Pubkey, verifier: Pubkey, payer: Pubkey, badge: u8) -> Instruction {
+    let (attestation, bump) = attestation_address(&entry, &verifier, badge);
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::VerifyEntry {
+            registry_config,
+            entry,
+            badge_definitions: pda(&[b"badges", registry_config.as_ref()]).0,
+            attestation,
+            verifier,
+            verifier_role: role_address(&registry_config, Role::Verifier, &verifier).0,
+            payer,
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::VerifyEntry { ix: VerifyEntryIx { bump, badge } }.data(),
+    }
+}
+
+fn unverify_entry_ix(registry_config: Pubkey, entry: Pubkey, verifier: Pubkey, badge: u8, rent_payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::UnverifyEntry { registry_config, entry, attestation: attestation_address(&entry, &verifier, badge).0, verifier, rent_payer }
+            .to_account_metas(None),
+        data: instruction::UnverifyEntry {}.data(),
+    }
+}
+
+// co-signers follow the first verifier as (attestation, verifier, verifier_role) triples
+fn renew_verification_ix(registry_config: Pubkey, entry: Pubkey, verifiers: &[Pubkey], badge: u8) -> Instruction {
+    let mut accounts = accounts::RenewVerification {
+        registry_config,
+        entry,
+        attestation: attestation_address(&entry, &verifiers[0], badge).0,
+        verifier: verifiers[0],
+        verifier_role: role_address(&registry_config, Role::Verifier, &verifiers[0]).0,
+    }
+    .to_account_metas(None);
+    for verifier in &verifiers[1..] {
+        accounts.push(AccountMeta::new_readonly(attestation_address(&entry, verifier, badge).0, false));
+        accounts.push(AccountMeta::new_readonly(*verifier, true));
+        accounts.push(AccountMeta::new_readonly(role_address(&registry_config, Role::Verifier, verifier).0, false));
+    }
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts,
+        data: instruction::RenewVerification { ix: RenewVerificationIx { badge } }.data(),
+    }
+}
+
+fn migrate_entry_ix(registry_config: Pubkey, entry: Pubkey, payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::MigrateEntry { registry_config, entry, payer, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::MigrateEntry {}.data(),
+    }
+}
+
+fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: permissionless_verifiable_schema_registry::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// entries have no update instruction, badge grants are the only thing that resizes them
+fn propose_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::ProposeAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::ProposeAuthority {}.data(),
+    }
+}
+
+fn accept_authority_ix(registry_config: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::AcceptAuthority { registry_config, new_authority }.to_account_metas(None),
+        data: instruction::AcceptAuthority {}.data(),
+    }
+}
+
+fn cancel_authority_transfer_ix(registry_config: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::CancelAuthorityTransfer { registry_config, authority }.to_account_metas(None),
+        data: instruction::CancelAuthorityTransfer {}.data(),
+    }
+}
+
+fn set_multisig_ix(registry_config: Pubkey, authority: Pubkey, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::SetMultisig { registry_config, authority }.to_account_metas(None),
+        data: instruction::SetMultisig { ix: SetMultisigIx { signers, threshold } }.data(),
+    }
+}
+
+fn transfer_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::TransferAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::TransferAuthority {}.data(),
+    }
+}
+
+// token fee accounts and the bond vault follow the named accounts
+fn with_accounts(mut ix: Instruction, accounts: &[AccountMeta]) -> Instruction {
+    ix.accounts.extend_from_slice(accounts);
+    ix
+}
+
+fn token_fee_accounts(creator_token_account: Pubkey, fee_vault: Pubkey) -> [AccountMeta; 3] {
+    [AccountMeta::new(creator_token_account, false), AccountMeta::new(fee_vault, false), AccountMeta::new_readonly(spl_token::id(), false)]
+}
+
+async fn create_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Keypair::new();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer, None, 0).unwrap(),
+    ];
+    process(context, &instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_account(context: &mut ProgramTestContext, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let account = Keypair::new();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &account.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), &mint, &owner).unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &mint, &account.pubkey(), &payer, &[], amount).unwrap(),
+    ];
+    process(context, &instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+async fn token_balance(context: &mut ProgramTestContext, account: Pubkey) -> u64 {
+    let account = context.banks_client.get_account(account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+async fn init_fee_vault(context: &mut ProgramTestContext, registry_config: Pubkey, fee_mint: Pubkey, token_fee: u64) -> Pubkey {
+    let (fee_vault, vault_bump) = pda(&[b"fee-vault", registry_config.as_ref(), fee_mint.as_ref()]);
+    let payer = context.payer.pubkey();
+    let ix = Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::InitFeeVault {
+            registry_config,
+            treasury: pda(&[b"treasury", registry_config.as_ref()]).0,
+            fee_vault,
+            fee_mint,
+            authority: payer,
+            payer,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::InitFeeVault { ix: InitFeeVaultIx { vault_bump, token_fee } }.data(),
+    };
+    process(context, &[ix], &[]).await.unwrap();
+    fee_vault
+}
+
+fn withdraw_token_fees_ix(registry_config: Pubkey, fee_vault: Pubkey, authority: Pubkey, destination: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_schema_registry::id(),
+        accounts: accounts::WithdrawTokenFees {
+            registry_config,
+            treasury: pda(&[b"treasury", registry_config.as_ref()]).0,
+            fee_vault,
+            authority,
+            destination,
+            token_program: spl_token::id(),
+        }
+        .to_account_metas(None),
+        data: instruction::WithdrawTokenFees { ix: WithdrawFeesIx { amount } }.data(),
+    }
+}
+
+// co-signers approve through the remaining accounts
+fn with_signers(mut ix: Instruction, signers: &[&Keypair]) -> Instruction {
+    ix.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    ix
+}
+
+#[tokio::test]
+async fn verify_entry_grows_the_account_for_each_new_badge() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified", "audited"]).await;
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    let (base_size, _) = account_size(&mut context, entry).await;
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 1)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, base_size + BADGE_GRANT_SIZE);
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, base_size + 2 * BADGE_GRANT_SIZE);
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.badge_grants.len(), 2);
+    assert_eq!(entry_data.badges, 0b11);
+}
+
+#[tokio::test]
+async fn a_second_attestation_of_a_badge_does_not_resize() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.verification_threshold = 2).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let second_verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    grant_role(&mut context, registry_config, Role::Verifier, second_verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await.unwrap();
+    let (size, _) = account_size(&mut context, entry).await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, second_verifier.pubkey(), payer, 0)], &[&second_verifier])
+        .await
+        .unwrap();
+    assert_eq!(account_size(&mut context, entry).await.0, size);
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.badge_grants[0].attestations, 2);
+    assert_eq!(entry_data.badges, 1);
+}
+
+#[tokio::test]
+async fn verify_entry_requires_the_verifier_role() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.badge_grants.len(), 0);
+}
+
+#[tokio::test]
+async fn proposed_authority_takes_over_once_it_accepts() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, payer);
+    assert_eq!(config.pending_authority, Some(new_authority.pubkey()));
+
+    process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.pending_authority, None);
+
+    // the previous authority lost its rights
+    let result = process(&mut context, &[propose_authority_ix(registry_config, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn only_the_pending_authority_can_accept() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+    let impostor = Keypair::new();
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let result = process(&mut context, &[accept_authority_ix(registry_config, impostor.pubkey())], &[&impostor]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.authority, payer);
+}
+
+#[tokio::test]
+async fn cancelled_handoff_cannot_be_accepted() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    process(&mut context, &[cancel_authority_transfer_ix(registry_config, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, None);
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+}
+
+#[tokio::test]
+async fn only_the_authority_can_propose_or_cancel() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let stranger = Keypair::new();
+
+    let result = process(&mut context, &[propose_authority_ix(registry_config, stranger.pubkey(), stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[cancel_authority_transfer_ix(registry_config, stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority.is_some());
+}
+
+#[tokio::test]
+async fn revoked_verifier_can_no_longer_attest() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified", "audited"]).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+
+    let (_, role_lamports) = account_size(&mut context, role_member).await;
+    let balance = context.banks_client.get_balance(payer).await.unwrap();
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, payer, system_program::id())], &[]).await.unwrap();
+    assert!(context.banks_client.get_account(role_member).await.unwrap().is_none());
+    // the role rent goes back to the admin, minus the fee it paid as fee payer
+    assert_eq!(context.banks_client.get_balance(payer).await.unwrap(), balance + role_lamports - 5000);
+
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 1)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn admin_role_members_can_revoke_roles() {
+    let mut context = program_test().start_with_context().await;
+    let registry_config = init(&mut context, |_| {}).await;
+    let admin = Keypair::new();
+    let admin_role = grant_role(&mut context, registry_config, Role::Admin, admin.pubkey()).await;
+    let role_member = grant_role(&mut context, registry_config, Role::Moderator, Pubkey::new_unique()).await;
+
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, admin.pubkey(), admin_role)], &[&admin]).await.unwrap();
+    assert!(context.banks_client.get_account(role_member).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn revoke_role_requires_an_admin() {
+    let mut context = program_test().start_with_context().await;
+    let registry_config = init(&mut context, |_| {}).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+
+    // holding another role is not enough
+    let result = process(&mut context, &[revoke_role_ix(registry_config, role_member, verifier.pubkey(), role_member)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let role: RoleMember = fetch(&mut context, role_member).await;
+    assert_eq!(role.member, verifier.pubkey());
+    assert_eq!(role.role, Role::Verifier);
+}
+
+#[tokio::test]
+async fn multisig_members_act_once_the_threshold_is_met() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let (member_a, member_b) = (Keypair::new(), Keypair::new());
+    let signers = vec![member_a.pubkey(), member_b.pubkey()];
+    process(&mut context, &[set_multisig_ix(registry_config, payer, signers.clone(), 2)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.multisig_signers, signers);
+    assert_eq!(config.multisig_threshold, 2);
+
+    let new_authority = Pubkey::new_unique();
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member_a.pubkey(), new_authority)], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // a member listed twice still counts once
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_a]);
+    let result = process(&mut context, &[ix], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_b]);
+    process(&mut context, &[ix], &[&member_a, &member_b]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, Some(new_authority));
+}
+
+#[tokio::test]
+async fn set_multisig_rejects_invalid_signer_sets() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Pubkey::new_unique();
+
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member], 2)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member, member], 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let too_many = (0..11).map(|_| Pubkey::new_unique()).collect();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, too_many, 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, stranger.pubkey(), vec![stranger.pubkey()], 1)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn handing_over_authority_clears_the_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Keypair::new();
+    process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member.pubkey()], 1)], &[]).await.unwrap();
+
+    let new_authority = Keypair::new();
+    process(&mut context, &[transfer_authority_ix(registry_config, payer, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), member.pubkey())], &[&member]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // the same holds for the two step handoff
+    let authority = new_authority;
+    process(&mut context, &[set_multisig_ix(registry_config, authority.pubkey(), vec![member.pubkey()], 1)], &[&authority]).await.unwrap();
+    let next_authority = Keypair::new();
+    process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), next_authority.pubkey())], &[&member]).await.unwrap();
+    process(&mut context, &[accept_authority_ix(registry_config, next_authority.pubkey())], &[&next_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, next_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+}
+
+#[tokio::test]
+async fn update_config_changes_only_the_given_fields() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entry_size = 500).await;
+
+    let treasurer = Pubkey::new_unique();
+    let ix = config_update(|ix| {
+        ix.permissionless_add = Some(false);
+        ix.entry_seed = Some("renamed".to_string());
+        ix.add_fee = Some(1_000);
+        ix.max_entries = Some(7);
+        ix.treasurer = Some(Some(treasurer));
+        ix.bond_lock_period = Some(3600);
+    });
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert!(!config.permissionless_add);
+    assert_eq!(config.entry_seed, "renamed");
+    assert_eq!(config.add_fee, 1_000);
+    assert!(!config.require_proof);
+    assert_eq!(config.max_entries, 7);
+    assert_eq!(config.max_entry_size, 500);
+    assert_eq!(config.treasurer, Some(treasurer));
+    assert_eq!(config.bond_lock_period, 3600);
+
+    let stranger = Keypair::new();
+    let ix = config_update(|ix| ix.add_fee = Some(0));
+    let result = process(&mut context, &[update_config_ix(registry_config, stranger.pubkey(), ix)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.add_fee, 1_000);
+}
+
+#[tokio::test]
+async fn entry_seed_is_locked_once_the_registry_has_entries() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+
+    let ix = config_update(|ix| ix.entry_seed = Some("renamed".to_string()));
+    let result = process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await;
+    assert_error(result, ErrorCode::SeedLocked);
+
+    // the seed is free again once the last entry is gone
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer)], &[]).await.unwrap();
+    let ix = config_update(|ix| ix.entry_seed = Some("moved".to_string()));
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_seed, "moved");
+}
+
+#[tokio::test]
+async fn max_entries_caps_the_registry_until_an_entry_is_removed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entries = 1).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, &Pubkey::new_unique().to_bytes(), b"data")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 0);
+    add_entry(&mut context, registry_config, b"data").await;
+
+    // raising the cap makes room without removing anything
+    process(&mut context, &[update_config_ix(registry_config, payer, config_update(|ix| ix.max_entries = Some(2)))], &[]).await.unwrap();
+    add_entry(&mut context, registry_config, b"data").await;
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, &Pubkey::new_unique().to_bytes(), b"data")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 2);
+}
+
+#[tokio::test]
+async fn token_fee_is_collected_into_the_fee_vault_and_withdrawn() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let fee_mint = create_mint(&mut context).await;
+    let creator_token_account = create_token_account(&mut context, fee_mint, payer, 100).await;
+    let fee_vault = init_fee_vault(&mut context, registry_config, fee_mint, 30).await;
+
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let charged = token_fee_accounts(creator_token_account, fee_vault);
+    process(&mut context, &[with_accounts(add_entry_ix(registry_config, payer, &primary_key, b"data"), &charged)], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut context, creator_token_account).await, 70);
+    assert_eq!(token_balance(&mut context, fee_vault).await, 30);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.token_fees_collected, 30);
+
+    let destination = create_token_account(&mut context, fee_mint, payer, 0).await;
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[withdraw_token_fees_ix(registry_config, fee_vault, stranger.pubkey(), destination, 30)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    process(&mut context, &[withdraw_token_fees_ix(registry_config, fee_vault, payer, destination, 30)], &[]).await.unwrap();
+    assert_eq!(token_balance(&mut context, destination).await, 30);
+    assert_eq!(token_balance(&mut context, fee_vault).await, 0);
+}
+
+#[tokio::test]
+async fn token_fee_requires_the_registry_fee_vault() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let fee_mint = create_mint(&mut context).await;
+    let creator_token_account = create_token_account(&mut context, fee_mint, payer, 100).await;
+    let fee_vault = init_fee_vault(&mut context, registry_config, fee_mint, 30).await;
+    let decoy = create_token_account(&mut context, fee_mint, payer, 0).await;
+
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, &primary_key, b"data")], &[]).await;
+    assert_error(result, ErrorCode::InvalidFeeAccount);
+    let ix = with_accounts(add_entry_ix(registry_config, payer, &primary_key, b"data"), &token_fee_accounts(creator_token_account, decoy));
+    assert_error(process(&mut context, &[ix], &[]).await, ErrorCode::InvalidFeeAccount);
+    assert_eq!(token_balance(&mut context, creator_token_account).await, 100);
+    assert_eq!(token_balance(&mut context, fee_vault).await, 0);
+    assert!(context.banks_client.get_account(entry_address(&registry_config, &primary_key).0).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn bond_vault_is_only_needed_when_the_registry_takes_a_bond() {
+    let bond_amount = 1_000_000;
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.bond_amount = bond_amount).await;
+
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let (entry, _) = entry_address(&registry_config, &primary_key);
+    let bond_vault = pda(&[b"bond", entry.as_ref()]).0;
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, &primary_key, b"data")], &[]).await;
+    assert_error(result, ErrorCode::InvalidBondVault);
+    let ix = with_accounts(add_entry_ix(registry_config, payer, &primary_key, b"data"), &[AccountMeta::new(Pubkey::new_unique(), false)]);
+    assert_error(process(&mut context, &[ix], &[]).await, ErrorCode::InvalidBondVault);
+
+    process(&mut context, &[with_accounts(add_entry_ix(registry_config, payer, &primary_key, b"data"), &[AccountMeta::new(bond_vault, false)])], &[]).await.unwrap();
+    let bond: BondVault = fetch(&mut context, bond_vault).await;
+    assert_eq!(bond.entry, entry);
+    assert_eq!(bond.depositor, payer);
+    assert_eq!(bond.amount, bond_amount);
+
+    // the bond comes back with the entry
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer)], &[]).await.unwrap();
+    assert!(context.banks_client.get_account(bond_vault).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn withdrawing_the_last_attestation_clears_verified_at() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    grant_role(&mut context, registry_config, Role::Verifier, payer).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, payer, 0)], &[]).await.unwrap();
+    assert_ne!(fetch::<EntryData>(&mut context, entry).await.verified_at, 0);
+
+    process(&mut context, &[unverify_entry_ix(registry_config, entry, payer, 0, payer)], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.badges, 0);
+    assert_eq!(entry_data.attestation_count, 0);
+    assert_eq!(entry_data.verified_at, 0);
+}
+
+#[tokio::test]
+async fn verifier_attests_once_per_round() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+
+    // a second payer keeps the transaction distinct from the first attestation
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let ix = verify_entry_ix(registry_config, entry, verifier.pubkey(), other_payer.pubkey(), 0);
+    let result = process(&mut context, &[ix], &[&verifier, &other_payer]).await;
+    assert_error(result, ErrorCode::AlreadyAttested);
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.attestation_count, 1);
+}
+
+#[tokio::test]
+async fn re_created_entry_does_not_inherit_attestations() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let (entry, _) = entry_address(&registry_config, &primary_key);
+    process(&mut context, &[add_entry_ix(registry_config, payer, &primary_key, b"first")], &[]).await.unwrap();
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let first_round = fetch::<EntryData>(&mut context, entry).await.verification_round;
+
+    // attested entries can be removed, their attestations stay behind until withdrawn
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer)], &[]).await.unwrap();
+    process(&mut context, &[add_entry_ix(registry_config, payer, &primary_key, b"second")], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(entry_data.verification_round > first_round);
+    assert_eq!(entry_data.attestation_count, 0);
+    assert_eq!(entry_data.badges, 0);
+
+    // withdrawing the stale attestation refunds its rent and leaves the new entry alone
+    let attestation = attestation_address(&entry, &verifier.pubkey(), 0).0;
+    process(&mut context, &[unverify_entry_ix(registry_config, entry, verifier.pubkey(), 0, payer)], &[&verifier]).await.unwrap();
+    assert!(context.banks_client.get_account(attestation).await.unwrap().is_none());
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.attestation_count, 0);
+
+    // a stale attestation left in place is taken over by the next attestation, its rent stays with the first payer
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let ix = verify_entry_ix(registry_config, entry, verifier.pubkey(), other_payer.pubkey(), 0);
+    process(&mut context, &[ix], &[&verifier, &other_payer]).await.unwrap();
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer)], &[]).await.unwrap();
+    process(&mut context, &[add_entry_ix(registry_config, payer, &primary_key, b"third")], &[]).await.unwrap();
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.attestation_count, 1);
+    assert_eq!(entry_data.badges, 1);
+    let record: Attestation = fetch(&mut context, attestation).await;
+    assert_eq!(record.round, entry_data.verification_round);
+    assert_eq!(record.rent_payer, other_payer.pubkey());
+}
+
+#[tokio::test]
+async fn pre_badge_entry_migrates_in_place() {
+    let mut program_test = program_test();
+    let (registry_config, _) = pda(&[b"registry-config", b"registry"]);
+    let primary_key = Pubkey::new_unique().to_bytes();
+    let (entry, bump) = entry_address(&registry_config, &primary_key);
+    let legacy = EntryData {
+        bump,
+        registry: registry_config,
+        primary_key: primary_key.to_vec(),
+        creator: Pubkey::new_unique(),
+        created_at: 1,
+        // the version byte held `is_verified` before badges
+        version: 1,
+        verified_at: 2,
+        schema_version: 0,
+        data: b"legacy".to_vec(),
+        proof: ProofMethod::None,
+        rent_payer: Pubkey::new_unique(),
+        attestation_count: 1,
+        badges: 0,
+        badge_grants: vec![],
+        verification_round: 0,
+    };
+    let mut data = vec![];
+    legacy.try_serialize(&mut data).unwrap();
+    // the pre-badge layout ends before the badge fields and the round
+    data.truncate(data.len() - 16);
+    let legacy_size = data.len();
+    program_test.add_account(entry, program_account(data));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    init(&mut context, |_| {}).await;
+
+    process(&mut context, &[migrate_entry_ix(registry_config, entry, payer)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, legacy_size + 16 + BADGE_GRANT_SIZE);
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.version, 2);
+    assert_eq!(entry_data.badges, 1);
+    assert_eq!(entry_data.badge_grants[0].attestations, 1);
+    assert_eq!(entry_data.badge_grants[0].granted_at, Some(2));
+    assert_eq!(entry_data.data, b"legacy");
+
+    // a second payer keeps the transaction distinct from the migration
+    let other_payer = Keypair::new();
+    process(&mut context, &[system_instruction::transfer(&payer, &other_payer.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let result = process(&mut context, &[migrate_entry_ix(registry_config, entry, other_payer.pubkey())], &[&other_payer]).await;
+    assert_error(result, ErrorCode::EntryMigrated);
+}
+
+#[tokio::test]
+async fn entries_from_before_registry_seeds_are_not_migrated() {
+    let mut program_test = program_test();
+    let primary_key = Pubkey::new_unique().to_bytes();
+    // the baseline layout: no bump or registry, and the entry sits at [entry_seed, primary_key]
+    let (entry, _) = pda(&[ENTRY_SEED.as_bytes(), &primary_key]);
+    let mut data = EntryData::discriminator().to_vec();
+    (primary_key.to_vec(), Pubkey::new_unique(), 1i64, true, 2i64, 0u8, b"baseline".to_vec()).serialize(&mut data).unwrap();
+    data.resize(1024, 0);
+    program_test.add_account(entry, program_account(data));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+
+    let result = process(&mut context, &[migrate_entry_ix(registry_config, entry, payer)], &[]).await;
+    assert_error(result, ErrorCode::UnsupportedEntryLayout);
+}
+
+#[tokio::test]
+async fn revoked_verifier_cannot_renew_a_badge() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.verification_ttl = 3600).await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let verifier = Keypair::new();
+    let role_member = grant_role(&mut context, registry_config, Role::Verifier, verifier.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[&verifier]).await.unwrap();
+    let expires_at = fetch::<EntryData>(&mut context, entry).await.badge_grants[0].expires_at;
+
+    process(&mut context, &[revoke_role_ix(registry_config, role_member, payer, system_program::id())], &[]).await.unwrap();
+    let result = process(&mut context, &[renew_verification_ix(registry_config, entry, &[verifier.pubkey()], 0)], &[&verifier]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.badge_grants[0].expires_at, expires_at);
+}
+
+#[tokio::test]
+async fn revoked_co_signer_does_not_count_towards_a_renewal() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| {
+        ix.verification_threshold = 2;
+        ix.verification_ttl = 3600;
+    })
+    .await;
+    init_badges(&mut context, registry_config, &["verified"]).await;
+    let (first, second) = (Keypair::new(), Keypair::new());
+    grant_role(&mut context, registry_config, Role::Verifier, first.pubkey()).await;
+    let second_role = grant_role(&mut context, registry_config, Role::Verifier, second.pubkey()).await;
+    let entry = add_entry(&mut context, registry_config, b"data").await;
+    for verifier in [&first, &second] {
+        process(&mut context, &[verify_entry_ix(registry_config, entry, verifier.pubkey(), payer, 0)], &[verifier]).await.unwrap();
+    }
+    let ix = renew_verification_ix(registry_config, entry, &[second.pubkey(), first.pubkey()], 0);
+    process(&mut context, &[ix], &[&first, &second]).await.unwrap();
+
+    process(&mut context, &[revoke_role_ix(registry_config, second_role, payer, system_program::id())], &[]).await.unwrap();
+    let ix = renew_verification_ix(registry_config, entry, &[first.pubkey(), second.pubkey()], 0);
+    let result = process(&mut context, &[ix], &[&first, &second]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
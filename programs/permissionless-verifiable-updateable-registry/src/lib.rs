@@ -1,7 +1,20 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::pubkey::MAX_SEED_LEN;
+use anchor_lang::solana_program::system_instruction;
+use std::convert::TryInto;
 
 declare_id!("tksCmRWYaXgAsgLxNoMP7Z5hLyiaiLf1fVisgV2njVh");
+const MAX_MULTISIG_SIGNERS: usize = 10;
+// serialized size of RegistryConfig with the name, entry seed and multisig at their maximum
+const REGISTRY_CONFIG_SIZE: usize =
+    8 + 1 + 4 + MAX_SEED_LEN + 32 + 33 + 4 + 32 * MAX_MULTISIG_SIGNERS + 1 + 4 + MAX_SEED_LEN + 1 + 1 + 1 + 1 + 4 + 8 + 4 + 33 + 8;
+// serialized size of every EntryData field except `data`, with all options set
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 8 + 1 + 8 + 1 + 1 + 9 + 33 + 32 + 32 + 8;
 
 #[program]
 pub mod permissionless_verifiable_updateable_registry {
@@ -9,53 +22,285 @@ pub mod permissionless_verifiable_updateable_registry {
 
     pub fn init(ctx: Context<Init>, ix: InitIx) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.bump = ix.bump;
+        registry_config.name = ix.name;
         registry_config.authority = *ctx.accounts.authority.key;
         registry_config.entry_seed = ix.entry_seed;
         registry_config.permissionless_add = ix.permissionless_add;
+        registry_config.require_proof = ix.require_proof;
+        registry_config.authority_can_update = ix.authority_can_update;
+        registry_config.require_executable = ix.require_executable;
+        registry_config.max_entries = ix.max_entries;
+        registry_config.max_entry_size = ix.max_entry_size;
+        registry_config.moderation_treasury = ix.moderation_treasury;
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
         let registry_config = &mut ctx.accounts.registry_config;
         registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn propose_authority(ctx: Context<ProposeAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = Some(*ctx.accounts.new_authority.key);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.authority = *ctx.accounts.new_authority.key;
+        registry_config.pending_authority = None;
+        clear_multisig(registry_config);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> ProgramResult {
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.pending_authority = None;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn set_multisig(ctx: Context<SetMultisig>, ix: SetMultisigIx) -> ProgramResult {
+        if ix.signers.len() > MAX_MULTISIG_SIGNERS || ix.threshold as usize > ix.signers.len() {
+            return Err(ErrorCode::InvalidMultisig.into());
+        }
+        for (i, signer) in ix.signers.iter().enumerate() {
+            if ix.signers[..i].contains(signer) {
+                return Err(ErrorCode::InvalidMultisig.into());
+            }
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.multisig_signers = ix.signers;
+        registry_config.multisig_threshold = ix.threshold;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn update_config(ctx: Context<UpdateConfig>, ix: UpdateConfigIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_config.to_account_info().key;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if let Some(permissionless_add) = ix.permissionless_add {
+            config_changed(registry, "permissionless_add", &registry_config.permissionless_add, &permissionless_add);
+            registry_config.permissionless_add = permissionless_add;
+        }
+        if let Some(entry_seed) = ix.entry_seed {
+            if registry_config.entry_count > 0 {
+                return Err(ErrorCode::SeedLocked.into());
+            }
+            config_changed(registry, "entry_seed", &registry_config.entry_seed.clone(), &entry_seed);
+            registry_config.entry_seed = entry_seed;
+        }
+        if let Some(require_proof) = ix.require_proof {
+            config_changed(registry, "require_proof", &registry_config.require_proof, &require_proof);
+            registry_config.require_proof = require_proof;
+        }
+        if let Some(authority_can_update) = ix.authority_can_update {
+            config_changed(registry, "authority_can_update", &registry_config.authority_can_update, &authority_can_update);
+            registry_config.authority_can_update = authority_can_update;
+        }
+        if let Some(require_executable) = ix.require_executable {
+            config_changed(registry, "require_executable", &registry_config.require_executable, &require_executable);
+            registry_config.require_executable = require_executable;
+        }
+        if let Some(max_entries) = ix.max_entries {
+            config_changed(registry, "max_entries", &registry_config.max_entries, &max_entries);
+            registry_config.max_entries = max_entries;
+        }
+        if let Some(max_entry_size) = ix.max_entry_size {
+            config_changed(registry, "max_entry_size", &registry_config.max_entry_size, &max_entry_size);
+            registry_config.max_entry_size = max_entry_size;
+        }
+        if let Some(moderation_treasury) = ix.moderation_treasury {
+            config_changed(
+                registry,
+                "moderation_treasury",
+                &format!("{:?}", registry_config.moderation_treasury),
+                &format!("{:?}", moderation_treasury),
+            );
+            registry_config.moderation_treasury = moderation_treasury;
+        }
         Ok(())
     }
     
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn add_entry(ctx: Context<AddEntry>, ix: AddEntryIx) -> ProgramResult {
+        entry_size(&ctx.accounts.registry_config, &ix.data)?;
+        let registry_config = &mut ctx.accounts.registry_config;
+        if registry_config.max_entries > 0 && registry_config.entry_count >= registry_config.max_entries as u64 {
+            return Err(ErrorCode::RegistryFull.into());
+        }
+        registry_config.entry_count += 1;
+        let delegate_generation = next_delegate_generation(registry_config);
         let entry = &mut ctx.accounts.entry;
+        entry.bump = ix.bump;
+        entry.delegate_generation = delegate_generation;
+        entry.registry = *ctx.accounts.registry_config.to_account_info().key;
         entry.address = ix.address;
         entry.data = ix.data;
         entry.schema_version = ix.schema_version;
         entry.creator = *ctx.accounts.creator.key;
+        entry.update_authority = *ctx.accounts.creator.key;
+        entry.rent_payer = *ctx.accounts.creator.key;
         entry.created_at = Clock::get().unwrap().unix_timestamp;
         entry.updated_at = Clock::get().unwrap().unix_timestamp;
         entry.is_verified = false; // default to false
+        if ctx.accounts.registry_config.require_proof && ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        assert_proof(ix.proof, &entry.address, &ctx.accounts.creator, ctx.remaining_accounts)?;
+        entry.proof = ix.proof;
         Ok(())
     }
 
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn update_entry(ctx: Context<UpdateEntry>, ix: UpdateEntryIx) -> ProgramResult {
+        resize_entry(
+            &ctx.accounts.entry.to_account_info(),
+            entry_size(&ctx.accounts.registry_config, &ix.data)?,
+            &ctx.accounts.creator,
+            &ctx.accounts.rent_payer,
+            &ctx.accounts.system_program,
+        )?;
         let entry = &mut ctx.accounts.entry;
         entry.data = ix.data;
         entry.schema_version = ix.schema_version;
         entry.updated_at = Clock::get().unwrap().unix_timestamp;
+        if entry.is_verified && entry.verified_hash != Some(content_hash(entry.schema_version, &entry.data)) {
+            entry.is_verified = false;
+            entry.verified_deploy_slot = None;
+            entry.verified_hash = None;
+            emit!(VerificationRevoked {
+                registry: entry.registry,
+                entry: *entry.to_account_info().key,
+                reason: RevocationReason::ContentChanged,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn set_update_authority(ctx: Context<SetUpdateAuthority>) -> ProgramResult {
+        let entry = &mut ctx.accounts.entry;
+        entry.update_authority = *ctx.accounts.new_update_authority.key;
+        Ok(())
+    }
+
+    pub fn add_delegate(ctx: Context<AddDelegate>, ix: AddDelegateIx) -> ProgramResult {
+        let entry_delegate = &mut ctx.accounts.entry_delegate;
+        entry_delegate.bump = ix.bump;
+        entry_delegate.entry = *ctx.accounts.entry.to_account_info().key;
+        entry_delegate.delegate = *ctx.accounts.delegate.key;
+        entry_delegate.scope = ix.scope;
+        entry_delegate.expires_at = ix.expires_at;
+        entry_delegate.generation = ctx.accounts.entry.delegate_generation;
+        Ok(())
+    }
+
+    pub fn remove_delegate(ctx: Context<RemoveDelegate>) -> ProgramResult {
+        ctx.accounts.entry_delegate.close(ctx.accounts.update_authority.to_account_info())
+    }
+
+    pub fn claim_entry(ctx: Context<ClaimEntry>, ix: ClaimEntryIx) -> ProgramResult {
+        let entry = &mut ctx.accounts.entry;
+        if ix.proof == ProofMethod::None {
+            return Err(ErrorCode::InvalidProof.into());
+        }
+        assert_proof(ix.proof, &entry.address, &ctx.accounts.claimant, ctx.remaining_accounts)?;
+        let previous_creator = entry.creator;
+        entry.creator = *ctx.accounts.claimant.key;
+        entry.update_authority = *ctx.accounts.claimant.key;
+        // delegates appointed by the previous creator stop applying
+        entry.delegate_generation = next_delegate_generation(&mut ctx.accounts.registry_config);
+        entry.proof = ix.proof;
+        entry.is_verified = false;
+        entry.verified_deploy_slot = None;
+        entry.verified_hash = None;
+        emit!(EntryClaimed {
+            registry: entry.registry,
+            entry: *entry.to_account_info().key,
+            address: entry.address,
+            previous_creator,
+            new_creator: entry.creator,
+        });
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn verify_entry(ctx: Context<VerifyEntry>) -> ProgramResult {
         let entry = &mut ctx.accounts.entry;
         entry.is_verified = true;
         entry.verified_at = Clock::get().unwrap().unix_timestamp;
+        // entries that are not programs have no deployment to snapshot
+        entry.verified_deploy_slot = if ctx.accounts.registry_config.require_executable {
+            checked_deployment(&ctx.accounts.program, &ctx.accounts.program_data)?.deploy_slot
+        } else {
+            None
+        };
+        entry.verified_hash = Some(content_hash(entry.schema_version, &entry.data));
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_config, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn unverify_entry(ctx: Context<UnverifyEntry>) -> ProgramResult {
         let entry = &mut ctx.accounts.entry;
         entry.is_verified = false;
+        entry.verified_deploy_slot = None;
+        entry.verified_hash = None;
         Ok(())
     }
 
+    pub fn check_verification(ctx: Context<CheckVerification>) -> ProgramResult {
+        let program = &ctx.accounts.program;
+        let program_data = &ctx.accounts.program_data;
+        let entry = &mut ctx.accounts.entry;
+        let verified_deploy_slot = match (entry.is_verified, entry.verified_deploy_slot) {
+            (true, Some(slot)) => slot,
+            // nothing was snapshotted so there is nothing to compare against
+            _ => return Ok(()),
+        };
+        // only a program that is gone counts as closed, accounts that fail to parse are rejected
+        let reason = if program.lamports() == 0 || !program.executable {
+            RevocationReason::ProgramClosed
+        } else {
+            match programdata_address(program)? {
+                Some(address) if address != *program_data.key => return Err(ErrorCode::InvalidProgramData.into()),
+                // closing an upgradeable program empties its program data but leaves the program account
+                Some(_) if program_data.data_is_empty() => RevocationReason::ProgramClosed,
+                _ if checked_deployment(program, program_data)?.deploy_slot == Some(verified_deploy_slot) => return Ok(()),
+                _ => RevocationReason::ProgramRedeployed,
+            }
+        };
+        entry.is_verified = false;
+        entry.verified_deploy_slot = None;
+        entry.verified_hash = None;
+        emit!(VerificationRevoked {
+            registry: entry.registry,
+            entry: *entry.to_account_info().key,
+            reason,
+        });
+        Ok(())
+    }
+
+    #[access_control(ctx.accounts.validate(ctx.remaining_accounts))]
     pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.authority.to_account_info()).unwrap();
+        let entry = &ctx.accounts.entry;
+        let authority = &ctx.accounts.authority;
+        let moderated = entry.creator != *authority.key
+            && !is_delegate(&entry.key(), entry.delegate_generation, &ctx.accounts.entry_delegate, authority.key, DelegateScope::EditAndRemove);
+        if *ctx.accounts.rent_recipient.key != rent_recipient(&ctx.accounts.registry_config, &entry.rent_payer, moderated) {
+            return Err(ErrorCode::InvalidRentRecipient.into());
+        }
+        let registry_config = &mut ctx.accounts.registry_config;
+        registry_config.entry_count = registry_config.entry_count.saturating_sub(1);
+        next_delegate_generation(registry_config);
+        ctx.accounts.entry.close(ctx.accounts.rent_recipient.to_account_info()).unwrap();
         Ok(())
     }
 }
@@ -65,8 +310,15 @@ pub mod permissionless_verifiable_updateable_registry {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitIx {
     pub bump: u8,
+    pub name: String,
     pub entry_seed: String,
     pub permissionless_add: bool,
+    pub require_proof: bool,
+    pub require_executable: bool,
+    pub authority_can_update: bool,
+    pub max_entries: u32,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -75,6 +327,7 @@ pub struct AddEntryIx {
     pub address: Pubkey,
     pub schema_version: u8,
     pub data: String,
+    pub proof: ProofMethod,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -83,6 +336,36 @@ pub struct UpdateEntryIx {
     pub data: String,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AddDelegateIx {
+    pub bump: u8,
+    pub scope: DelegateScope,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ClaimEntryIx {
+    pub proof: ProofMethod,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetMultisigIx {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateConfigIx {
+    pub permissionless_add: Option<bool>,
+    pub entry_seed: Option<String>,
+    pub require_proof: Option<bool>,
+    pub authority_can_update: Option<bool>,
+    pub require_executable: Option<bool>,
+    pub max_entries: Option<u32>,
+    pub max_entry_size: Option<u32>,
+    pub moderation_treasury: Option<Option<Pubkey>>,
+}
+
 ///////////////// Contexts /////////////////
 
 #[derive(Accounts)]
@@ -91,9 +374,8 @@ pub struct Init<'info> {
     #[account(
         init,
         payer = authority,
-        // extra space for future upgrades
-        space = 128,
-        seeds = [b"registry-config".as_ref()],
+        space = REGISTRY_CONFIG_SIZE,
+        seeds = [b"registry-config".as_ref(), ix.name.as_ref()],
         bump = ix.bump,
     )]
     pub registry_config: Account<'info, RegistryConfig>,
@@ -103,85 +385,295 @@ pub struct Init<'info> {
 
 #[derive(Accounts)]
 pub struct TransferAuthority<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(constraint = registry_config.pending_authority == Some(*new_authority.to_account_info().key) @ ErrorCode::InvalidPendingAuthority)]
     pub new_authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMultisig<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(ix: AddEntryIx)]
 pub struct AddEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
     #[account(
         init,
         payer = creator,
-        // extra space for future upgrades
-        space = 1024,
-        seeds = [registry_config.entry_seed.as_ref(), ix.address.as_ref()],
+        // sized to the payload, update_entry reallocs when it changes
+        space = ENTRY_BASE_SIZE + ix.data.try_to_vec().unwrap().len(),
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), ix.address.as_ref()],
         bump = ix.bump,
     )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.permissionless_add || (registry_config.authority == *creator.to_account_info().key) @ ErrorCode::InsufficientAuthority)]
     pub creator: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> AddEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.registry_config.permissionless_add {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.creator, remaining_accounts)
+    }
+}
+
 #[derive(Accounts)]
 #[instruction(ix: UpdateEntryIx)]
 pub struct UpdateEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.permissionless_add || (registry_config.authority == *creator.to_account_info().key) @ ErrorCode::InsufficientAuthority)]
+    #[account(mut)]
     pub creator: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == *rent_payer.key @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
+    pub entry_delegate: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.entry.update_authority == *self.creator.key
+            || is_delegate(&self.entry.key(), self.entry.delegate_generation, &self.entry_delegate, self.creator.key, DelegateScope::Edit)
+        {
+            return Ok(());
+        }
+        if !self.registry_config.authority_can_update {
+            return Err(ErrorCode::InsufficientAuthority.into());
+        }
+        authorized(&self.registry_config, &self.creator, remaining_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetUpdateAuthority<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
+    pub new_update_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(ix: AddDelegateIx)]
+pub struct AddDelegate<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(
+        init,
+        payer = update_authority,
+        // extra space for future upgrades
+        space = 128,
+        seeds = [b"delegate".as_ref(), entry.key().as_ref(), delegate.key.as_ref()],
+        bump = ix.bump,
+    )]
+    pub entry_delegate: Account<'info, EntryDelegate>,
+    pub delegate: AccountInfo<'info>,
+    #[account(mut, constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RemoveDelegate<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(
+        mut,
+        seeds = [b"delegate".as_ref(), entry.key().as_ref(), entry_delegate.delegate.as_ref()],
+        bump = entry_delegate.bump,
+        constraint = entry_delegate.entry == entry.key() @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry_delegate: Account<'info, EntryDelegate>,
+    #[account(mut, constraint = entry.update_authority == *update_authority.key @ ErrorCode::InsufficientAuthority)]
+    pub update_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEntry<'info> {
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    pub claimant: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct VerifyEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(constraint = !registry_config.require_executable || *program.key == entry.address @ ErrorCode::InvalidProgram)]
+    pub program: AccountInfo<'info>,
+    pub program_data: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
 pub struct UnverifyEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CheckVerification<'info> {
+    #[account(seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
+    pub registry_config: ProgramAccount<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
+    pub entry: Account<'info, EntryData>,
+    #[account(constraint = *program.key == entry.address @ ErrorCode::InvalidProgram)]
+    pub program: AccountInfo<'info>,
+    pub program_data: AccountInfo<'info>,
+}
+
 #[derive(Accounts)]
 pub struct RemoveEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-config".as_ref(), registry_config.name.as_ref()], bump = registry_config.bump)]
     pub registry_config: ProgramAccount<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [registry_config.entry_seed.as_ref(), registry_config.to_account_info().key.as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == *registry_config.to_account_info().key @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_config.authority == *authority.to_account_info().key || entry.creator == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(mut)]
+    pub rent_recipient: AccountInfo<'info>,
+    pub entry_delegate: AccountInfo<'info>,
+}
+
+impl<'info> RemoveEntry<'info> {
+    fn validate(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        if self.entry.creator == *self.authority.key
+            || is_delegate(&self.entry.key(), self.entry.delegate_generation, &self.entry_delegate, self.authority.key, DelegateScope::EditAndRemove)
+        {
+            return Ok(());
+        }
+        authorized(&self.registry_config, &self.authority, remaining_accounts)
+    }
 }
 
 ///////////////// DATA /////////////////
 
 #[account]
 pub struct RegistryConfig{
+    pub bump: u8,
+    pub name: String,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub multisig_signers: Vec<Pubkey>,
+    pub multisig_threshold: u8,
     pub entry_seed: String,
     pub permissionless_add: bool,
+    pub require_proof: bool,
+    pub authority_can_update: bool,
+    pub require_executable: bool,
+    pub max_entries: u32,
+    pub entry_count: u64,
+    pub max_entry_size: u32,
+    pub moderation_treasury: Option<Pubkey>,
+    pub delegate_generation: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum ProofMethod {
+    None,
+    AddressSignature,
+    UpgradeAuthority,
 }
 
 #[account]
 pub struct EntryData {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub address: Pubkey,
     pub creator: Pubkey,
     pub created_at: i64,
@@ -190,6 +682,251 @@ pub struct EntryData {
     pub verified_at: i64,
     pub schema_version: u8,
     pub data: String,
+    pub proof: ProofMethod,
+    pub verified_deploy_slot: Option<u64>,
+    pub verified_hash: Option<[u8; 32]>,
+    pub update_authority: Pubkey,
+    pub rent_payer: Pubkey,
+    pub delegate_generation: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ProgramDeployment {
+    pub upgrade_authority: Option<Pubkey>,
+    pub deploy_slot: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum RevocationReason {
+    ProgramRedeployed,
+    ProgramClosed,
+    ContentChanged,
+}
+
+#[account]
+pub struct EntryDelegate {
+    pub bump: u8,
+    pub entry: Pubkey,
+    pub delegate: Pubkey,
+    pub scope: DelegateScope,
+    pub expires_at: Option<i64>,
+    pub generation: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub enum DelegateScope {
+    Edit,
+    EditAndRemove,
+}
+
+#[event]
+pub struct EntryClaimed {
+    pub registry: Pubkey,
+    pub entry: Pubkey,
+    pub address: Pubkey,
+    pub previous_creator: Pubkey,
+    pub new_creator: Pubkey,
+}
+
+#[event]
+pub struct VerificationRevoked {
+    pub registry: Pubkey,
+    pub entry: Pubkey,
+    pub reason: RevocationReason,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub registry: Pubkey,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn authorized(registry_config: &RegistryConfig, signer: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    if signer.is_signer && registry_config.authority == *signer.key {
+        return Ok(());
+    }
+    if registry_config.multisig_threshold > 0 {
+        let mut approvals: Vec<&Pubkey> = Vec::new();
+        let signers = remaining_accounts.iter().map(|account| (account.key, account.is_signer));
+        for (key, is_signer) in std::iter::once((signer.key, signer.is_signer)).chain(signers) {
+            if is_signer && registry_config.multisig_signers.contains(key) && !approvals.contains(&key) {
+                approvals.push(key);
+            }
+        }
+        if approvals.len() >= registry_config.multisig_threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(ErrorCode::InsufficientAuthority.into())
+}
+
+// signers approved by the previous authority do not carry over to the next one
+pub fn clear_multisig(registry_config: &mut RegistryConfig) {
+    registry_config.multisig_signers.clear();
+    registry_config.multisig_threshold = 0;
+}
+
+pub fn config_changed<T: ToString>(registry: Pubkey, field: &str, old_value: &T, new_value: &T) {
+    emit!(ConfigUpdated {
+        registry,
+        field: field.to_string(),
+        old_value: old_value.to_string(),
+        new_value: new_value.to_string(),
+    });
+}
+
+// drawn from the registry so generations keep increasing when an entry is removed and re-created
+pub fn next_delegate_generation(registry_config: &mut RegistryConfig) -> u64 {
+    registry_config.delegate_generation += 1;
+    registry_config.delegate_generation
+}
+
+pub fn rent_recipient(registry_config: &RegistryConfig, rent_payer: &Pubkey, moderated: bool) -> Pubkey {
+    match registry_config.moderation_treasury {
+        Some(treasury) if moderated => treasury,
+        _ => *rent_payer,
+    }
+}
+
+pub fn is_delegate(entry: &Pubkey, delegate_generation: u64, delegate_account: &AccountInfo, delegate: &Pubkey, scope: DelegateScope) -> bool {
+    if delegate_account.owner != &ID {
+        return false;
+    }
+    let data = match delegate_account.try_borrow_data() {
+        Ok(data) => data,
+        Err(_) => return false,
+    };
+    match EntryDelegate::try_deserialize(&mut data.as_ref()) {
+        Ok(entry_delegate) => {
+            entry_delegate.entry == *entry
+                && entry_delegate.generation == delegate_generation
+                && entry_delegate.delegate == *delegate
+                && (entry_delegate.scope == scope || entry_delegate.scope == DelegateScope::EditAndRemove)
+                && !matches!(entry_delegate.expires_at, Some(expires_at) if expires_at <= Clock::get().unwrap().unix_timestamp)
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn assert_proof(proof: ProofMethod, address: &Pubkey, creator: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    let proven = match proof {
+        ProofMethod::None => true,
+        ProofMethod::AddressSignature => std::iter::once((creator.key, creator.is_signer))
+            .chain(remaining_accounts.iter().map(|account| (account.key, account.is_signer)))
+            .any(|(key, is_signer)| key == address && is_signer),
+        ProofMethod::UpgradeAuthority => {
+            let (program_data, _) = Pubkey::find_program_address(&[address.as_ref()], &bpf_loader_upgradeable::id());
+            match remaining_accounts.iter().find(|account| *account.key == program_data) {
+                Some(account) => creator.is_signer && program_data_header(account)?.1 == Some(*creator.key),
+                None => false,
+            }
+        }
+    };
+    if !proven {
+        return Err(ErrorCode::InvalidProof.into());
+    }
+    Ok(())
+}
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProgramData.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+// None for programs that cannot be upgraded
+pub fn programdata_address(program: &AccountInfo) -> std::result::Result<Option<Pubkey>, ProgramError> {
+    if program.owner != &bpf_loader_upgradeable::id() {
+        return Ok(None);
+    }
+    // UpgradeableLoaderState::Program { programdata_address: Pubkey }
+    let data = program.try_borrow_data()?;
+    if data.len() < 36 || data[0..4] != [2, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    Ok(Some(Pubkey::new_from_array(data[4..36].try_into().unwrap())))
+}
+
+pub fn program_deployment(program_id: &Pubkey, remaining_accounts: &[AccountInfo]) -> std::result::Result<ProgramDeployment, ProgramError> {
+    let program = remaining_accounts
+        .iter()
+        .find(|account| account.key == program_id)
+        .ok_or(ErrorCode::InvalidProgram)?;
+    let program_data = match programdata_address(program)? {
+        Some(address) => remaining_accounts
+            .iter()
+            .find(|account| *account.key == address)
+            .ok_or(ErrorCode::InvalidProgramData)?,
+        None => program,
+    };
+    checked_deployment(program, program_data)
+}
+
+// program_data has to be the account the program points at, it is ignored for programs that cannot be upgraded
+pub fn checked_deployment(program: &AccountInfo, program_data: &AccountInfo) -> std::result::Result<ProgramDeployment, ProgramError> {
+    if !program.executable {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    match programdata_address(program)? {
+        None => Ok(ProgramDeployment { upgrade_authority: None, deploy_slot: None }),
+        Some(address) if address != *program_data.key => Err(ErrorCode::InvalidProgramData.into()),
+        Some(_) => {
+            let (slot, upgrade_authority) = program_data_header(program_data)?;
+            Ok(ProgramDeployment { upgrade_authority, deploy_slot: Some(slot) })
+        }
+    }
+}
+
+pub fn content_hash<T: AnchorSerialize>(schema_version: u8, data: &T) -> [u8; 32] {
+    hashv(&[&[schema_version], &data.try_to_vec().unwrap()]).to_bytes()
+}
+
+pub fn entry_size<T: AnchorSerialize>(registry_config: &RegistryConfig, data: &T) -> std::result::Result<usize, ProgramError> {
+    let size = ENTRY_BASE_SIZE + data.try_to_vec().unwrap().len();
+    if registry_config.max_entry_size > 0 && size > registry_config.max_entry_size as usize {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    Ok(size)
+}
+
+// growth is paid by `payer`, a shrink refunds `rent_payer`
+pub fn resize_entry<'info>(entry: &AccountInfo<'info>, size: usize, payer: &AccountInfo<'info>, rent_payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> ProgramResult {
+    let current_size = entry.data_len();
+    if size == current_size {
+        return Ok(());
+    }
+    if size > current_size + MAX_PERMITTED_DATA_INCREASE {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    let rent = Rent::get()?.minimum_balance(size);
+    if rent > entry.lamports() {
+        invoke(
+            &system_instruction::transfer(payer.key, entry.key, rent - entry.lamports()),
+            &[payer.clone(), entry.clone(), system_program.clone()],
+        )?;
+    } else {
+        let refund = entry.lamports() - rent;
+        **entry.try_borrow_mut_lamports()? -= refund;
+        **rent_payer.try_borrow_mut_lamports()? += refund;
+    }
+    // zero the grown tail, it may still hold bytes from an earlier shrink in this transaction
+    entry.realloc(size, true)
 }
 
 ///////////////// ERRORS /////////////////
@@ -198,4 +935,24 @@ pub struct EntryData {
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry entry")]
     InsufficientAuthority,
+    #[msg("Signer is not the pending authority for this registry")]
+    InvalidPendingAuthority,
+    #[msg("Entry does not belong to this registry")]
+    InvalidRegistry,
+    #[msg("Multisig signers or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Creator did not prove control of the entry address")]
+    InvalidProof,
+    #[msg("Referenced account is not an executable program")]
+    InvalidProgram,
+    #[msg("Program data account does not match the program")]
+    InvalidProgramData,
+    #[msg("Seed fields cannot change once the registry has entries")]
+    SeedLocked,
+    #[msg("Registry has reached its maximum number of entries")]
+    RegistryFull,
+    #[msg("Entry data exceeds the maximum entry size for this registry")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer or registry treasury")]
+    InvalidRentRecipient,
 }
\ No newline at end of file
@@ -0,0 +1,844 @@
+use anchor_lang::{AccountDeserialize, AnchorSerialize, InstructionData, ToAccountMetas};
+use permissionless_verifiable_updateable_registry::{
+    accounts, instruction, AddDelegateIx, AddEntryIx, ClaimEntryIx, DelegateScope, EntryData, ErrorCode, InitIx, ProofMethod,
+    RegistryConfig, SetMultisigIx, UpdateConfigIx, UpdateEntryIx,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 32 + 8 + 8 + 1 + 8 + 1 + 1 + 9 + 33 + 32 + 32 + 8;
+const ENTRY_SEED: &str = "entry";
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "permissionless_verifiable_updateable_registry",
+        permissionless_verifiable_updateable_registry::id(),
+        processor!(permissionless_verifiable_updateable_registry::entry),
+    )
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn init(context: &mut ProgramTestContext, configure: impl FnOnce(&mut InitIx)) -> Pubkey {
+    let mut ix = InitIx {
+        bump: 0,
+        name: "registry".to_string(),
+        entry_seed: ENTRY_SEED.to_string(),
+        permissionless_add: true,
+        require_proof: false,
+        require_executable: false,
+        authority_can_update: false,
+        max_entries: 0,
+        max_entry_size: 0,
+        moderation_treasury: None,
+    };
+    configure(&mut ix);
+    let (registry_config, bump) =
+        Pubkey::find_program_address(&[b"registry-config", ix.name.as_bytes()], &permissionless_verifiable_updateable_registry::id());
+    ix.bump = bump;
+    let init = Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::Init { registry_config, authority: context.payer.pubkey(), system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::Init { ix }.data(),
+    };
+    process(context, &[init], &[]).await.unwrap();
+    registry_config
+}
+
+fn entry_address(registry_config: &Pubkey, address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ENTRY_SEED.as_bytes(), registry_config.as_ref(), address.as_ref()],
+        &permissionless_verifiable_updateable_registry::id(),
+    )
+}
+
+fn add_entry_ix(registry_config: Pubkey, creator: Pubkey, address: Pubkey, data: &str) -> Instruction {
+    let (entry, bump) = entry_address(&registry_config, &address);
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::AddEntry { registry_config, entry, creator, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::AddEntry { ix: AddEntryIx { bump, address, schema_version: 0, data: data.to_string(), proof: ProofMethod::None } }
+            .data(),
+    }
+}
+
+async fn add_entry(context: &mut ProgramTestContext, registry_config: Pubkey, data: &str) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let ix = add_entry_ix(registry_config, context.payer.pubkey(), address, data);
+    process(context, &[ix], &[]).await.unwrap();
+    entry_address(&registry_config, &address).0
+}
+
+fn update_entry_ix(registry_config: Pubkey, entry: Pubkey, creator: Pubkey, rent_payer: Pubkey, entry_delegate: Pubkey, data: &str) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::UpdateEntry { registry_config, entry, creator, rent_payer, entry_delegate, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::UpdateEntry { ix: UpdateEntryIx { schema_version: 0, data: data.to_string() } }.data(),
+    }
+}
+
+fn delegate_address(entry: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"delegate", entry.as_ref(), delegate.as_ref()], &permissionless_verifiable_updateable_registry::id())
+}
+
+fn add_delegate_ix(registry_config: Pubkey, entry: Pubkey, delegate: Pubkey, update_authority: Pubkey, scope: DelegateScope, expires_at: Option<i64>) -> Instruction {
+    let (entry_delegate, bump) = delegate_address(&entry, &delegate);
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::AddDelegate { registry_config, entry, entry_delegate, delegate, update_authority, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::AddDelegate { ix: AddDelegateIx { bump, scope, expires_at } }.data(),
+    }
+}
+
+fn set_update_authority_ix(registry_config: Pubkey, entry: Pubkey, update_authority: Pubkey, new_update_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::SetUpdateAuthority { registry_config, entry, update_authority, new_update_authority }.to_account_metas(None),
+        data: instruction::SetUpdateAuthority {}.data(),
+    }
+}
+
+fn remove_delegate_ix(registry_config: Pubkey, entry: Pubkey, entry_delegate: Pubkey, update_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::RemoveDelegate { registry_config, entry, entry_delegate, update_authority }.to_account_metas(None),
+        data: instruction::RemoveDelegate {}.data(),
+    }
+}
+
+fn remove_entry_ix(registry_config: Pubkey, entry: Pubkey, authority: Pubkey, rent_recipient: Pubkey, entry_delegate: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::RemoveEntry {
+            registry_config,
+            entry,
+            authority,
+            rent_recipient,
+            entry_delegate,
+        }
+        .to_account_metas(None),
+        data: instruction::RemoveEntry {}.data(),
+    }
+}
+
+fn update_config_ix(registry_config: Pubkey, authority: Pubkey, ix: UpdateConfigIx) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::UpdateConfig { registry_config, authority }.to_account_metas(None),
+        data: instruction::UpdateConfig { ix }.data(),
+    }
+}
+
+fn config_update(configure: impl FnOnce(&mut UpdateConfigIx)) -> UpdateConfigIx {
+    let mut ix = UpdateConfigIx {
+        permissionless_add: None,
+        entry_seed: None,
+        require_proof: None,
+        authority_can_update: None,
+        require_executable: None,
+        max_entries: None,
+        max_entry_size: None,
+        moderation_treasury: None,
+    };
+    configure(&mut ix);
+    ix
+}
+
+fn claim_entry_ix(registry_config: Pubkey, entry: Pubkey, claimant: Pubkey, proof: ProofMethod) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::ClaimEntry { registry_config, entry, claimant }.to_account_metas(None),
+        data: instruction::ClaimEntry { ix: ClaimEntryIx { proof } }.data(),
+    }
+}
+
+fn verify_entry_ix(registry_config: Pubkey, entry: Pubkey, authority: Pubkey, program: Pubkey, program_data: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::VerifyEntry { registry_config, entry, authority, program, program_data }.to_account_metas(None),
+        data: instruction::VerifyEntry {}.data(),
+    }
+}
+
+fn check_verification_ix(registry_config: Pubkey, entry: Pubkey, program: Pubkey, program_data: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::CheckVerification { registry_config, entry, program, program_data }.to_account_metas(None),
+        data: instruction::CheckVerification {}.data(),
+    }
+}
+
+fn program_data_account(deploy_slot: u64, upgrade_authority: Pubkey) -> Account {
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&deploy_slot.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(upgrade_authority.as_ref());
+    Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: bpf_loader_upgradeable::id(), executable: false, rent_epoch: 0 }
+}
+
+// an upgradeable program with its program data, neither is ever invoked
+fn add_upgradeable_program(program_test: &mut ProgramTest, deploy_slot: u64) -> (Pubkey, Pubkey) {
+    let program = Pubkey::new_unique();
+    let (program_data, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    let mut data = vec![2, 0, 0, 0];
+    data.extend_from_slice(program_data.as_ref());
+    program_test.add_account(
+        program,
+        Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: bpf_loader_upgradeable::id(), executable: true, rent_epoch: 0 },
+    );
+    program_test.add_account(program_data, program_data_account(deploy_slot, Pubkey::new_unique()));
+    (program, program_data)
+}
+
+async fn account_size(context: &mut ProgramTestContext, address: Pubkey) -> (usize, u64) {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    (account.data.len(), account.lamports)
+}
+
+fn propose_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::ProposeAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::ProposeAuthority {}.data(),
+    }
+}
+
+fn accept_authority_ix(registry_config: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::AcceptAuthority { registry_config, new_authority }.to_account_metas(None),
+        data: instruction::AcceptAuthority {}.data(),
+    }
+}
+
+fn cancel_authority_transfer_ix(registry_config: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::CancelAuthorityTransfer { registry_config, authority }.to_account_metas(None),
+        data: instruction::CancelAuthorityTransfer {}.data(),
+    }
+}
+
+fn set_multisig_ix(registry_config: Pubkey, authority: Pubkey, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::SetMultisig { registry_config, authority }.to_account_metas(None),
+        data: instruction::SetMultisig { ix: SetMultisigIx { signers, threshold } }.data(),
+    }
+}
+
+fn transfer_authority_ix(registry_config: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: permissionless_verifiable_updateable_registry::id(),
+        accounts: accounts::TransferAuthority { registry_config, authority, new_authority }.to_account_metas(None),
+        data: instruction::TransferAuthority {}.data(),
+    }
+}
+
+// co-signers approve through the remaining accounts
+fn with_signers(mut ix: Instruction, signers: &[&Keypair]) -> Instruction {
+    ix.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    ix
+}
+
+#[tokio::test]
+async fn update_entry_grows_and_shrinks_the_account() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "short").await;
+
+    let grown = "x".repeat(600);
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), &grown)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + grown.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data, grown);
+
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "tiny")], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + "tiny".to_string().try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data, "tiny");
+}
+
+#[tokio::test]
+async fn shrink_then_grow_in_one_transaction_zeroes_the_tail() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, &"x".repeat(300)).await;
+
+    let regrown = "y".repeat(300);
+    process(
+        &mut context,
+        &[
+            update_entry_ix(registry_config, entry, payer, payer, system_program::id(), ""),
+            update_entry_ix(registry_config, entry, payer, payer, system_program::id(), &regrown),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.data, regrown);
+}
+
+#[tokio::test]
+async fn update_entry_rejects_data_above_the_max_entry_size() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entry_size = (ENTRY_BASE_SIZE + 100) as u32).await;
+    let entry = add_entry(&mut context, registry_config, "short").await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), &"x".repeat(100))], &[]).await;
+    assert_error(result, ErrorCode::DataTooLarge);
+}
+
+#[tokio::test]
+async fn shrink_refunds_only_the_rent_payer() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, &"x".repeat(300)).await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, Pubkey::new_unique(), system_program::id(), "tiny")], &[]).await;
+    assert_error(result, ErrorCode::InvalidRentRecipient);
+}
+
+#[tokio::test]
+async fn proposed_authority_takes_over_once_it_accepts() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, payer);
+    assert_eq!(config.pending_authority, Some(new_authority.pubkey()));
+
+    process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.pending_authority, None);
+
+    // the previous authority lost its rights
+    let result = process(&mut context, &[propose_authority_ix(registry_config, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn only_the_pending_authority_can_accept() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+    let impostor = Keypair::new();
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let result = process(&mut context, &[accept_authority_ix(registry_config, impostor.pubkey())], &[&impostor]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.authority, payer);
+}
+
+#[tokio::test]
+async fn cancelled_handoff_cannot_be_accepted() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, new_authority.pubkey())], &[]).await.unwrap();
+    process(&mut context, &[cancel_authority_transfer_ix(registry_config, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, None);
+
+    let result = process(&mut context, &[accept_authority_ix(registry_config, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+}
+
+#[tokio::test]
+async fn only_the_authority_can_propose_or_cancel() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let stranger = Keypair::new();
+
+    let result = process(&mut context, &[propose_authority_ix(registry_config, stranger.pubkey(), stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_config, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[cancel_authority_transfer_ix(registry_config, stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority.is_some());
+}
+
+#[tokio::test]
+async fn multisig_members_act_once_the_threshold_is_met() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let (member_a, member_b) = (Keypair::new(), Keypair::new());
+    let signers = vec![member_a.pubkey(), member_b.pubkey()];
+    process(&mut context, &[set_multisig_ix(registry_config, payer, signers.clone(), 2)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.multisig_signers, signers);
+    assert_eq!(config.multisig_threshold, 2);
+
+    let new_authority = Pubkey::new_unique();
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member_a.pubkey(), new_authority)], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // a member listed twice still counts once
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_a]);
+    let result = process(&mut context, &[ix], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let ix = with_signers(propose_authority_ix(registry_config, member_a.pubkey(), new_authority), &[&member_b]);
+    process(&mut context, &[ix], &[&member_a, &member_b]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.pending_authority, Some(new_authority));
+}
+
+#[tokio::test]
+async fn set_multisig_rejects_invalid_signer_sets() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Pubkey::new_unique();
+
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member], 2)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member, member], 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let too_many = (0..11).map(|_| Pubkey::new_unique()).collect();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, payer, too_many, 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[set_multisig_ix(registry_config, stranger.pubkey(), vec![stranger.pubkey()], 1)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn handing_over_authority_clears_the_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let member = Keypair::new();
+    process(&mut context, &[set_multisig_ix(registry_config, payer, vec![member.pubkey()], 1)], &[]).await.unwrap();
+
+    let new_authority = Keypair::new();
+    process(&mut context, &[transfer_authority_ix(registry_config, payer, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+    let result = process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), member.pubkey())], &[&member]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // the same holds for the two step handoff
+    let authority = new_authority;
+    process(&mut context, &[set_multisig_ix(registry_config, authority.pubkey(), vec![member.pubkey()], 1)], &[&authority]).await.unwrap();
+    let next_authority = Keypair::new();
+    process(&mut context, &[propose_authority_ix(registry_config, member.pubkey(), next_authority.pubkey())], &[&member]).await.unwrap();
+    process(&mut context, &[accept_authority_ix(registry_config, next_authority.pubkey())], &[&next_authority]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.authority, next_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+}
+
+#[tokio::test]
+async fn config_fits_the_longest_seeds_and_a_full_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| {
+        ix.name = "n".repeat(32);
+        ix.entry_seed = "s".repeat(32);
+    }).await;
+
+    let signers: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+    process(&mut context, &[set_multisig_ix(registry_config, payer, signers.clone(), 10)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert_eq!(config.name, "n".repeat(32));
+    assert_eq!(config.multisig_signers, signers);
+}
+
+#[tokio::test]
+async fn address_owner_can_claim_a_squatted_entry() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let owner = Keypair::new();
+    process(&mut context, &[add_entry_ix(registry_config, payer, owner.pubkey(), "squatted")], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &owner.pubkey()).0;
+    let delegate = Keypair::new();
+    process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), payer, DelegateScope::Edit, None)], &[]).await.unwrap();
+
+    process(&mut context, &[claim_entry_ix(registry_config, entry, owner.pubkey(), ProofMethod::AddressSignature)], &[&owner]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.creator, owner.pubkey());
+    assert_eq!(entry_data.update_authority, owner.pubkey());
+    assert_eq!(entry_data.proof, ProofMethod::AddressSignature);
+
+    // neither the squatter nor the delegate it appointed can edit anymore
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "squatted")], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let entry_delegate = delegate_address(&entry, &delegate.pubkey()).0;
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, "squatted")], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn claim_entry_requires_a_proof_of_control() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let owner = Pubkey::new_unique();
+    process(&mut context, &[add_entry_ix(registry_config, payer, owner, "data")], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &owner).0;
+    let claimant = Keypair::new();
+
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, claimant.pubkey(), ProofMethod::None)], &[&claimant]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, claimant.pubkey(), ProofMethod::AddressSignature)], &[&claimant]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+    // the upgrade authority proof needs the program data of the address
+    let result = process(&mut context, &[claim_entry_ix(registry_config, entry, claimant.pubkey(), ProofMethod::UpgradeAuthority)], &[&claimant]).await;
+    assert_error(result, ErrorCode::InvalidProof);
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.creator, payer);
+}
+
+#[tokio::test]
+async fn entries_that_are_not_programs_can_be_verified() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let address = fetch::<EntryData>(&mut context, entry).await.address;
+
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, address, address)], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(entry_data.is_verified);
+    assert_eq!(entry_data.verified_deploy_slot, None);
+    // there is no deployment to compare against
+    process(&mut context, &[check_verification_ix(registry_config, entry, address, address)], &[]).await.unwrap();
+    assert!(fetch::<EntryData>(&mut context, entry).await.is_verified);
+
+    let registry_config = init(&mut context, |ix| {
+        ix.name = "programs".to_string();
+        ix.require_executable = true;
+    })
+    .await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let address = fetch::<EntryData>(&mut context, entry).await.address;
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, payer, address, address)], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, payer, permissionless_verifiable_updateable_registry::id(), address)], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+}
+
+#[tokio::test]
+async fn check_verification_revokes_redeployed_and_closed_programs() {
+    let mut program_test = program_test();
+    let (program, program_data) = add_upgradeable_program(&mut program_test, 42);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.require_executable = true).await;
+    process(&mut context, &[add_entry_ix(registry_config, payer, program, "data")], &[]).await.unwrap();
+    let entry = entry_address(&registry_config, &program).0;
+
+    let result = process(&mut context, &[verify_entry_ix(registry_config, entry, payer, program, Pubkey::new_unique())], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, program, program_data)], &[]).await.unwrap();
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.verified_deploy_slot, Some(42));
+
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    assert!(fetch::<EntryData>(&mut context, entry).await.is_verified);
+    let result = process(&mut context, &[check_verification_ix(registry_config, entry, program, Pubkey::new_unique())], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    let result = process(&mut context, &[check_verification_ix(registry_config, entry, payer, program_data)], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+
+    context.set_account(&program_data, &program_data_account(43, Pubkey::new_unique()).into());
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(!entry_data.is_verified);
+    assert_eq!(entry_data.verified_deploy_slot, None);
+    assert_eq!(entry_data.verified_hash, None);
+
+    // closing the program empties its program data
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, program, program_data)], &[]).await.unwrap();
+    let closed = Account { lamports: 0, data: vec![], owner: system_program::id(), executable: false, rent_epoch: 0 };
+    context.set_account(&program_data, &closed.into());
+    process(&mut context, &[check_verification_ix(registry_config, entry, program, program_data)], &[]).await.unwrap();
+    assert!(!fetch::<EntryData>(&mut context, entry).await.is_verified);
+}
+
+#[tokio::test]
+async fn update_keeps_verification_only_while_content_is_unchanged() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let address = fetch::<EntryData>(&mut context, entry).await.address;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, address, address)], &[]).await.unwrap();
+    let verified_hash = fetch::<EntryData>(&mut context, entry).await.verified_hash;
+    assert!(verified_hash.is_some());
+
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "data")], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(entry_data.is_verified);
+    assert_eq!(entry_data.verified_hash, verified_hash);
+
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "swapped after review")], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert!(!entry_data.is_verified);
+    assert_eq!(entry_data.verified_hash, None);
+    // restoring the verified content does not restore the verification
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "data")], &[]).await.unwrap();
+    assert!(!fetch::<EntryData>(&mut context, entry).await.is_verified);
+}
+
+#[tokio::test]
+async fn schema_version_change_resets_verification() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let address = fetch::<EntryData>(&mut context, entry).await.address;
+    process(&mut context, &[verify_entry_ix(registry_config, entry, payer, address, address)], &[]).await.unwrap();
+
+    // the same bytes mean something else under another schema
+    let mut ix = update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "data");
+    ix.data = instruction::UpdateEntry { ix: UpdateEntryIx { schema_version: 1, data: "data".to_string() } }.data();
+    process(&mut context, &[ix], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.schema_version, 1);
+    assert!(!entry_data.is_verified);
+    assert_eq!(entry_data.verified_hash, None);
+}
+
+#[tokio::test]
+async fn update_authority_can_be_handed_over() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let maintainer = Keypair::new();
+
+    let result = process(&mut context, &[set_update_authority_ix(registry_config, entry, maintainer.pubkey(), maintainer.pubkey())], &[&maintainer]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, maintainer.pubkey())], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.update_authority, maintainer.pubkey());
+    assert_eq!(entry_data.creator, payer);
+
+    // same sized data so the maintainer does not pay for growth
+    process(&mut context, &[update_entry_ix(registry_config, entry, maintainer.pubkey(), payer, system_program::id(), "atad")], &[&maintainer]).await.unwrap();
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.data, "atad");
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "data")], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn registry_authority_updates_entries_only_when_allowed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "moderated")], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let registry_config = init(&mut context, |ix| {
+        ix.name = "moderated".to_string();
+        ix.authority_can_update = true;
+    })
+    .await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    process(&mut context, &[set_update_authority_ix(registry_config, entry, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    process(&mut context, &[update_entry_ix(registry_config, entry, payer, payer, system_program::id(), "moderated")], &[]).await.unwrap();
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.data, "moderated");
+
+    // other signers still need the update authority
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, stranger.pubkey(), payer, system_program::id(), "squatted")], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn removed_delegates_lose_their_rights() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let delegate = Keypair::new();
+    let stranger = Keypair::new();
+    let entry_delegate = delegate_address(&entry, &delegate.pubkey()).0;
+
+    // funded so the record can be paid for and only the authority check fails
+    process(&mut context, &[system_instruction::transfer(&payer, &stranger.pubkey(), 1_000_000_000)], &[]).await.unwrap();
+    let result = process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), stranger.pubkey(), DelegateScope::Edit, None)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    process(&mut context, &[add_delegate_ix(registry_config, entry, delegate.pubkey(), payer, DelegateScope::Edit, None)], &[]).await.unwrap();
+    // same sized data so the delegate does not pay for growth
+    process(&mut context, &[update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, "atad")], &[&delegate]).await.unwrap();
+    assert_eq!(fetch::<EntryData>(&mut context, entry).await.data, "atad");
+
+    let result = process(&mut context, &[remove_delegate_ix(registry_config, entry, entry_delegate, delegate.pubkey())], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let (_, delegate_lamports) = account_size(&mut context, entry_delegate).await;
+    let balance = context.banks_client.get_balance(payer).await.unwrap();
+    process(&mut context, &[remove_delegate_ix(registry_config, entry, entry_delegate, payer)], &[]).await.unwrap();
+    assert!(context.banks_client.get_account(entry_delegate).await.unwrap().is_none());
+    // the record rent goes back to the update authority, minus the fee it paid as fee payer
+    assert_eq!(context.banks_client.get_balance(payer).await.unwrap(), balance + delegate_lamports - 5000);
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, delegate.pubkey(), payer, entry_delegate, "data")], &[&delegate]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn delegate_scope_and_expiry_are_enforced() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+    let (editor, remover, expired) = (Keypair::new(), Keypair::new(), Keypair::new());
+    process(
+        &mut context,
+        &[
+            add_delegate_ix(registry_config, entry, editor.pubkey(), payer, DelegateScope::Edit, None),
+            add_delegate_ix(registry_config, entry, remover.pubkey(), payer, DelegateScope::EditAndRemove, None),
+            add_delegate_ix(registry_config, entry, expired.pubkey(), payer, DelegateScope::EditAndRemove, Some(1)),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let editor_delegate = delegate_address(&entry, &editor.pubkey()).0;
+    let remover_delegate = delegate_address(&entry, &remover.pubkey()).0;
+    let expired_delegate = delegate_address(&entry, &expired.pubkey()).0;
+
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, expired.pubkey(), payer, expired_delegate, "data")], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    // a delegate record only speaks for its own delegate
+    let result = process(&mut context, &[update_entry_ix(registry_config, entry, expired.pubkey(), payer, editor_delegate, "data")], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[remove_entry_ix(registry_config, entry, editor.pubkey(), payer, editor_delegate)], &[&editor]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    let result = process(&mut context, &[remove_entry_ix(registry_config, entry, expired.pubkey(), payer, expired_delegate)], &[&expired]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[remove_entry_ix(registry_config, entry, remover.pubkey(), payer, remover_delegate)], &[&remover]).await.unwrap();
+    assert!(context.banks_client.get_account(entry).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn update_config_changes_only_the_given_fields() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entry_size = 500).await;
+
+    let treasury = Pubkey::new_unique();
+    let ix = config_update(|ix| {
+        ix.permissionless_add = Some(false);
+        ix.entry_seed = Some("renamed".to_string());
+        ix.authority_can_update = Some(true);
+        ix.max_entries = Some(7);
+        ix.moderation_treasury = Some(Some(treasury));
+    });
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    let config: RegistryConfig = fetch(&mut context, registry_config).await;
+    assert!(!config.permissionless_add);
+    assert_eq!(config.entry_seed, "renamed");
+    assert!(config.authority_can_update);
+    assert!(!config.require_proof);
+    assert_eq!(config.max_entries, 7);
+    assert_eq!(config.max_entry_size, 500);
+    assert_eq!(config.moderation_treasury, Some(treasury));
+
+    let stranger = Keypair::new();
+    let ix = config_update(|ix| ix.moderation_treasury = Some(None));
+    let result = process(&mut context, &[update_config_ix(registry_config, stranger.pubkey(), ix)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.moderation_treasury, Some(treasury));
+}
+
+#[tokio::test]
+async fn entry_seed_is_locked_once_the_registry_has_entries() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+
+    let ix = config_update(|ix| ix.entry_seed = Some("renamed".to_string()));
+    let result = process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await;
+    assert_error(result, ErrorCode::SeedLocked);
+
+    // the seed is free again once the last entry is gone
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    let ix = config_update(|ix| ix.entry_seed = Some("moved".to_string()));
+    process(&mut context, &[update_config_ix(registry_config, payer, ix)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_seed, "moved");
+}
+
+#[tokio::test]
+async fn max_entries_caps_the_registry_until_an_entry_is_removed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_config = init(&mut context, |ix| ix.max_entries = 1).await;
+    let entry = add_entry(&mut context, registry_config, "data").await;
+
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, Pubkey::new_unique(), "data")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+
+    process(&mut context, &[remove_entry_ix(registry_config, entry, payer, payer, system_program::id())], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 0);
+    add_entry(&mut context, registry_config, "data").await;
+
+    // raising the cap makes room without removing anything
+    process(&mut context, &[update_config_ix(registry_config, payer, config_update(|ix| ix.max_entries = Some(2)))], &[]).await.unwrap();
+    add_entry(&mut context, registry_config, "data").await;
+    let result = process(&mut context, &[add_entry_ix(registry_config, payer, Pubkey::new_unique(), "data")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+    assert_eq!(fetch::<RegistryConfig>(&mut context, registry_config).await.entry_count, 2);
+}
@@ -1,36 +1,133 @@
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::pubkey::MAX_SEED_LEN;
+use anchor_lang::solana_program::system_instruction;
+use std::convert::TryInto;
 
-declare_id!("govHvVVCZsdJLynaFJdqEWBU9AbJ4aHYdZsWno114V9");
+declare_id!("ASbdGMb4A4JgNqv7iyXkstgD28VUBxKb148DBeYxLtWU");
+const MAX_MULTISIG_SIGNERS: usize = 10;
+// serialized size of RegistryContextAccount with the name and multisig at their maximum
+const REGISTRY_CONTEXT_SIZE: usize = 8 + 1 + 4 + MAX_SEED_LEN + 32 + 33 + 4 + 32 * MAX_MULTISIG_SIGNERS + 1 + 1 + 4 + 8 + 4;
+// serialized size of every EntryData field except `additional_data_url`, with all options set
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 8 + 43 + 32;
 
 #[program]
 pub mod verified_registry {
     use super::*;
 
-    pub fn init(ctx: Context<Init>, _ix: InitIx) -> ProgramResult {
+    pub fn init(ctx: Context<Init>, ix: InitIx) -> ProgramResult {
         let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.bump = ix.bump;
+        registry_context.name = ix.name;
         registry_context.authority = *ctx.accounts.authority.key;
+        registry_context.require_executable = ix.require_executable;
+        registry_context.max_entries = ix.max_entries;
+        registry_context.max_entry_size = ix.max_entry_size;
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn transfer_authority(ctx: Context<TransferVerificationAuthority>) -> ProgramResult {
         let registry_context = &mut ctx.accounts.registry_context;
         registry_context.authority = *ctx.accounts.new_authority.key;
+        registry_context.pending_authority = None;
+        clear_multisig(registry_context);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn propose_authority(ctx: Context<ProposeAuthority>) -> ProgramResult {
+        let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.pending_authority = Some(*ctx.accounts.new_authority.key);
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.authority = *ctx.accounts.new_authority.key;
+        registry_context.pending_authority = None;
+        clear_multisig(registry_context);
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> ProgramResult {
+        let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.pending_authority = None;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn set_multisig(ctx: Context<SetMultisig>, ix: SetMultisigIx) -> ProgramResult {
+        if ix.signers.len() > MAX_MULTISIG_SIGNERS || ix.threshold as usize > ix.signers.len() {
+            return Err(ErrorCode::InvalidMultisig.into());
+        }
+        for (i, signer) in ix.signers.iter().enumerate() {
+            if ix.signers[..i].contains(signer) {
+                return Err(ErrorCode::InvalidMultisig.into());
+            }
+        }
+        let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.multisig_signers = ix.signers;
+        registry_context.multisig_threshold = ix.threshold;
+        Ok(())
+    }
+
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
+    pub fn update_config(ctx: Context<UpdateConfig>, ix: UpdateConfigIx) -> ProgramResult {
+        let registry = *ctx.accounts.registry_context.to_account_info().key;
+        let registry_context = &mut ctx.accounts.registry_context;
+        if let Some(require_executable) = ix.require_executable {
+            config_changed(registry, "require_executable", &registry_context.require_executable, &require_executable);
+            registry_context.require_executable = require_executable;
+        }
+        if let Some(max_entries) = ix.max_entries {
+            config_changed(registry, "max_entries", &registry_context.max_entries, &max_entries);
+            registry_context.max_entries = max_entries;
+        }
+        if let Some(max_entry_size) = ix.max_entry_size {
+            config_changed(registry, "max_entry_size", &registry_context.max_entry_size, &max_entry_size);
+            registry_context.max_entry_size = max_entry_size;
+        }
         Ok(())
     }
     
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn add_entry(ctx: Context<AddEntry>, ix: AddEntryIx) -> ProgramResult {
+        entry_size(&ctx.accounts.registry_context, &ix.additional_data_url)?;
+        let registry_context = &mut ctx.accounts.registry_context;
+        if registry_context.max_entries > 0 && registry_context.entry_count >= registry_context.max_entries as u64 {
+            return Err(ErrorCode::RegistryFull.into());
+        }
+        registry_context.entry_count += 1;
         let clock = Clock::get().unwrap();
         let timestamp = clock.unix_timestamp;
         let entry = &mut ctx.accounts.entry;
+        entry.bump = ix.bump;
+        entry.registry = ctx.accounts.registry_context.key();
         entry.address = ix.address;
         entry.additional_data_url = ix.additional_data_url;
         entry.created_at = timestamp;
         entry.updated_at = timestamp;
+        entry.rent_payer = *ctx.accounts.authority.key;
+        if ctx.accounts.registry_context.require_executable {
+            entry.deployment = Some(program_deployment(&entry.address, ctx.remaining_accounts)?);
+        }
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn update_entry(ctx: Context<UpdateEntry>, ix: UpdateEntryIx) -> ProgramResult {
+        resize_entry(
+            &ctx.accounts.entry.to_account_info(),
+            entry_size(&ctx.accounts.registry_context, &ix.additional_data_url)?,
+            &ctx.accounts.authority,
+            &ctx.accounts.rent_payer,
+            &ctx.accounts.system_program,
+        )?;
         let clock = Clock::get().unwrap();
         let timestamp = clock.unix_timestamp;
         let entry = &mut ctx.accounts.entry;
@@ -39,8 +136,11 @@ pub mod verified_registry {
         Ok(())
     }
 
+    #[access_control(authorized(&ctx.accounts.registry_context, &ctx.accounts.authority, ctx.remaining_accounts))]
     pub fn remove_entry(ctx: Context<RemoveEntry>) -> ProgramResult {
-        ctx.accounts.entry.close(ctx.accounts.authority.to_account_info()).unwrap();
+        let registry_context = &mut ctx.accounts.registry_context;
+        registry_context.entry_count = registry_context.entry_count.saturating_sub(1);
+        ctx.accounts.entry.close(ctx.accounts.rent_payer.to_account_info()).unwrap();
         Ok(())
     }
 }
@@ -50,6 +150,10 @@ pub mod verified_registry {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitIx {
     pub bump: u8,
+    pub name: String,
+    pub require_executable: bool,
+    pub max_entries: u32,
+    pub max_entry_size: u32,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -57,7 +161,6 @@ pub struct AddEntryIx {
     pub address: Pubkey,
     pub additional_data_url: String,
     pub bump: u8,
-    pub seed: [u8; 32],
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -65,6 +168,19 @@ pub struct UpdateEntryIx {
     pub additional_data_url: String,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SetMultisigIx {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateConfigIx {
+    pub require_executable: Option<bool>,
+    pub max_entries: Option<u32>,
+    pub max_entry_size: Option<u32>,
+}
+
 ///////////////// Contexts /////////////////
 #[derive(Accounts)]
 #[instruction(ix: InitIx)]
@@ -72,9 +188,8 @@ pub struct Init<'info> {
     #[account(
         init,
         payer = authority,
-        // extra space for future upgrades
-        space = 128,
-        seeds = [b"registry-context".as_ref()],
+        space = REGISTRY_CONTEXT_SIZE,
+        seeds = [b"registry-context".as_ref(), ix.name.as_ref()],
         bump = ix.bump,
     )]
     pub registry_context: Account<'info, RegistryContextAccount>,
@@ -84,53 +199,99 @@ pub struct Init<'info> {
 
 #[derive(Accounts)]
 pub struct TransferVerificationAuthority<'info> {
-    // TODO constraint this is the singleton registry context address? Maybe we can rely on account discriminator + owner check
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
+    pub registry_context: Account<'info, RegistryContextAccount>,
+    pub authority: Signer<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
     pub registry_context: Account<'info, RegistryContextAccount>,
-    #[account(constraint = registry_context.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    pub new_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
+    pub registry_context: Account<'info, RegistryContextAccount>,
+    #[account(constraint = registry_context.pending_authority == Some(*new_authority.to_account_info().key) @ ErrorCode::InvalidPendingAuthority)]
     pub new_authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
+    pub registry_context: Account<'info, RegistryContextAccount>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMultisig<'info> {
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
+    pub registry_context: Account<'info, RegistryContextAccount>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
+    pub registry_context: Account<'info, RegistryContextAccount>,
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(ix: AddEntryIx)]
 pub struct AddEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
     pub registry_context: Account<'info, RegistryContextAccount>,
     #[account(
         init,
         payer = authority,
-        // extra space for future upgrades
-        space = 256,
-        // TODO constraint seed == ix.address? or just use address as seed?
-        seeds = [b"governance-program".as_ref(), ix.seed.as_ref()],
+        // sized to the payload, update_entry reallocs when it changes
+        space = ENTRY_BASE_SIZE + ix.additional_data_url.try_to_vec().unwrap().len(),
+        seeds = [b"governance-program".as_ref(), registry_context.key().as_ref(), ix.address.as_ref()],
         bump = ix.bump,
     )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_context.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateEntry<'info> {
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
     pub registry_context: Account<'info, RegistryContextAccount>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"governance-program".as_ref(), registry_context.key().as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == registry_context.key() @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_context.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
+    #[account(mut)]
     pub authority: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == rent_payer.key() @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct RemoveEntry<'info> {
-    // TODO constraint this is the singleton registry context address? Maybe we can rely on account discriminator + owner check
-    #[account(mut)]
+    #[account(mut, seeds = [b"registry-context".as_ref(), registry_context.name.as_ref()], bump = registry_context.bump)]
     pub registry_context: Account<'info, RegistryContextAccount>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"governance-program".as_ref(), registry_context.key().as_ref(), entry.address.as_ref()],
+        bump = entry.bump,
+        constraint = entry.registry == registry_context.key() @ ErrorCode::InvalidRegistry,
+    )]
     pub entry: Account<'info, EntryData>,
-    #[account(constraint = registry_context.authority == *authority.to_account_info().key @ ErrorCode::InsufficientAuthority)]
     pub authority: Signer<'info>,
+    #[account(mut, constraint = entry.rent_payer == rent_payer.key() @ ErrorCode::InvalidRentRecipient)]
+    pub rent_payer: AccountInfo<'info>,
 }
 
 ///////////////// DATA /////////////////
@@ -138,20 +299,176 @@ pub struct RemoveEntry<'info> {
 #[account]
 #[derive(Default)]
 pub struct RegistryContextAccount{
+    pub bump: u8,
+    pub name: String,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub multisig_signers: Vec<Pubkey>,
+    pub multisig_threshold: u8,
+    pub require_executable: bool,
+    pub max_entries: u32,
+    pub entry_count: u64,
+    pub max_entry_size: u32,
 }
 
 #[account]
 #[derive(Default)]
 pub struct EntryData {
+    pub bump: u8,
+    pub registry: Pubkey,
     pub address: Pubkey,
     pub additional_data_url: String,
     pub created_at: i64,
     pub updated_at: i64,
+    pub deployment: Option<ProgramDeployment>,
+    pub rent_payer: Pubkey,
+}
+
+#[derive(Debug, Clone, PartialEq, AnchorSerialize, AnchorDeserialize)]
+pub struct ProgramDeployment {
+    pub upgrade_authority: Option<Pubkey>,
+    pub deploy_slot: Option<u64>,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub registry: Pubkey,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+///////////////// UTILS /////////////////
+
+pub fn authorized(registry_context: &RegistryContextAccount, signer: &AccountInfo, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+    if signer.is_signer && registry_context.authority == *signer.key {
+        return Ok(());
+    }
+    if registry_context.multisig_threshold > 0 {
+        let mut approvals: Vec<&Pubkey> = Vec::new();
+        let signers = remaining_accounts.iter().map(|account| (account.key, account.is_signer));
+        for (key, is_signer) in std::iter::once((signer.key, signer.is_signer)).chain(signers) {
+            if is_signer && registry_context.multisig_signers.contains(key) && !approvals.contains(&key) {
+                approvals.push(key);
+            }
+        }
+        if approvals.len() >= registry_context.multisig_threshold as usize {
+            return Ok(());
+        }
+    }
+    Err(ErrorCode::InsufficientAuthority.into())
+}
+
+// signers approved by the previous authority do not carry over to the next one
+pub fn clear_multisig(registry_context: &mut RegistryContextAccount) {
+    registry_context.multisig_signers.clear();
+    registry_context.multisig_threshold = 0;
+}
+
+pub fn config_changed<T: ToString>(registry: Pubkey, field: &str, old_value: &T, new_value: &T) {
+    emit!(ConfigUpdated {
+        registry,
+        field: field.to_string(),
+        old_value: old_value.to_string(),
+        new_value: new_value.to_string(),
+    });
+}
+
+pub fn program_data_header(program_data: &AccountInfo) -> std::result::Result<(u64, Option<Pubkey>), ProgramError> {
+    if program_data.owner != &bpf_loader_upgradeable::id() {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    // UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+    let data = program_data.try_borrow_data()?;
+    if data.len() < 45 || data[0..4] != [3, 0, 0, 0] {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    let slot = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let upgrade_authority = match data[12] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[13..45].try_into().unwrap())),
+        _ => return Err(ErrorCode::InvalidProgramData.into()),
+    };
+    Ok((slot, upgrade_authority))
+}
+
+pub fn program_deployment(program_id: &Pubkey, remaining_accounts: &[AccountInfo]) -> std::result::Result<ProgramDeployment, ProgramError> {
+    let program = remaining_accounts
+        .iter()
+        .find(|account| account.key == program_id)
+        .ok_or(ErrorCode::InvalidProgram)?;
+    if !program.executable {
+        return Err(ErrorCode::InvalidProgram.into());
+    }
+    if program.owner != &bpf_loader_upgradeable::id() {
+        return Ok(ProgramDeployment { upgrade_authority: None, deploy_slot: None });
+    }
+    // UpgradeableLoaderState::Program { programdata_address: Pubkey }
+    let programdata_address = {
+        let data = program.try_borrow_data()?;
+        if data.len() < 36 || data[0..4] != [2, 0, 0, 0] {
+            return Err(ErrorCode::InvalidProgram.into());
+        }
+        Pubkey::new_from_array(data[4..36].try_into().unwrap())
+    };
+    let program_data = remaining_accounts
+        .iter()
+        .find(|account| *account.key == programdata_address)
+        .ok_or(ErrorCode::InvalidProgramData)?;
+    let (slot, upgrade_authority) = program_data_header(program_data)?;
+    Ok(ProgramDeployment { upgrade_authority, deploy_slot: Some(slot) })
+}
+
+pub fn entry_size<T: AnchorSerialize>(registry_context: &RegistryContextAccount, data: &T) -> std::result::Result<usize, ProgramError> {
+    let size = ENTRY_BASE_SIZE + data.try_to_vec().unwrap().len();
+    if registry_context.max_entry_size > 0 && size > registry_context.max_entry_size as usize {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    Ok(size)
+}
+
+// growth is paid by `payer`, a shrink refunds `rent_payer`
+pub fn resize_entry<'info>(entry: &AccountInfo<'info>, size: usize, payer: &AccountInfo<'info>, rent_payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> ProgramResult {
+    let current_size = entry.data_len();
+    if size == current_size {
+        return Ok(());
+    }
+    if size > current_size + MAX_PERMITTED_DATA_INCREASE {
+        return Err(ErrorCode::DataTooLarge.into());
+    }
+    let rent = Rent::get()?.minimum_balance(size);
+    if rent > entry.lamports() {
+        invoke(
+            &system_instruction::transfer(payer.key, entry.key, rent - entry.lamports()),
+            &[payer.clone(), entry.clone(), system_program.clone()],
+        )?;
+    } else {
+        let refund = entry.lamports() - rent;
+        **entry.try_borrow_mut_lamports()? -= refund;
+        **rent_payer.try_borrow_mut_lamports()? += refund;
+    }
+    // zero the grown tail, it may still hold bytes from an earlier shrink in this transaction
+    entry.realloc(size, true)
 }
 
 #[error]
 pub enum ErrorCode {
     #[msg("User does not have authority to modify this registry")]
     InsufficientAuthority,
+    #[msg("Signer is not the pending authority for this registry")]
+    InvalidPendingAuthority,
+    #[msg("Entry does not belong to this registry")]
+    InvalidRegistry,
+    #[msg("Multisig signers or threshold are invalid")]
+    InvalidMultisig,
+    #[msg("Referenced account is not an executable program")]
+    InvalidProgram,
+    #[msg("Program data account does not match the program")]
+    InvalidProgramData,
+    #[msg("Registry has reached its maximum number of entries")]
+    RegistryFull,
+    #[msg("Entry data exceeds the maximum entry size for this registry")]
+    DataTooLarge,
+    #[msg("Rent recipient does not match the entry rent payer")]
+    InvalidRentRecipient,
 }
\ No newline at end of file
@@ -0,0 +1,493 @@
+use anchor_lang::{AccountDeserialize, AnchorSerialize, InstructionData, ToAccountMetas};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use verified_registry::{
+    accounts, instruction, AddEntryIx, EntryData, ErrorCode, InitIx, ProgramDeployment, RegistryContextAccount, SetMultisigIx,
+    UpdateConfigIx, UpdateEntryIx,
+};
+
+const ENTRY_BASE_SIZE: usize = 8 + 1 + 32 + 32 + 8 + 8 + 43 + 32;
+
+fn program_test() -> ProgramTest {
+    ProgramTest::new("verified_registry", verified_registry::id(), processor!(verified_registry::entry))
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(error as u32 + 300)),
+    );
+}
+
+async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn init(context: &mut ProgramTestContext, configure: impl FnOnce(&mut InitIx)) -> Pubkey {
+    let mut ix = InitIx { bump: 0, name: "registry".to_string(), require_executable: false, max_entries: 0, max_entry_size: 0 };
+    configure(&mut ix);
+    let (registry_context, bump) = Pubkey::find_program_address(&[b"registry-context", ix.name.as_bytes()], &verified_registry::id());
+    ix.bump = bump;
+    let init = Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::Init { registry_context, authority: context.payer.pubkey(), system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::Init { ix }.data(),
+    };
+    process(context, &[init], &[]).await.unwrap();
+    registry_context
+}
+
+fn entry_address(registry_context: &Pubkey, address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"governance-program", registry_context.as_ref(), address.as_ref()], &verified_registry::id())
+}
+
+fn add_entry_ix(registry_context: Pubkey, authority: Pubkey, address: Pubkey, additional_data_url: &str) -> Instruction {
+    let (entry, bump) = entry_address(&registry_context, &address);
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::AddEntry { registry_context, entry, authority, system_program: system_program::id() }.to_account_metas(None),
+        data: instruction::AddEntry { ix: AddEntryIx { address, additional_data_url: additional_data_url.to_string(), bump } }.data(),
+    }
+}
+
+async fn add_entry(context: &mut ProgramTestContext, registry_context: Pubkey, additional_data_url: &str) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let ix = add_entry_ix(registry_context, context.payer.pubkey(), address, additional_data_url);
+    process(context, &[ix], &[]).await.unwrap();
+    entry_address(&registry_context, &address).0
+}
+
+fn update_entry_ix(registry_context: Pubkey, entry: Pubkey, authority: Pubkey, rent_payer: Pubkey, additional_data_url: &str) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::UpdateEntry { registry_context, entry, authority, rent_payer, system_program: system_program::id() }
+            .to_account_metas(None),
+        data: instruction::UpdateEntry { ix: UpdateEntryIx { additional_data_url: additional_data_url.to_string() } }.data(),
+    }
+}
+
+async fn account_size(context: &mut ProgramTestContext, address: Pubkey) -> (usize, u64) {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    (account.data.len(), account.lamports)
+}
+
+fn propose_authority_ix(registry_context: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::ProposeAuthority { registry_context, authority, new_authority }.to_account_metas(None),
+        data: instruction::ProposeAuthority {}.data(),
+    }
+}
+
+fn accept_authority_ix(registry_context: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::AcceptAuthority { registry_context, new_authority }.to_account_metas(None),
+        data: instruction::AcceptAuthority {}.data(),
+    }
+}
+
+fn cancel_authority_transfer_ix(registry_context: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::CancelAuthorityTransfer { registry_context, authority }.to_account_metas(None),
+        data: instruction::CancelAuthorityTransfer {}.data(),
+    }
+}
+
+fn set_multisig_ix(registry_context: Pubkey, authority: Pubkey, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::SetMultisig { registry_context, authority }.to_account_metas(None),
+        data: instruction::SetMultisig { ix: SetMultisigIx { signers, threshold } }.data(),
+    }
+}
+
+fn transfer_authority_ix(registry_context: Pubkey, authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::TransferVerificationAuthority { registry_context, authority, new_authority }.to_account_metas(None),
+        data: instruction::TransferAuthority {}.data(),
+    }
+}
+
+fn update_config_ix(registry_context: Pubkey, authority: Pubkey, ix: UpdateConfigIx) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::UpdateConfig { registry_context, authority }.to_account_metas(None),
+        data: instruction::UpdateConfig { ix }.data(),
+    }
+}
+
+fn remove_entry_ix(registry_context: Pubkey, entry: Pubkey, authority: Pubkey, rent_payer: Pubkey) -> Instruction {
+    Instruction {
+        program_id: verified_registry::id(),
+        accounts: accounts::RemoveEntry { registry_context, entry, authority, rent_payer }.to_account_metas(None),
+        data: instruction::RemoveEntry {}.data(),
+    }
+}
+
+// co-signers approve through the remaining accounts
+fn with_signers(mut ix: Instruction, signers: &[&Keypair]) -> Instruction {
+    ix.accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    ix
+}
+
+// an upgradeable program with its program data, neither is ever invoked
+fn add_upgradeable_program(program_test: &mut ProgramTest, deploy_slot: u64, upgrade_authority: Pubkey) -> (Pubkey, Pubkey) {
+    let program = Pubkey::new_unique();
+    let (program_data, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_state = vec![2, 0, 0, 0];
+    program_state.extend_from_slice(program_data.as_ref());
+    let mut program_data_state = vec![3, 0, 0, 0];
+    program_data_state.extend_from_slice(&deploy_slot.to_le_bytes());
+    program_data_state.push(1);
+    program_data_state.extend_from_slice(upgrade_authority.as_ref());
+    for (address, data, executable) in [(program, program_state, true), (program_data, program_data_state, false)] {
+        program_test.add_account(
+            address,
+            Account { lamports: Rent::default().minimum_balance(data.len()), data, owner: bpf_loader_upgradeable::id(), executable, rent_epoch: 0 },
+        );
+    }
+    (program, program_data)
+}
+
+fn with_accounts(mut ix: Instruction, accounts: &[Pubkey]) -> Instruction {
+    ix.accounts.extend(accounts.iter().map(|account| AccountMeta::new_readonly(*account, false)));
+    ix
+}
+
+#[tokio::test]
+async fn update_entry_grows_and_shrinks_the_account() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_context, "https://a").await;
+
+    let grown = format!("https://{}", "x".repeat(600));
+    process(&mut context, &[update_entry_ix(registry_context, entry, payer, payer, &grown)], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + grown.try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.additional_data_url, grown);
+
+    process(&mut context, &[update_entry_ix(registry_context, entry, payer, payer, "https://b")], &[]).await.unwrap();
+    let (size, lamports) = account_size(&mut context, entry).await;
+    assert_eq!(size, ENTRY_BASE_SIZE + "https://b".to_string().try_to_vec().unwrap().len());
+    assert_eq!(lamports, Rent::default().minimum_balance(size));
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.additional_data_url, "https://b");
+}
+
+#[tokio::test]
+async fn shrink_then_grow_in_one_transaction_zeroes_the_tail() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_context, &"x".repeat(300)).await;
+
+    let regrown = "y".repeat(300);
+    process(
+        &mut context,
+        &[update_entry_ix(registry_context, entry, payer, payer, ""), update_entry_ix(registry_context, entry, payer, payer, &regrown)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry).await;
+    assert_eq!(entry_data.additional_data_url, regrown);
+}
+
+#[tokio::test]
+async fn update_entry_rejects_data_above_the_max_entry_size() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.max_entry_size = (ENTRY_BASE_SIZE + 100) as u32).await;
+    let entry = add_entry(&mut context, registry_context, "https://a").await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_context, entry, payer, payer, &"x".repeat(100))], &[]).await;
+    assert_error(result, ErrorCode::DataTooLarge);
+}
+
+#[tokio::test]
+async fn shrink_refunds_only_the_rent_payer() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let entry = add_entry(&mut context, registry_context, &"x".repeat(300)).await;
+
+    let result = process(&mut context, &[update_entry_ix(registry_context, entry, payer, Pubkey::new_unique(), "https://b")], &[]).await;
+    assert_error(result, ErrorCode::InvalidRentRecipient);
+}
+
+#[tokio::test]
+async fn proposed_authority_takes_over_once_it_accepts() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_context, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.authority, payer);
+    assert_eq!(config.pending_authority, Some(new_authority.pubkey()));
+
+    process(&mut context, &[accept_authority_ix(registry_context, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert_eq!(config.pending_authority, None);
+
+    // the previous authority lost its rights
+    let result = process(&mut context, &[propose_authority_ix(registry_context, payer, payer)], &[]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn only_the_pending_authority_can_accept() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+    let impostor = Keypair::new();
+
+    let result = process(&mut context, &[accept_authority_ix(registry_context, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_context, payer, new_authority.pubkey())], &[]).await.unwrap();
+    let result = process(&mut context, &[accept_authority_ix(registry_context, impostor.pubkey())], &[&impostor]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+    assert_eq!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.authority, payer);
+}
+
+#[tokio::test]
+async fn cancelled_handoff_cannot_be_accepted() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let new_authority = Keypair::new();
+
+    process(&mut context, &[propose_authority_ix(registry_context, payer, new_authority.pubkey())], &[]).await.unwrap();
+    process(&mut context, &[cancel_authority_transfer_ix(registry_context, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.pending_authority, None);
+
+    let result = process(&mut context, &[accept_authority_ix(registry_context, new_authority.pubkey())], &[&new_authority]).await;
+    assert_error(result, ErrorCode::InvalidPendingAuthority);
+}
+
+#[tokio::test]
+async fn only_the_authority_can_propose_or_cancel() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let stranger = Keypair::new();
+
+    let result = process(&mut context, &[propose_authority_ix(registry_context, stranger.pubkey(), stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    process(&mut context, &[propose_authority_ix(registry_context, payer, Pubkey::new_unique())], &[]).await.unwrap();
+    let result = process(&mut context, &[cancel_authority_transfer_ix(registry_context, stranger.pubkey())], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.pending_authority.is_some());
+}
+
+#[tokio::test]
+async fn multisig_members_act_once_the_threshold_is_met() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let (member_a, member_b) = (Keypair::new(), Keypair::new());
+    let signers = vec![member_a.pubkey(), member_b.pubkey()];
+    process(&mut context, &[set_multisig_ix(registry_context, payer, signers.clone(), 2)], &[]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.multisig_signers, signers);
+    assert_eq!(config.multisig_threshold, 2);
+
+    let new_authority = Pubkey::new_unique();
+    let result = process(&mut context, &[propose_authority_ix(registry_context, member_a.pubkey(), new_authority)], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // a member listed twice still counts once
+    let ix = with_signers(propose_authority_ix(registry_context, member_a.pubkey(), new_authority), &[&member_a]);
+    let result = process(&mut context, &[ix], &[&member_a]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    let ix = with_signers(propose_authority_ix(registry_context, member_a.pubkey(), new_authority), &[&member_b]);
+    process(&mut context, &[ix], &[&member_a, &member_b]).await.unwrap();
+    assert_eq!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.pending_authority, Some(new_authority));
+}
+
+#[tokio::test]
+async fn set_multisig_rejects_invalid_signer_sets() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let member = Pubkey::new_unique();
+
+    let result = process(&mut context, &[set_multisig_ix(registry_context, payer, vec![member], 2)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let result = process(&mut context, &[set_multisig_ix(registry_context, payer, vec![member, member], 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+    let too_many = (0..11).map(|_| Pubkey::new_unique()).collect();
+    let result = process(&mut context, &[set_multisig_ix(registry_context, payer, too_many, 1)], &[]).await;
+    assert_error(result, ErrorCode::InvalidMultisig);
+
+    let stranger = Keypair::new();
+    let result = process(&mut context, &[set_multisig_ix(registry_context, stranger.pubkey(), vec![stranger.pubkey()], 1)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+}
+
+#[tokio::test]
+async fn handing_over_authority_clears_the_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |_| {}).await;
+    let member = Keypair::new();
+    process(&mut context, &[set_multisig_ix(registry_context, payer, vec![member.pubkey()], 1)], &[]).await.unwrap();
+
+    let new_authority = Keypair::new();
+    process(&mut context, &[transfer_authority_ix(registry_context, payer, new_authority.pubkey())], &[&new_authority]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.authority, new_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+    let result = process(&mut context, &[propose_authority_ix(registry_context, member.pubkey(), member.pubkey())], &[&member]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+
+    // the same holds for the two step handoff
+    let authority = new_authority;
+    process(&mut context, &[set_multisig_ix(registry_context, authority.pubkey(), vec![member.pubkey()], 1)], &[&authority]).await.unwrap();
+    let next_authority = Keypair::new();
+    process(&mut context, &[propose_authority_ix(registry_context, member.pubkey(), next_authority.pubkey())], &[&member]).await.unwrap();
+    process(&mut context, &[accept_authority_ix(registry_context, next_authority.pubkey())], &[&next_authority]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.authority, next_authority.pubkey());
+    assert!(config.multisig_signers.is_empty());
+    assert_eq!(config.multisig_threshold, 0);
+}
+
+#[tokio::test]
+async fn config_fits_the_longest_seeds_and_a_full_multisig() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.name = "n".repeat(32)).await;
+
+    let signers: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+    process(&mut context, &[set_multisig_ix(registry_context, payer, signers.clone(), 10)], &[]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert_eq!(config.name, "n".repeat(32));
+    assert_eq!(config.multisig_signers, signers);
+}
+
+#[tokio::test]
+async fn executable_registry_snapshots_the_program_deployment() {
+    let mut program_test = program_test();
+    let upgrade_authority = Pubkey::new_unique();
+    let (program, program_data) = add_upgradeable_program(&mut program_test, 42, upgrade_authority);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.require_executable = true).await;
+
+    let ix = with_accounts(add_entry_ix(registry_context, payer, program, "https://example.com"), &[program, program_data]);
+    process(&mut context, &[ix], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry_address(&registry_context, &program).0).await;
+    assert_eq!(entry_data.deployment, Some(ProgramDeployment { upgrade_authority: Some(upgrade_authority), deploy_slot: Some(42) }));
+
+    // programs that cannot be upgraded have nothing to snapshot
+    let ix = with_accounts(add_entry_ix(registry_context, payer, verified_registry::id(), "https://example.com"), &[verified_registry::id()]);
+    process(&mut context, &[ix], &[]).await.unwrap();
+    let entry_data: EntryData = fetch(&mut context, entry_address(&registry_context, &verified_registry::id()).0).await;
+    assert_eq!(entry_data.deployment, Some(ProgramDeployment { upgrade_authority: None, deploy_slot: None }));
+}
+
+#[tokio::test]
+async fn executable_registry_rejects_entries_that_are_not_programs() {
+    let mut program_test = program_test();
+    let (program, _) = add_upgradeable_program(&mut program_test, 42, Pubkey::new_unique());
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.require_executable = true).await;
+
+    let result = process(&mut context, &[add_entry_ix(registry_context, payer, program, "https://example.com")], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+    let result = process(&mut context, &[with_accounts(add_entry_ix(registry_context, payer, payer, "https://example.com"), &[payer])], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgram);
+    let result = process(&mut context, &[with_accounts(add_entry_ix(registry_context, payer, program, "https://example.com"), &[program])], &[]).await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    // the program data has to be the account the program points at
+    let result = process(
+        &mut context,
+        &[with_accounts(add_entry_ix(registry_context, payer, program, "https://example.com"), &[program, Pubkey::new_unique()])],
+        &[],
+    )
+    .await;
+    assert_error(result, ErrorCode::InvalidProgramData);
+    assert!(context.banks_client.get_account(entry_address(&registry_context, &program).0).await.unwrap().is_none());
+
+    // entries are not checked while the registry does not require executables
+    let registry_context = init(&mut context, |ix| ix.name = "open".to_string()).await;
+    process(&mut context, &[add_entry_ix(registry_context, payer, payer, "https://example.com")], &[]).await.unwrap();
+    assert_eq!(fetch::<EntryData>(&mut context, entry_address(&registry_context, &payer).0).await.deployment, None);
+}
+
+#[tokio::test]
+async fn update_config_changes_only_the_given_fields() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.max_entry_size = 500).await;
+
+    let ix = UpdateConfigIx { require_executable: Some(true), max_entries: Some(7), max_entry_size: None };
+    process(&mut context, &[update_config_ix(registry_context, payer, ix)], &[]).await.unwrap();
+    let config: RegistryContextAccount = fetch(&mut context, registry_context).await;
+    assert!(config.require_executable);
+    assert_eq!(config.max_entries, 7);
+    assert_eq!(config.max_entry_size, 500);
+
+    let stranger = Keypair::new();
+    let ix = UpdateConfigIx { require_executable: Some(false), max_entries: None, max_entry_size: None };
+    let result = process(&mut context, &[update_config_ix(registry_context, stranger.pubkey(), ix)], &[&stranger]).await;
+    assert_error(result, ErrorCode::InsufficientAuthority);
+    assert!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.require_executable);
+}
+
+#[tokio::test]
+async fn max_entries_caps_the_registry_until_an_entry_is_removed() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let registry_context = init(&mut context, |ix| ix.max_entries = 1).await;
+    let entry = add_entry(&mut context, registry_context, "https://a").await;
+
+    let result = process(&mut context, &[add_entry_ix(registry_context, payer, Pubkey::new_unique(), "https://b")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+
+    process(&mut context, &[remove_entry_ix(registry_context, entry, payer, payer)], &[]).await.unwrap();
+    assert_eq!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.entry_count, 0);
+    add_entry(&mut context, registry_context, "https://b").await;
+
+    // raising the cap makes room without removing anything
+    let ix = UpdateConfigIx { require_executable: None, max_entries: Some(2), max_entry_size: None };
+    process(&mut context, &[update_config_ix(registry_context, payer, ix)], &[]).await.unwrap();
+    add_entry(&mut context, registry_context, "https://c").await;
+    let result = process(&mut context, &[add_entry_ix(registry_context, payer, Pubkey::new_unique(), "https://d")], &[]).await;
+    assert_error(result, ErrorCode::RegistryFull);
+    assert_eq!(fetch::<RegistryContextAccount>(&mut context, registry_context).await.entry_count, 2);
+}
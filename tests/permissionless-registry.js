@@ -0,0 +1,230 @@
+const anchor = require("@project-serum/anchor");
+const web3 = require("@solana/web3.js");
+const assert = require("assert");
+
+const ENTRY_SEED = "governance-program";
+
+describe("Permissionless Registry Tests", () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.PermissionlessRegistry;
+  const seed = web3.Keypair.generate().publicKey.toBuffer();
+  const newAuthority = web3.Keypair.generate();
+  const entryData = {
+    name: "registry-test",
+    description: "Entry in the permissionless registry",
+    imageUrl: "https://example.com/logo.png",
+    programAddress: web3.Keypair.generate().publicKey,
+    additionalDataUrl: "https://example.com/data.json",
+    authority: provider.wallet.publicKey,
+  };
+  let entry;
+
+  it("Add entry", async () => {
+    const [entryAddress, bump] = await web3.PublicKey.findProgramAddress(
+      [anchor.utils.bytes.utf8.encode(ENTRY_SEED), seed],
+      program.programId
+    );
+    entry = entryAddress;
+
+    const tx = await program.rpc.addEntry(
+      { entry: entryData, bump, seed: [...seed], validateProgram: false },
+      {
+        accounts: {
+          entry,
+          user: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(data.data.name, entryData.name);
+    assert.equal(
+      data.data.authority.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
+    assert.equal(data.deployment, null);
+  });
+
+  it("Update entry persists data and keeps the authority", async () => {
+    const tx = await program.rpc.updateEntry(
+      {
+        entry: {
+          ...entryData,
+          description: "Updated description",
+          authority: newAuthority.publicKey,
+        },
+        validateProgram: false,
+      },
+      {
+        accounts: {
+          entry,
+          user: provider.wallet.publicKey,
+          rentPayer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(data.data.description, "Updated description");
+    assert.equal(
+      data.data.authority.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
+    assert.ok(data.updatedAt.gte(data.createdAt));
+  });
+
+  it("Update entry validates a changed program", async () => {
+    const accounts = {
+      entry,
+      user: provider.wallet.publicKey,
+      rentPayer: provider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+    const programAddress = anchor.web3.SystemProgram.programId;
+    await program.rpc.updateEntry(
+      { entry: { ...entryData, programAddress }, validateProgram: true },
+      {
+        accounts,
+        remainingAccounts: [
+          { pubkey: programAddress, isWritable: false, isSigner: false },
+        ],
+      }
+    );
+    let data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(
+      data.data.programAddress.toBase58(),
+      programAddress.toBase58()
+    );
+    assert.deepStrictEqual(data.deployment, {
+      upgradeAuthority: null,
+      deploySlot: null,
+    });
+
+    const tx = await program.rpc.updateEntry(
+      { entry: entryData, validateProgram: false },
+      { accounts }
+    );
+    console.log("Your transaction signature", tx);
+    data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(data.deployment, null);
+  });
+
+  it("Cannot update entry with a non executable program", async () => {
+    try {
+      await program.rpc.updateEntry(
+        { entry: entryData, validateProgram: true },
+        {
+          accounts: {
+            entry,
+            user: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          remainingAccounts: [
+            {
+              pubkey: entryData.programAddress,
+              isWritable: false,
+              isSigner: false,
+            },
+          ],
+        }
+      );
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 301);
+    }
+  });
+
+  it("Transfer entry authority pending acceptance", async () => {
+    const tx = await program.rpc.transferEntryAuthority(
+      { requireAcceptance: true },
+      {
+        accounts: {
+          entry,
+          user: provider.wallet.publicKey,
+          newAuthority: newAuthority.publicKey,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(
+      data.data.authority.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
+    assert.equal(
+      data.pendingAuthority.toBase58(),
+      newAuthority.publicKey.toBase58()
+    );
+  });
+
+  it("Cannot accept entry authority if not pending", async () => {
+    const other = web3.Keypair.generate();
+    try {
+      await program.rpc.acceptEntryAuthority({
+        accounts: { entry, newAuthority: other.publicKey },
+        signers: [other],
+      });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 303);
+    }
+  });
+
+  it("Accept entry authority", async () => {
+    const tx = await program.rpc.acceptEntryAuthority({
+      accounts: { entry, newAuthority: newAuthority.publicKey },
+      signers: [newAuthority],
+    });
+    console.log("Your transaction signature", tx);
+    const data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(
+      data.data.authority.toBase58(),
+      newAuthority.publicKey.toBase58()
+    );
+    assert.equal(data.pendingAuthority, null);
+  });
+
+  it("Cannot update entry after handing off authority", async () => {
+    try {
+      await program.rpc.updateEntry(
+        { entry: entryData, validateProgram: false },
+        {
+          accounts: {
+            entry,
+            user: provider.wallet.publicKey,
+            rentPayer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+        }
+      );
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 300);
+    }
+  });
+
+  it("Transfer entry authority directly", async () => {
+    const tx = await program.rpc.transferEntryAuthority(
+      { requireAcceptance: false },
+      {
+        accounts: {
+          entry,
+          user: newAuthority.publicKey,
+          newAuthority: provider.wallet.publicKey,
+        },
+        signers: [newAuthority],
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.governanceProgramAccount.fetch(entry);
+    assert.equal(
+      data.data.authority.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
+    assert.equal(data.pendingAuthority, null);
+  });
+});
@@ -1,8 +1,10 @@
 const anchor = require("@project-serum/anchor");
 const web3 = require("@solana/web3.js");
 const assert = require("assert");
+const { Token, TOKEN_PROGRAM_ID } = require("@solana/spl-token");
 
 const REGISTRY_CONFIG_SEED = "registry-config";
+const REGISTRY_NAME = "test-registry";
 const ENTRY_SEED = "governance-program";
 
 describe("Registry Tests", () => {
@@ -14,12 +16,29 @@ describe("Registry Tests", () => {
 
   it("Initializes the registry", async () => {
     const [registryConfig, bump] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const tx = await program.rpc.init(
-      { bump, entrySeed: ENTRY_SEED, permissionless_add: true },
+      {
+        bump,
+        name: REGISTRY_NAME,
+        entrySeed: ENTRY_SEED,
+        permissionless_add: true,
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        challengeStake: new anchor.BN(0),
+        votePeriod: new anchor.BN(60 * 60),
+        voteQuorum: new anchor.BN(0),
+        voterRewardPct: 0,
+        voteMint: null,
+      },
       {
         accounts: {
           registryConfig,
@@ -40,13 +59,17 @@ describe("Registry Tests", () => {
 
   it("Add entry", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -58,6 +81,7 @@ describe("Registry Tests", () => {
         data: testData,
         schema_version: 0,
         address: programInstance.publicKey,
+        proof: { none: {} },
       },
       {
         accounts: {
@@ -81,13 +105,17 @@ describe("Registry Tests", () => {
 
   it("Verify an entry", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -112,13 +140,17 @@ describe("Registry Tests", () => {
 
   it("Remove an entry", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -128,6 +160,7 @@ describe("Registry Tests", () => {
         registryConfig,
         entry: seededPubkey,
         authority: provider.wallet.publicKey,
+        rentRecipient: provider.wallet.publicKey,
       },
     });
     console.log("Your transaction signature", tx);
@@ -142,13 +175,17 @@ describe("Registry Tests", () => {
   it("Add entry back", async () => {
     const data = "https://fkrok";
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -159,6 +196,7 @@ describe("Registry Tests", () => {
         bump,
         data,
         address: programInstance.publicKey,
+        proof: { none: {} },
       },
       {
         accounts: {
@@ -183,13 +221,17 @@ describe("Registry Tests", () => {
   it("Cannot add entry again", async () => {
     const data = "https://fkrok";
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -200,6 +242,7 @@ describe("Registry Tests", () => {
           bump,
           data,
           address: programInstance.publicKey,
+          proof: { none: {} },
         },
         {
           accounts: {
@@ -218,13 +261,17 @@ describe("Registry Tests", () => {
 
   it("Cannot verify entry if not authority", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
@@ -246,4 +293,851 @@ describe("Registry Tests", () => {
       assert.equal(e.code, 300);
     }
   });
+
+  it("Add same address to a second registry", async () => {
+    const otherName = "other-registry";
+    const [otherConfig, configBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(otherName),
+      ],
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump: configBump,
+        name: otherName,
+        entrySeed: ENTRY_SEED,
+        permissionless_add: true,
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        challengeStake: new anchor.BN(0),
+        votePeriod: new anchor.BN(60 * 60),
+        voteQuorum: new anchor.BN(0),
+        voterRewardPct: 0,
+        voteMint: null,
+      },
+      {
+        accounts: {
+          registryConfig: otherConfig,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        otherConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const tx = await program.rpc.addEntry(
+      {
+        bump,
+        data: testData,
+        schema_version: 0,
+        address: programInstance.publicKey,
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig: otherConfig,
+          entry: seededPubkey,
+          creator: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(
+      entry.address.toBase58(),
+      programInstance.publicKey.toBase58()
+    );
+    assert.equal(entry.isVerified, false);
+  });
+
+  it("Cannot verify entry through another registry", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const [otherConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode("other-registry"),
+      ],
+      program.programId
+    );
+
+    const [seededPubkey] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    try {
+      await program.rpc.verifyEntry({
+        accounts: {
+          registryConfig: otherConfig,
+          entry: seededPubkey,
+          authority: provider.wallet.publicKey,
+        },
+      });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 146);
+    }
+  });
+
+  it("Hand over authority in two steps", async () => {
+    const [otherConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode("other-registry"),
+      ],
+      program.programId
+    );
+    const newAuthority = web3.Keypair.generate();
+
+    await program.rpc.proposeAuthority({
+      accounts: {
+        registryConfig: otherConfig,
+        authority: provider.wallet.publicKey,
+        newAuthority: newAuthority.publicKey,
+      },
+    });
+    let data = await program.account.registryConfig.fetch(otherConfig);
+    assert.equal(
+      data.pendingAuthority.toBase58(),
+      newAuthority.publicKey.toBase58()
+    );
+
+    await program.rpc.cancelAuthorityTransfer({
+      accounts: {
+        registryConfig: otherConfig,
+        authority: provider.wallet.publicKey,
+      },
+    });
+    data = await program.account.registryConfig.fetch(otherConfig);
+    assert.equal(data.pendingAuthority, null);
+
+    try {
+      await program.rpc.acceptAuthority({
+        accounts: {
+          registryConfig: otherConfig,
+          newAuthority: newAuthority.publicKey,
+        },
+        signers: [newAuthority],
+      });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 301);
+    }
+
+    await program.rpc.setMultisig(
+      { signers: [web3.Keypair.generate().publicKey], threshold: 1 },
+      {
+        accounts: {
+          registryConfig: otherConfig,
+          authority: provider.wallet.publicKey,
+        },
+      }
+    );
+    await program.rpc.proposeAuthority({
+      accounts: {
+        registryConfig: otherConfig,
+        authority: provider.wallet.publicKey,
+        newAuthority: newAuthority.publicKey,
+      },
+    });
+    await program.rpc.acceptAuthority({
+      accounts: {
+        registryConfig: otherConfig,
+        newAuthority: newAuthority.publicKey,
+      },
+      signers: [newAuthority],
+    });
+    data = await program.account.registryConfig.fetch(otherConfig);
+    assert.equal(data.authority.toBase58(), newAuthority.publicKey.toBase58());
+    assert.equal(data.pendingAuthority, null);
+    // the previous authority's multisig does not carry over
+    assert.equal(data.multisigSigners.length, 0);
+    assert.equal(data.multisigThreshold, 0);
+  });
+
+  it("Verify entry with multisig approval", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const [seededPubkey] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const memberA = web3.Keypair.generate();
+    const memberB = web3.Keypair.generate();
+
+    await program.rpc.setMultisig(
+      { signers: [memberA.publicKey, memberB.publicKey], threshold: 2 },
+      {
+        accounts: {
+          registryConfig,
+          authority: provider.wallet.publicKey,
+        },
+      }
+    );
+
+    try {
+      await program.rpc.verifyEntry({
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          authority: memberA.publicKey,
+        },
+        signers: [memberA],
+      });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 300);
+    }
+
+    await program.rpc.verifyEntry({
+      accounts: {
+        registryConfig,
+        entry: seededPubkey,
+        authority: memberA.publicKey,
+      },
+      remainingAccounts: [
+        { pubkey: memberB.publicKey, isSigner: true, isWritable: false },
+      ],
+      signers: [memberA, memberB],
+    });
+    const entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.isVerified, true);
+  });
+
+  it("Register entry with proof of address control", async () => {
+    const proofName = "proof-registry";
+    const [proofConfig, configBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(proofName),
+      ],
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump: configBump,
+        name: proofName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        requireProof: true,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        challengeStake: new anchor.BN(0),
+        votePeriod: new anchor.BN(60 * 60),
+        voteQuorum: new anchor.BN(0),
+        voterRewardPct: 0,
+        voteMint: null,
+      },
+      {
+        accounts: {
+          registryConfig: proofConfig,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        proofConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const accounts = {
+      registryConfig: proofConfig,
+      entry: seededPubkey,
+      creator: provider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    await assert.rejects(
+      async () => {
+        await program.rpc.addEntry(
+          {
+            bump,
+            data: testData,
+            schemaVersion: 0,
+            address: programInstance.publicKey,
+            proof: { none: {} },
+          },
+          { accounts }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 304);
+        return true;
+      }
+    );
+
+    const tx = await program.rpc.addEntry(
+      {
+        bump,
+        data: testData,
+        schemaVersion: 0,
+        address: programInstance.publicKey,
+        proof: { addressSignature: {} },
+      },
+      {
+        accounts,
+        remainingAccounts: [
+          {
+            pubkey: programInstance.publicKey,
+            isSigner: true,
+            isWritable: false,
+          },
+        ],
+        signers: [programInstance],
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const entry = await program.account.entryData.fetch(seededPubkey);
+    assert.deepStrictEqual(entry.proof, { addressSignature: {} });
+  });
+
+  it("Update config and refuse seed changes once entries exist", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const emptyUpdate = {
+      permissionlessAdd: null,
+      entrySeed: null,
+      requireProof: null,
+      maxEntries: null,
+      maxEntrySize: null,
+      moderationTreasury: null,
+      challengeStake: null,
+      votePeriod: null,
+      voteQuorum: null,
+      voterRewardPct: null,
+      voteMint: null,
+    };
+    const accounts = {
+      registryConfig,
+      authority: provider.wallet.publicKey,
+    };
+
+    const tx = await program.rpc.updateConfig(
+      { ...emptyUpdate, permissionlessAdd: false, maxEntries: 100 },
+      { accounts }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.registryConfig.fetch(registryConfig);
+    assert.equal(data.permissionlessAdd, false);
+    assert.equal(data.maxEntries, 100);
+
+    await assert.rejects(
+      async () => {
+        await program.rpc.updateConfig(
+          { ...emptyUpdate, entrySeed: "other-seed" },
+          { accounts }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 305);
+        return true;
+      }
+    );
+  });
+
+  it("Size entries to their payload and reject oversized data", async () => {
+    const sizedName = "sized-registry";
+    const [sizedConfig, configBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+        anchor.utils.bytes.utf8.encode(sizedName),
+      ],
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump: configBump,
+        name: sizedName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 200,
+        moderationTreasury: null,
+        challengeStake: new anchor.BN(0),
+        votePeriod: new anchor.BN(60 * 60),
+        voteQuorum: new anchor.BN(0),
+        voterRewardPct: 0,
+        voteMint: null,
+      },
+      {
+        accounts: {
+          registryConfig: sizedConfig,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const oversized = web3.Keypair.generate().publicKey;
+    const [oversizedEntry, oversizedBump] =
+      await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+          sizedConfig.toBuffer(),
+          oversized.toBuffer(),
+        ],
+        program.programId
+      );
+    await assert.rejects(
+      async () => {
+        await program.rpc.addEntry(
+          {
+            bump: oversizedBump,
+            data: testData.repeat(10),
+            schemaVersion: 0,
+            address: oversized,
+            proof: { none: {} },
+          },
+          {
+            accounts: {
+              registryConfig: sizedConfig,
+              entry: oversizedEntry,
+              creator: provider.wallet.publicKey,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+          }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 307);
+        return true;
+      }
+    );
+
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        sizedConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const tx = await program.rpc.addEntry(
+      {
+        bump,
+        data: testData,
+        schemaVersion: 0,
+        address: programInstance.publicKey,
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig: sizedConfig,
+          entry: seededPubkey,
+          creator: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const account = await provider.connection.getAccountInfo(seededPubkey);
+    // 125 bytes of fixed fields plus the length-prefixed data
+    assert.equal(account.data.length, 125 + 4 + testData.length);
+  });
+
+  it("Route rent of moderated entries to the treasury", async () => {
+    const moderatedName = "moderated-registry";
+    const treasury = web3.Keypair.generate().publicKey;
+    const creator = web3.Keypair.generate();
+    const [moderatedConfig, configBump] =
+      await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+          anchor.utils.bytes.utf8.encode(moderatedName),
+        ],
+        program.programId
+      );
+    await program.rpc.init(
+      {
+        bump: configBump,
+        name: moderatedName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: treasury,
+        challengeStake: new anchor.BN(0),
+        votePeriod: new anchor.BN(60 * 60),
+        voteQuorum: new anchor.BN(0),
+        voterRewardPct: 0,
+        voteMint: null,
+      },
+      {
+        accounts: {
+          registryConfig: moderatedConfig,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        creator.publicKey,
+        web3.LAMPORTS_PER_SOL
+      ),
+      "confirmed"
+    );
+
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        moderatedConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.rpc.addEntry(
+      {
+        bump,
+        data: testData,
+        schemaVersion: 0,
+        address: programInstance.publicKey,
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig: moderatedConfig,
+          entry: seededPubkey,
+          creator: creator.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [creator],
+      }
+    );
+    const entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.rentPayer.toBase58(), creator.publicKey.toBase58());
+
+    const accounts = {
+      registryConfig: moderatedConfig,
+      entry: seededPubkey,
+      authority: provider.wallet.publicKey,
+    };
+    await assert.rejects(
+      async () => {
+        await program.rpc.removeEntry({
+          accounts: { ...accounts, rentRecipient: creator.publicKey },
+        });
+      },
+      (err) => {
+        assert.equal(err.code, 308);
+        return true;
+      }
+    );
+
+    const rent = await provider.connection.getBalance(seededPubkey);
+    const tx = await program.rpc.removeEntry({
+      accounts: { ...accounts, rentRecipient: treasury },
+    });
+    console.log("Your transaction signature", tx);
+    assert.equal(await provider.connection.getBalance(treasury), rent);
+  });
+
+  it("Resolve challenges through a token weighted vote", async () => {
+    const tcrName = "tcr-registry";
+    const stake = web3.LAMPORTS_PER_SOL / 10;
+    const [registryConfig, configBump] =
+      await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG_SEED),
+          anchor.utils.bytes.utf8.encode(tcrName),
+        ],
+        program.programId
+      );
+    const initIx = {
+      bump: configBump,
+      name: tcrName,
+      entrySeed: ENTRY_SEED,
+      permissionlessAdd: true,
+      requireProof: false,
+      maxEntries: 0,
+      maxEntrySize: 0,
+      moderationTreasury: null,
+      challengeStake: new anchor.BN(stake),
+      votePeriod: new anchor.BN(0),
+      voteQuorum: new anchor.BN(0),
+      voterRewardPct: 50,
+      voteMint: null,
+    };
+    const initAccounts = {
+      registryConfig,
+      authority: provider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+    try {
+      await program.rpc.init(initIx, { accounts: initAccounts });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 317);
+    }
+
+    const voteMint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      provider.wallet.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    await program.rpc.init(
+      {
+        ...initIx,
+        votePeriod: new anchor.BN(2),
+        voteMint: voteMint.publicKey,
+      },
+      { accounts: initAccounts }
+    );
+
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.rpc.addEntry(
+      {
+        bump,
+        data: testData,
+        schemaVersion: 0,
+        address: programInstance.publicKey,
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          creator: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const challenger = web3.Keypair.generate();
+    const voter = web3.Keypair.generate();
+    for (const keypair of [challenger, voter]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          web3.LAMPORTS_PER_SOL
+        ),
+        "confirmed"
+      );
+    }
+    const voterTokenAccount = await voteMint.createAccount(voter.publicKey);
+    await voteMint.mintTo(
+      voterTokenAccount,
+      provider.wallet.publicKey,
+      [],
+      100
+    );
+
+    const openChallenge = async (index, signer) => {
+      const [challenge, challengeBump] =
+        await web3.PublicKey.findProgramAddress(
+          [
+            anchor.utils.bytes.utf8.encode("challenge"),
+            seededPubkey.toBuffer(),
+            new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+          ],
+          program.programId
+        );
+      const [vault] = await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode("challenge-vault"),
+          challenge.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.rpc.challengeEntry(
+        { bump: challengeBump },
+        {
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            challenge,
+            vault,
+            voteMint: voteMint.publicKey,
+            challenger: signer.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: web3.SYSVAR_RENT_PUBKEY,
+          },
+          signers: signer === provider.wallet ? [] : [signer],
+        }
+      );
+      return [challenge, vault];
+    };
+    const castVote = async (challenge, vault, weight) => {
+      const [vote, voteBump] = await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode("vote"),
+          challenge.toBuffer(),
+          voter.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+      await program.rpc.vote(
+        { bump: voteBump, support: false, weight: new anchor.BN(weight) },
+        {
+          accounts: {
+            challenge,
+            vote,
+            vault,
+            voterTokenAccount,
+            voter: voter.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          signers: [voter],
+        }
+      );
+      return vote;
+    };
+
+    // the creator challenging their own entry cannot verify it
+    const [selfChallenge, selfVault] = await openChallenge(0, provider.wallet);
+    try {
+      await castVote(selfChallenge, selfVault, 0);
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 318);
+    }
+    await castVote(selfChallenge, selfVault, 10);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    await program.rpc.resolveChallenge({
+      accounts: {
+        registryConfig,
+        entry: seededPubkey,
+        challenge: selfChallenge,
+        vault: selfVault,
+        challenger: provider.wallet.publicKey,
+        entryCreator: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+    let entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.isVerified, false);
+    assert.equal(entry.challenged, false);
+
+    // the entry can be challenged again before the previous votes are claimed
+    const [challenge, vault] = await openChallenge(1, challenger);
+    entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.challenged, true);
+    const vote = await castVote(challenge, vault, 50);
+    assert.equal(
+      (await voteMint.getAccountInfo(voterTokenAccount)).amount.toNumber(),
+      40
+    );
+
+    const resolveAccounts = {
+      registryConfig,
+      entry: seededPubkey,
+      challenge,
+      vault,
+      challenger: challenger.publicKey,
+      entryCreator: provider.wallet.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+    try {
+      await program.rpc.resolveChallenge({ accounts: resolveAccounts });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 312);
+    }
+    try {
+      await program.rpc.removeEntry({
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          authority: provider.wallet.publicKey,
+          rentRecipient: provider.wallet.publicKey,
+        },
+      });
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 309);
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    const tx = await program.rpc.resolveChallenge({
+      accounts: resolveAccounts,
+    });
+    console.log("Your transaction signature", tx);
+    entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.isVerified, true);
+    assert.equal(entry.challenged, false);
+    assert.equal(entry.deposit.toNumber(), stake);
+
+    const voterBalance = await provider.connection.getBalance(voter.publicKey);
+    await program.rpc.claimVote({
+      accounts: {
+        challenge,
+        vote,
+        vault,
+        voterTokenAccount,
+        voter: voter.publicKey,
+        challenger: challenger.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+    const voteRent =
+      await provider.connection.getMinimumBalanceForRentExemption(
+        8 + 1 + 32 + 32 + 32 + 1 + 8
+      );
+    assert.equal(
+      await provider.connection.getBalance(voter.publicKey),
+      voterBalance + stake / 2 + voteRent
+    );
+    assert.equal(
+      (await voteMint.getAccountInfo(voterTokenAccount)).amount.toNumber(),
+      90
+    );
+    assert.equal(await provider.connection.getBalance(challenge), 0);
+    assert.equal(await provider.connection.getBalance(vault), 0);
+  });
 });
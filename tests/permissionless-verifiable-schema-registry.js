@@ -2,10 +2,89 @@ const anchor = require("@project-serum/anchor");
 const web3 = require("@solana/web3.js");
 const assert = require("assert");
 const borsh = require("borsh");
+const { Token, TOKEN_PROGRAM_ID } = require("@solana/spl-token");
 
 const REGISTRY_CONFIG = "registry-config";
 const SCHEMA_SEED = "schema";
+const REGISTRY_NAME = "test-registry";
 const ENTRY_SEED = "entry-seed";
+const ROLE_SEED = "role";
+const TREASURY_SEED = "treasury";
+const FEE_VAULT_SEED = "fee-vault";
+const BOND_SEED = "bond";
+const ATTESTATION_SEED = "attestation";
+const BADGES_SEED = "badges";
+const VERIFIER_ROLE = 1;
+const MODERATOR_ROLE = 2;
+const ADD_FEE = web3.LAMPORTS_PER_SOL / 100;
+
+const findRoleAddress = (registryConfig, role, member, programId) =>
+  web3.PublicKey.findProgramAddress(
+    [
+      anchor.utils.bytes.utf8.encode(ROLE_SEED),
+      registryConfig.toBuffer(),
+      Buffer.from([role]),
+      member.toBuffer(),
+    ],
+    programId
+  );
+
+const findTreasuryAddress = (registryConfig, programId) =>
+  web3.PublicKey.findProgramAddress(
+    [anchor.utils.bytes.utf8.encode(TREASURY_SEED), registryConfig.toBuffer()],
+    programId
+  );
+
+const findBondAddress = (entry, programId) =>
+  web3.PublicKey.findProgramAddress(
+    [anchor.utils.bytes.utf8.encode(BOND_SEED), entry.toBuffer()],
+    programId
+  );
+
+const findAttestationAddress = (entry, verifier, badge, programId) =>
+  web3.PublicKey.findProgramAddress(
+    [
+      anchor.utils.bytes.utf8.encode(ATTESTATION_SEED),
+      entry.toBuffer(),
+      verifier.toBuffer(),
+      // the legacy badge 0 is left out of the seeds
+      Buffer.from(badge === 0 ? [] : [badge]),
+    ],
+    programId
+  );
+
+const findBadgesAddress = (registryConfig, programId) =>
+  web3.PublicKey.findProgramAddress(
+    [anchor.utils.bytes.utf8.encode(BADGES_SEED), registryConfig.toBuffer()],
+    programId
+  );
+
+const defineBadges = async (program, registryConfig, names) => {
+  const [badgeDefinitions, bump] = await findBadgesAddress(
+    registryConfig,
+    program.programId
+  );
+  const authority = program.provider.wallet.publicKey;
+  await program.rpc.initBadges(
+    { bump },
+    {
+      accounts: {
+        registryConfig,
+        badgeDefinitions,
+        authority,
+        payer: authority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    }
+  );
+  for (const name of names) {
+    await program.rpc.defineBadge(
+      { name },
+      { accounts: { registryConfig, badgeDefinitions, authority } }
+    );
+  }
+  return badgeDefinitions;
+};
 
 class BorshTokenData {
   token_symbol = "";
@@ -57,15 +136,39 @@ describe("Registry Tests", () => {
 
   it("Initializes the registry", async () => {
     const [registryConfig, bump] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const [treasury, treasuryBump] = await findTreasuryAddress(
+      registryConfig,
       program.programId
     );
 
     const tx = await program.rpc.init(
-      { bump, entrySeed: ENTRY_SEED, permissionless_add: true },
+      {
+        bump,
+        name: REGISTRY_NAME,
+        entrySeed: ENTRY_SEED,
+        permissionless_add: true,
+        addFee: new anchor.BN(ADD_FEE),
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        treasuryBump,
+        treasurer: null,
+        bondAmount: new anchor.BN(0),
+        bondLockPeriod: new anchor.BN(0),
+        verificationThreshold: 0,
+        verificationTtl: new anchor.BN(0),
+      },
       {
         accounts: {
           registryConfig,
+          treasury,
           authority: provider.wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
@@ -83,17 +186,25 @@ describe("Registry Tests", () => {
 
   it("Add entry", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
+    const [treasury] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
 
     const tx = await program.rpc.addEntry(
       {
@@ -101,13 +212,14 @@ describe("Registry Tests", () => {
         data: serializedTokenData,
         schemaVersion: 0,
         primaryKey: programInstance.publicKey.toBytes(),
+        proof: { none: {} },
       },
       {
         accounts: {
           registryConfig,
           entry: seededPubkey,
           creator: provider.wallet.publicKey,
-          authority: provider.wallet.publicKey,
+          treasury,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
       }
@@ -127,29 +239,110 @@ describe("Registry Tests", () => {
       new web3.PublicKey(entry.primaryKey).toBase58(),
       programInstance.publicKey.toBase58()
     );
-    assert.equal(entry.isVerified, false);
+    assert.equal(entry.badges, 0);
+  });
+
+  it("Grant verifier role", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const [roleMember, bump] = await findRoleAddress(
+      registryConfig,
+      VERIFIER_ROLE,
+      provider.wallet.publicKey,
+      program.programId
+    );
+
+    const tx = await program.rpc.grantRole(
+      { bump, role: { verifier: {} } },
+      {
+        accounts: {
+          registryConfig,
+          roleMember,
+          member: provider.wallet.publicKey,
+          admin: provider.wallet.publicKey,
+          adminRole: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const role = await program.account.roleMember.fetch(roleMember);
+    assert.equal(role.member.toBase58(), provider.wallet.publicKey.toBase58());
+    assert.deepStrictEqual(role.role, { verifier: {} });
+  });
+
+  it("Define verification badges", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const badgeDefinitions = await defineBadges(program, registryConfig, [
+      "audited",
+      "community",
+    ]);
+    const badges = await program.account.badgeDefinitions.fetch(
+      badgeDefinitions
+    );
+    assert.deepStrictEqual(badges.names, ["audited", "community"]);
   });
 
   it("Verify an entry", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
-    const tx = await program.rpc.verifyEntry({
-      accounts: {
-        registryConfig,
-        entry: seededPubkey,
-        authority: provider.wallet.publicKey,
-      },
-    });
+    const [verifierRole] = await findRoleAddress(
+      registryConfig,
+      VERIFIER_ROLE,
+      provider.wallet.publicKey,
+      program.programId
+    );
+    const [attestation, attestationBump] = await findAttestationAddress(
+      seededPubkey,
+      provider.wallet.publicKey,
+      0,
+      program.programId
+    );
+    const [badgeDefinitions] = await findBadgesAddress(
+      registryConfig,
+      program.programId
+    );
+    const tx = await program.rpc.verifyEntry(
+      { bump: attestationBump, badge: 0 },
+      {
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          badgeDefinitions,
+          attestation,
+          verifier: provider.wallet.publicKey,
+          verifierRole,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
     console.log("Your transaction signature", tx);
     const entry = await program.account.entryData.fetch(seededPubkey);
     console.log("Found data: ", entry);
@@ -165,30 +358,155 @@ describe("Registry Tests", () => {
       new web3.PublicKey(entry.primaryKey).toBase58(),
       programInstance.publicKey.toBase58()
     );
-    assert.equal(entry.isVerified, true);
+    assert.equal(entry.badges, 1);
+    assert.equal(
+      entry.badgeGrants[0].grantedBy.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
   });
 
-  it("Remove an entry", async () => {
+  it("Grant and revoke individual badges", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
-
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
-    const tx = await program.rpc.removeEntry({
+    const [verifierRole] = await findRoleAddress(
+      registryConfig,
+      VERIFIER_ROLE,
+      provider.wallet.publicKey,
+      program.programId
+    );
+    const [badgeDefinitions] = await findBadgesAddress(
+      registryConfig,
+      program.programId
+    );
+    const verify = async (badge) => {
+      const [attestation, bump] = await findAttestationAddress(
+        seededPubkey,
+        provider.wallet.publicKey,
+        badge,
+        program.programId
+      );
+      await program.rpc.verifyEntry(
+        { bump, badge },
+        {
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            badgeDefinitions,
+            attestation,
+            verifier: provider.wallet.publicKey,
+            verifierRole,
+            payer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+        }
+      );
+      return attestation;
+    };
+
+    await assert.rejects(
+      async () => {
+        await verify(2);
+      },
+      (err) => {
+        assert.equal(err.code, 314);
+        return true;
+      }
+    );
+
+    await verify(1);
+    let entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.badges, 3);
+    assert.deepStrictEqual(
+      entry.badgeGrants.map((grant) => grant.badge),
+      [0, 1]
+    );
+
+    const [attestation] = await findAttestationAddress(
+      seededPubkey,
+      provider.wallet.publicKey,
+      0,
+      program.programId
+    );
+    const tx = await program.rpc.unverifyEntry({
       accounts: {
         registryConfig,
         entry: seededPubkey,
-        authority: provider.wallet.publicKey,
+        attestation,
+        verifier: provider.wallet.publicKey,
+        rentPayer: provider.wallet.publicKey,
       },
     });
     console.log("Your transaction signature", tx);
+    entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.badges, 2);
+    assert.equal(entry.badgeGrants.length, 1);
+    assert.equal(entry.badgeGrants[0].badge, 1);
+  });
+
+  it("Remove an entry", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+
+    const [seededPubkey] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const [moderatorRole] = await findRoleAddress(
+      registryConfig,
+      MODERATOR_ROLE,
+      provider.wallet.publicKey,
+      program.programId
+    );
+    const [bondVault] = await findBondAddress(seededPubkey, program.programId);
+    const [treasury] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
+    const accounts = {
+      registryConfig,
+      entry: seededPubkey,
+      authority: provider.wallet.publicKey,
+      rentRecipient: provider.wallet.publicKey,
+      moderatorRole,
+      bondVault,
+      bondDepositor: provider.wallet.publicKey,
+      treasury,
+    };
+    const [attestation] = await findAttestationAddress(
+      seededPubkey,
+      provider.wallet.publicKey,
+      1,
+      program.programId
+    );
+    const tx = await program.rpc.removeEntry(
+      { slashBond: false },
+      { accounts }
+    );
+    console.log("Your transaction signature", tx);
+    // the attestation outlives the entry but won't count towards a re-created one
+    assert.notEqual(await provider.connection.getBalance(attestation), 0);
     try {
       await program.account.entryData.fetch(seededPubkey);
       throw Error("Expected to get an error");
@@ -199,17 +517,25 @@ describe("Registry Tests", () => {
 
   it("Add entry back", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
+    const [treasury] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
 
     const tx = await program.rpc.addEntry(
       {
@@ -217,13 +543,14 @@ describe("Registry Tests", () => {
         data: serializedTokenData,
         schemaVersion: 0,
         primaryKey: programInstance.publicKey.toBytes(),
+        proof: { none: {} },
       },
       {
         accounts: {
           registryConfig,
           entry: seededPubkey,
           creator: provider.wallet.publicKey,
-          authority: provider.wallet.publicKey,
+          treasury,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
       }
@@ -243,23 +570,31 @@ describe("Registry Tests", () => {
       new web3.PublicKey(entry.primaryKey).toBase58(),
       programInstance.publicKey.toBase58()
     );
-    assert.equal(entry.isVerified, false);
+    assert.equal(entry.badges, 0);
   });
 
   it("Cannot add entry again", async () => {
     const data = "https://fkrok";
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
+    const [treasury] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
     try {
       const tx = await program.rpc.addEntry(
         {
@@ -267,13 +602,14 @@ describe("Registry Tests", () => {
           data: serializedTokenData,
           schemaVersion: 0,
           primaryKey: programInstance.publicKey.toBytes(),
+          proof: { none: {} },
         },
         {
           accounts: {
             registryConfig,
             entry: seededPubkey,
             creator: provider.wallet.publicKey,
-            authority: provider.wallet.publicKey,
+            treasury,
             systemProgram: anchor.web3.SystemProgram.programId,
           },
         }
@@ -286,43 +622,700 @@ describe("Registry Tests", () => {
 
   it("Cannot verify entry if not authority", async () => {
     const [registryConfig] = await web3.PublicKey.findProgramAddress(
-      [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
       program.programId
     );
 
     const [seededPubkey] = await web3.PublicKey.findProgramAddress(
       [
         anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
         programInstance.publicKey.toBuffer(),
       ],
       program.programId
     );
 
     const nonAuthority = web3.Keypair.generate();
+    const [verifierRole] = await findRoleAddress(
+      registryConfig,
+      VERIFIER_ROLE,
+      nonAuthority.publicKey,
+      program.programId
+    );
+    const [attestation, attestationBump] = await findAttestationAddress(
+      seededPubkey,
+      nonAuthority.publicKey,
+      0,
+      program.programId
+    );
+    const [badgeDefinitions] = await findBadgesAddress(
+      registryConfig,
+      program.programId
+    );
+
+    try {
+      const tx = await program.rpc.verifyEntry(
+        { bump: attestationBump, badge: 0 },
+        {
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            badgeDefinitions,
+            attestation,
+            verifier: nonAuthority.publicKey,
+            verifierRole,
+            payer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          signers: [nonAuthority],
+        }
+      );
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 300);
+    }
+  });
+
+  it("Withdraw listing fees from the treasury", async () => {
+    const [registryConfig] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    const [treasury] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
+    const config = await program.account.registryConfig.fetch(registryConfig);
+    assert.equal(config.feesCollected.toNumber(), 2 * ADD_FEE);
+
+    const destination = web3.Keypair.generate().publicKey;
+    const nonAuthority = web3.Keypair.generate();
+    await assert.rejects(
+      async () => {
+        await program.rpc.withdrawFees(
+          { amount: new anchor.BN(ADD_FEE) },
+          {
+            accounts: {
+              registryConfig,
+              treasury,
+              authority: nonAuthority.publicKey,
+              destination,
+            },
+            signers: [nonAuthority],
+          }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 300);
+        return true;
+      }
+    );
+    await assert.rejects(
+      async () => {
+        await program.rpc.withdrawFees(
+          { amount: new anchor.BN(3 * ADD_FEE) },
+          {
+            accounts: {
+              registryConfig,
+              treasury,
+              authority: provider.wallet.publicKey,
+              destination,
+            },
+          }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 310);
+        return true;
+      }
+    );
+
+    const tx = await program.rpc.withdrawFees(
+      { amount: new anchor.BN(2 * ADD_FEE) },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          authority: provider.wallet.publicKey,
+          destination,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    assert.equal(
+      await provider.connection.getBalance(destination),
+      2 * ADD_FEE
+    );
+  });
+
+  it("Collect and withdraw SPL token listing fees", async () => {
+    const tokenRegistryName = "token-fee-registry";
+    const tokenFee = 25;
+    const [registryConfig, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(tokenRegistryName),
+      ],
+      program.programId
+    );
+    const [treasury, treasuryBump] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump,
+        name: tokenRegistryName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        addFee: new anchor.BN(0),
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        treasuryBump,
+        treasurer: null,
+        bondAmount: new anchor.BN(0),
+        bondLockPeriod: new anchor.BN(0),
+        verificationThreshold: 0,
+        verificationTtl: new anchor.BN(0),
+      },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const feeMint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      provider.wallet.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const creatorTokenAccount = await feeMint.createAccount(
+      provider.wallet.publicKey
+    );
+    await feeMint.mintTo(
+      creatorTokenAccount,
+      provider.wallet.publicKey,
+      [],
+      100
+    );
+    const [feeVault, vaultBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(FEE_VAULT_SEED),
+        registryConfig.toBuffer(),
+        feeMint.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.rpc.initFeeVault(
+      { vaultBump, tokenFee: new anchor.BN(tokenFee) },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          feeVault,
+          feeMint: feeMint.publicKey,
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: web3.SYSVAR_RENT_PUBKEY,
+        },
+      }
+    );
 
+    const [seededPubkey, entryBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.rpc.addEntry(
+      {
+        bump: entryBump,
+        data: serializedTokenData,
+        schemaVersion: 0,
+        primaryKey: programInstance.publicKey.toBytes(),
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          creator: provider.wallet.publicKey,
+          treasury,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        // the token fee accounts are only passed when the registry charges a token fee
+        remainingAccounts: [
+          { pubkey: creatorTokenAccount, isWritable: true, isSigner: false },
+          { pubkey: feeVault, isWritable: true, isSigner: false },
+          { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+        ],
+      }
+    );
+    const config = await program.account.registryConfig.fetch(registryConfig);
+    assert.equal(config.feeMint.toBase58(), feeMint.publicKey.toBase58());
+    assert.equal(config.tokenFeesCollected.toNumber(), tokenFee);
+    assert.equal(
+      (await feeMint.getAccountInfo(feeVault)).amount.toNumber(),
+      tokenFee
+    );
+
+    const destination = await feeMint.createAccount(provider.wallet.publicKey);
+    const tx = await program.rpc.withdrawTokenFees(
+      { amount: new anchor.BN(tokenFee) },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          feeVault,
+          authority: provider.wallet.publicKey,
+          destination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    assert.equal(
+      (await feeMint.getAccountInfo(destination)).amount.toNumber(),
+      tokenFee
+    );
+    assert.equal(
+      (await feeMint.getAccountInfo(creatorTokenAccount)).amount.toNumber(),
+      100 - tokenFee
+    );
+
+    const otherMint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      provider.wallet.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const [otherVault, otherVaultBump] =
+      await web3.PublicKey.findProgramAddress(
+        [
+          anchor.utils.bytes.utf8.encode(FEE_VAULT_SEED),
+          registryConfig.toBuffer(),
+          otherMint.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
     try {
-      const tx = await program.rpc.verifyEntry({
+      await program.rpc.initFeeVault(
+        { vaultBump: otherVaultBump, tokenFee: new anchor.BN(tokenFee) },
+        {
+          accounts: {
+            registryConfig,
+            treasury,
+            feeVault: otherVault,
+            feeMint: otherMint.publicKey,
+            authority: provider.wallet.publicKey,
+            payer: provider.wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: web3.SYSVAR_RENT_PUBKEY,
+          },
+        }
+      );
+      throw Error("Expected to get an error");
+    } catch (e) {
+      assert.equal(e.code, 318);
+    }
+  });
+
+  it("Escrow a bond and slash it on moderated removal", async () => {
+    const bondRegistryName = "bond-registry";
+    const bondAmount = web3.LAMPORTS_PER_SOL / 10;
+    const [registryConfig, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(bondRegistryName),
+      ],
+      program.programId
+    );
+    const [treasury, treasuryBump] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump,
+        name: bondRegistryName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        addFee: new anchor.BN(0),
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        treasuryBump,
+        treasurer: null,
+        bondAmount: new anchor.BN(bondAmount),
+        bondLockPeriod: new anchor.BN(60 * 60),
+        verificationThreshold: 0,
+        verificationTtl: new anchor.BN(0),
+      },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const [seededPubkey, entryBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const [bondVault] = await findBondAddress(seededPubkey, program.programId);
+    // lamports sent to the vault address ahead of time must not block the bond
+    await provider.send(
+      new web3.Transaction().add(
+        web3.SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: bondVault,
+          lamports: web3.LAMPORTS_PER_SOL / 100,
+        })
+      )
+    );
+    await program.rpc.addEntry(
+      {
+        bump: entryBump,
+        data: serializedTokenData,
+        schemaVersion: 0,
+        primaryKey: programInstance.publicKey.toBytes(),
+        proof: { none: {} },
+      },
+      {
         accounts: {
           registryConfig,
           entry: seededPubkey,
-          authority: nonAuthority.publicKey,
+          creator: provider.wallet.publicKey,
+          treasury,
+          systemProgram: anchor.web3.SystemProgram.programId,
         },
-        signers: [nonAuthority],
-      });
+        remainingAccounts: [
+          { pubkey: bondVault, isWritable: true, isSigner: false },
+        ],
+      }
+    );
+    const bond = await program.account.bondVault.fetch(bondVault);
+    assert.equal(
+      bond.depositor.toBase58(),
+      provider.wallet.publicKey.toBase58()
+    );
+    assert.equal(bond.amount.toNumber(), bondAmount);
+
+    await assert.rejects(
+      async () => {
+        await program.rpc.reclaimBond({
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            bondVault,
+            depositor: provider.wallet.publicKey,
+          },
+        });
+      },
+      (err) => {
+        assert.equal(err.code, 313);
+        return true;
+      }
+    );
+
+    const moderator = web3.Keypair.generate();
+    const [moderatorRole, roleBump] = await findRoleAddress(
+      registryConfig,
+      MODERATOR_ROLE,
+      moderator.publicKey,
+      program.programId
+    );
+    await program.rpc.grantRole(
+      { bump: roleBump, role: { moderator: {} } },
+      {
+        accounts: {
+          registryConfig,
+          roleMember: moderatorRole,
+          member: moderator.publicKey,
+          admin: provider.wallet.publicKey,
+          adminRole: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const treasuryBalance = await provider.connection.getBalance(treasury);
+    const tx = await program.rpc.removeEntry(
+      { slashBond: true },
+      {
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          authority: moderator.publicKey,
+          rentRecipient: provider.wallet.publicKey,
+          moderatorRole,
+          bondVault,
+          bondDepositor: provider.wallet.publicKey,
+          treasury,
+        },
+        signers: [moderator],
+      }
+    );
+    console.log("Your transaction signature", tx);
+    assert.equal(
+      await provider.connection.getBalance(treasury),
+      treasuryBalance + bondAmount
+    );
+    assert.equal(await provider.connection.getBalance(bondVault), 0);
+    const config = await program.account.registryConfig.fetch(registryConfig);
+    assert.equal(config.bondsSlashed.toNumber(), bondAmount);
+  });
+
+  it("Verify entry once the attestation threshold is met", async () => {
+    const attestedRegistryName = "attested-registry";
+    const [registryConfig, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(attestedRegistryName),
+      ],
+      program.programId
+    );
+    const [treasury, treasuryBump] = await findTreasuryAddress(
+      registryConfig,
+      program.programId
+    );
+    await program.rpc.init(
+      {
+        bump,
+        name: attestedRegistryName,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        addFee: new anchor.BN(0),
+        requireProof: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+        treasuryBump,
+        treasurer: null,
+        bondAmount: new anchor.BN(0),
+        bondLockPeriod: new anchor.BN(0),
+        verificationThreshold: 2,
+        verificationTtl: new anchor.BN(3600),
+      },
+      {
+        accounts: {
+          registryConfig,
+          treasury,
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const [seededPubkey, entryBump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.rpc.addEntry(
+      {
+        bump: entryBump,
+        data: serializedTokenData,
+        schemaVersion: 0,
+        primaryKey: programInstance.publicKey.toBytes(),
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig,
+          entry: seededPubkey,
+          creator: provider.wallet.publicKey,
+          treasury,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+
+    const badgeDefinitions = await defineBadges(program, registryConfig, [
+      "audited",
+    ]);
+    const verifiers = [web3.Keypair.generate(), web3.Keypair.generate()];
+    const attestations = [];
+    const verifierRoles = [];
+    for (const verifier of verifiers) {
+      const [verifierRole, roleBump] = await findRoleAddress(
+        registryConfig,
+        VERIFIER_ROLE,
+        verifier.publicKey,
+        program.programId
+      );
+      await program.rpc.grantRole(
+        { bump: roleBump, role: { verifier: {} } },
+        {
+          accounts: {
+            registryConfig,
+            roleMember: verifierRole,
+            member: verifier.publicKey,
+            admin: provider.wallet.publicKey,
+            adminRole: provider.wallet.publicKey,
+            payer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+        }
+      );
+      const [attestation, attestationBump] = await findAttestationAddress(
+        seededPubkey,
+        verifier.publicKey,
+        0,
+        program.programId
+      );
+      await program.rpc.verifyEntry(
+        { bump: attestationBump, badge: 0 },
+        {
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            badgeDefinitions,
+            attestation,
+            verifier: verifier.publicKey,
+            verifierRole,
+            payer: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          signers: [verifier],
+        }
+      );
+      attestations.push(attestation);
+      verifierRoles.push(verifierRole);
+      const entry = await program.account.entryData.fetch(seededPubkey);
+      assert.equal(entry.attestationCount, attestations.length);
+      assert.equal(entry.badges, attestations.length == 2 ? 1 : 0);
+    }
+
+    let entry = await program.account.entryData.fetch(seededPubkey);
+    const [grant] = entry.badgeGrants;
+    assert.equal(grant.expiresAt.toNumber(), grant.grantedAt.toNumber() + 3600);
+
+    const renew = (count) =>
+      program.rpc.renewVerification(
+        { badge: 0 },
+        {
+          accounts: {
+            registryConfig,
+            entry: seededPubkey,
+            attestation: attestations[0],
+            verifier: verifiers[0].publicKey,
+            verifierRole: verifierRoles[0],
+          },
+          remainingAccounts: verifiers
+            .slice(1, count)
+            .flatMap((verifier, i) => [
+              {
+                pubkey: attestations[i + 1],
+                isWritable: false,
+                isSigner: false,
+              },
+              { pubkey: verifier.publicKey, isWritable: false, isSigner: true },
+              {
+                pubkey: verifierRoles[i + 1],
+                isWritable: false,
+                isSigner: false,
+              },
+            ]),
+          signers: verifiers.slice(0, count),
+        }
+      );
+    try {
+      await renew(1);
       throw Error("Expected to get an error");
     } catch (e) {
-      assert.equal(e.code, 300);
+      assert.equal(e.code, 321);
     }
+    await renew(2);
+    entry = await program.account.entryData.fetch(seededPubkey);
+    assert.ok(
+      entry.badgeGrants[0].expiresAt.toNumber() >= grant.expiresAt.toNumber()
+    );
+
+    const assertVerified = (badges) =>
+      program.rpc.assertVerified(
+        { badges },
+        { accounts: { registryConfig, entry: seededPubkey } }
+      );
+    await assertVerified(1);
+    await assert.rejects(
+      async () => {
+        await assertVerified(2);
+      },
+      (err) => {
+        assert.equal(err.code, 316);
+        return true;
+      }
+    );
+
+    const tx = await program.rpc.unverifyEntry({
+      accounts: {
+        registryConfig,
+        entry: seededPubkey,
+        attestation: attestations[1],
+        verifier: verifiers[1].publicKey,
+        rentPayer: provider.wallet.publicKey,
+      },
+      signers: [verifiers[1]],
+    });
+    console.log("Your transaction signature", tx);
+    entry = await program.account.entryData.fetch(seededPubkey);
+    assert.equal(entry.attestationCount, 1);
+    assert.equal(entry.badges, 0);
+    assert.equal(entry.badgeGrants[0].expiresAt, null);
+    assert.equal(entry.badgeGrants[0].attestations, 1);
+    assert.equal(await provider.connection.getBalance(attestations[1]), 0);
   });
 
   // it("Add schema for entry", async () => {
   //   const [registryConfig] = await web3.PublicKey.findProgramAddress(
-  //     [anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG)],
+  //     [
+  //       anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+  //       anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+  //     ],
   //     program.programId
   //   );
 
   //   const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
-  //     [anchor.utils.bytes.utf8.encode(SCHEMA_SEED), [1]],
+  //     [
+  //       anchor.utils.bytes.utf8.encode(SCHEMA_SEED),
+  //       registryConfig.toBuffer(),
+  //       [1],
+  //     ],
   //     program.programId
   //   );
 
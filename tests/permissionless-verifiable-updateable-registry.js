@@ -0,0 +1,249 @@
+const anchor = require("@project-serum/anchor");
+const web3 = require("@solana/web3.js");
+const assert = require("assert");
+
+const REGISTRY_CONFIG = "registry-config";
+const REGISTRY_NAME = "updateable-registry";
+const ENTRY_SEED = "entry";
+const DELEGATE_SEED = "delegate";
+
+describe("Permissionless Verifiable Updateable Registry Tests", () => {
+  const provider = anchor.Provider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.PermissionlessVerifiableUpdateableRegistry;
+  const payer = provider.wallet.publicKey;
+  const programInstance = web3.Keypair.generate();
+  let registryConfig;
+  let entry;
+
+  const updateAccounts = (creator) => ({
+    registryConfig,
+    entry,
+    creator,
+    rentPayer: payer,
+    entryDelegate: anchor.web3.SystemProgram.programId,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  it("Initializes the registry", async () => {
+    const [config, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(REGISTRY_CONFIG),
+        anchor.utils.bytes.utf8.encode(REGISTRY_NAME),
+      ],
+      program.programId
+    );
+    registryConfig = config;
+
+    const tx = await program.rpc.init(
+      {
+        bump,
+        name: REGISTRY_NAME,
+        entrySeed: ENTRY_SEED,
+        permissionlessAdd: true,
+        requireProof: false,
+        requireExecutable: false,
+        authorityCanUpdate: false,
+        maxEntries: 0,
+        maxEntrySize: 0,
+        moderationTreasury: null,
+      },
+      {
+        accounts: {
+          registryConfig,
+          authority: payer,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.registryConfig.fetch(registryConfig);
+    assert.equal(data.authority.toBase58(), payer.toBase58());
+    assert.equal(data.requireExecutable, false);
+  });
+
+  it("Add entry", async () => {
+    const [seededPubkey, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(ENTRY_SEED),
+        registryConfig.toBuffer(),
+        programInstance.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    entry = seededPubkey;
+
+    const tx = await program.rpc.addEntry(
+      {
+        bump,
+        address: programInstance.publicKey,
+        schemaVersion: 0,
+        data: "entry data",
+        proof: { none: {} },
+      },
+      {
+        accounts: {
+          registryConfig,
+          entry,
+          creator: payer,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      }
+    );
+    console.log("Your transaction signature", tx);
+    const data = await program.account.entryData.fetch(entry);
+    assert.equal(data.data, "entry data");
+    assert.equal(data.updateAuthority.toBase58(), payer.toBase58());
+    assert.equal(data.isVerified, false);
+  });
+
+  it("Verify an entry that is not a program", async () => {
+    const tx = await program.rpc.verifyEntry({
+      accounts: {
+        registryConfig,
+        entry,
+        authority: payer,
+        // only checked when the registry requires program entries
+        program: programInstance.publicKey,
+        programData: programInstance.publicKey,
+      },
+    });
+    console.log("Your transaction signature", tx);
+    const data = await program.account.entryData.fetch(entry);
+    assert.equal(data.isVerified, true);
+    assert.equal(data.verifiedDeploySlot, null);
+    assert.notEqual(data.verifiedHash, null);
+  });
+
+  it("Update keeps verification only while content is unchanged", async () => {
+    await program.rpc.updateEntry(
+      { schemaVersion: 0, data: "entry data" },
+      { accounts: updateAccounts(payer) }
+    );
+    let data = await program.account.entryData.fetch(entry);
+    assert.equal(data.isVerified, true);
+
+    const tx = await program.rpc.updateEntry(
+      { schemaVersion: 0, data: "swapped after review" },
+      { accounts: updateAccounts(payer) }
+    );
+    console.log("Your transaction signature", tx);
+    data = await program.account.entryData.fetch(entry);
+    assert.equal(data.isVerified, false);
+    assert.equal(data.verifiedHash, null);
+    assert.equal(data.data, "swapped after review");
+  });
+
+  it("Cannot update entry without update authority", async () => {
+    const stranger = web3.Keypair.generate();
+    await assert.rejects(
+      async () => {
+        await program.rpc.updateEntry(
+          { schemaVersion: 0, data: "squatted" },
+          { accounts: updateAccounts(stranger.publicKey), signers: [stranger] }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 300);
+        return true;
+      }
+    );
+  });
+
+  it("Hand the update authority to another key", async () => {
+    const tx = await program.rpc.setUpdateAuthority({
+      accounts: {
+        registryConfig,
+        entry,
+        updateAuthority: payer,
+        newUpdateAuthority: programInstance.publicKey,
+      },
+    });
+    console.log("Your transaction signature", tx);
+    const data = await program.account.entryData.fetch(entry);
+    assert.equal(
+      data.updateAuthority.toBase58(),
+      programInstance.publicKey.toBase58()
+    );
+    assert.equal(data.creator.toBase58(), payer.toBase58());
+
+    await assert.rejects(
+      async () => {
+        await program.rpc.updateEntry(
+          { schemaVersion: 0, data: "entry data" },
+          { accounts: updateAccounts(payer) }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 300);
+        return true;
+      }
+    );
+  });
+
+  it("Delegate can update entry until it is removed", async () => {
+    const [entryDelegate, bump] = await web3.PublicKey.findProgramAddress(
+      [
+        anchor.utils.bytes.utf8.encode(DELEGATE_SEED),
+        entry.toBuffer(),
+        payer.toBuffer(),
+      ],
+      program.programId
+    );
+    // the entry update authority pays for the delegate record
+    await provider.send(
+      new web3.Transaction().add(
+        web3.SystemProgram.transfer({
+          fromPubkey: payer,
+          toPubkey: programInstance.publicKey,
+          lamports: web3.LAMPORTS_PER_SOL / 10,
+        })
+      )
+    );
+    await program.rpc.addDelegate(
+      { bump, scope: { edit: {} }, expiresAt: null },
+      {
+        accounts: {
+          registryConfig,
+          entry,
+          entryDelegate,
+          delegate: payer,
+          updateAuthority: programInstance.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [programInstance],
+      }
+    );
+    const accounts = { ...updateAccounts(payer), entryDelegate };
+    const tx = await program.rpc.updateEntry(
+      { schemaVersion: 0, data: "maintained by a delegate" },
+      { accounts }
+    );
+    console.log("Your transaction signature", tx);
+    let data = await program.account.entryData.fetch(entry);
+    assert.equal(data.data, "maintained by a delegate");
+
+    await program.rpc.removeDelegate({
+      accounts: {
+        registryConfig,
+        entry,
+        entryDelegate,
+        updateAuthority: programInstance.publicKey,
+      },
+      signers: [programInstance],
+    });
+    assert.equal(await provider.connection.getAccountInfo(entryDelegate), null);
+    await assert.rejects(
+      async () => {
+        await program.rpc.updateEntry(
+          { schemaVersion: 0, data: "entry data" },
+          { accounts }
+        );
+      },
+      (err) => {
+        assert.equal(err.code, 300);
+        return true;
+      }
+    );
+  });
+});